@@ -0,0 +1,191 @@
+use crate::result::{Warning, WarningKind};
+use std::fs;
+use std::path::Path;
+
+/// Numbers that only make sense with Solana's ~400ms slots, when they appear
+/// on a line that talks about slots: milliseconds per slot, seconds per slot
+/// and slots per second.
+const SLOT_TIMING_NUMBERS: &[&str] = &["400", "0.4", "2.5"];
+
+/// Solana's slots per epoch, which is worth flagging wherever it appears.
+const SLOTS_PER_EPOCH: &str = "432000";
+
+/// Solana SDK constants that bake in the same timing.
+const SLOT_TIMING_CONSTANTS: &[&str] = &["DEFAULT_MS_PER_SLOT", "DEFAULT_SLOTS_PER_EPOCH"];
+
+/// Rent-exempt balances on Solana for common accounts: an empty account,
+/// an SPL token account and an SPL mint.
+const RENT_EXEMPT_BALANCES: &[&str] = &["890880", "2039280", "1461600"];
+
+/// Solana's rent per byte-year, and per byte once the two-year exemption
+/// threshold is applied, when they appear on a line about rent or lamports.
+const RENT_RATES: &[&str] = &["3480", "6960"];
+
+/// Ways to use Solana's rent parameters instead of the network's.
+const RENT_CONSTANTS: &[&str] = &[
+    "DEFAULT_LAMPORTS_PER_BYTE_YEAR",
+    "DEFAULT_EXEMPTION_THRESHOLD",
+    "Rent::default()",
+];
+
+/// Number literals on a line, without digit separators or type suffixes, so
+/// `432_000u64` reads as `432000`.
+fn numbers(line: &str) -> Vec<String> {
+    line.split(|c: char| !c.is_ascii_alphanumeric() && c != '.' && c != '_')
+        .filter(|word| word.starts_with(|c: char| c.is_ascii_digit()))
+        .map(|word| {
+            word.replace('_', "")
+                .chars()
+                .take_while(|c| c.is_ascii_digit() || *c == '.')
+                .collect::<String>()
+                .trim_end_matches('.')
+                .to_string()
+        })
+        .collect()
+}
+
+/// The Solana slot timing `line` relies on, if any.
+fn slot_timing_assumption(line: &str) -> Option<String> {
+    let found = numbers(line);
+    if let Some(constant) = SLOT_TIMING_CONSTANTS.iter().find(|c| line.contains(*c)) {
+        return Some(constant.to_string());
+    }
+    if let Some(epoch) = found.iter().find(|n| *n == SLOTS_PER_EPOCH) {
+        return Some(epoch.clone());
+    }
+    if !line.to_ascii_lowercase().contains("slot") {
+        return None;
+    }
+    found
+        .into_iter()
+        .find(|n| SLOT_TIMING_NUMBERS.contains(&n.as_str()))
+}
+
+/// The Solana rent figure `line` relies on, if any.
+fn rent_assumption(line: &str) -> Option<String> {
+    if let Some(constant) = RENT_CONSTANTS.iter().find(|c| line.contains(*c)) {
+        return Some(constant.to_string());
+    }
+    let found = numbers(line);
+    if let Some(balance) = found
+        .iter()
+        .find(|n| RENT_EXEMPT_BALANCES.contains(&n.as_str()))
+    {
+        return Some(balance.clone());
+    }
+    let lower = line.to_ascii_lowercase();
+    if !lower.contains("rent") && !lower.contains("lamport") {
+        return None;
+    }
+    found.into_iter().find(|n| RENT_RATES.contains(&n.as_str()))
+}
+
+/// One warning per line of `file` for which `assumption` finds something,
+/// built by `warning` from the line number and what was found.
+fn line_warnings(
+    project: &Path,
+    file: &str,
+    assumption: fn(&str) -> Option<String>,
+    warning: impl Fn(usize, &str) -> Warning,
+) -> Vec<Warning> {
+    let Ok(content) = fs::read_to_string(project.join(file)) else {
+        return Vec::new();
+    };
+
+    content
+        .lines()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let found = assumption(line)?;
+            Some(warning(index + 1, &found).in_file(file))
+        })
+        .collect()
+}
+
+/// Warnings for lines of `file` that convert between slots and time with
+/// Solana's slot timing, such as vesting or auction math that counts
+/// 432,000 slots per epoch or 2.5 slots per second.
+pub fn slot_timing_warnings(project: &Path, file: &str) -> Vec<Warning> {
+    line_warnings(project, file, slot_timing_assumption, |line, found| {
+        Warning::new(
+            WarningKind::SlotTiming,
+            format!("line {} assumes Solana's slot timing ({})", line, found),
+        )
+        .recommend(format!(
+            "SOON's block cadence differs from Solana's ~400ms slots; base the math in {} on Clock::unix_timestamp instead of {}.",
+            file, found
+        ))
+    })
+}
+
+/// Warnings for lines of `file` that hardcode Solana's rent: rent-exempt
+/// balances, the rent rate, or `Rent::default()` in place of the sysvar.
+/// Deposits computed this way are wrong wherever rent is set differently.
+pub fn rent_warnings(project: &Path, file: &str) -> Vec<Warning> {
+    line_warnings(project, file, rent_assumption, |line, found| {
+        Warning::new(
+            WarningKind::RentAssumption,
+            format!("line {} hardcodes Solana's rent ({})", line, found),
+        )
+        .recommend(format!(
+            "Derive the balance in {} from the Rent sysvar on SOON (Rent::get()?.minimum_balance, or getMinimumBalanceForRentExemption) instead of {}.",
+            file, found
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slot_timing_warnings() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::write(
+            dir.path().join("vesting.rs"),
+            "const EPOCH: u64 = 432_000u64;\n\
+             let seconds = slots as f64 * 0.4;\n\
+             let fee = amount * 0.4;\n\
+             let wait = DEFAULT_MS_PER_SLOT * 10;\n\
+             let slots_left = 4000;\n",
+        )
+        .unwrap();
+
+        let warnings = slot_timing_warnings(dir.path(), "vesting.rs");
+
+        let messages: Vec<&str> = warnings.iter().map(|w| w.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "line 1 assumes Solana's slot timing (432000)",
+                "line 2 assumes Solana's slot timing (0.4)",
+                "line 4 assumes Solana's slot timing (DEFAULT_MS_PER_SLOT)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rent_warnings() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::write(
+            dir.path().join("deposit.ts"),
+            "const TOKEN_ACCOUNT_RENT = 2_039_280;\n\
+             const lamports = size * 6960;\n\
+             const width = 6960;\n\
+             const rent = await connection.getMinimumBalanceForRentExemption(size);\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join("lib.rs"), "let rent = Rent::default();\n").unwrap();
+
+        let warnings = rent_warnings(dir.path(), "deposit.ts");
+        let messages: Vec<&str> = warnings.iter().map(|w| w.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "line 1 hardcodes Solana's rent (2039280)",
+                "line 2 hardcodes Solana's rent (6960)",
+            ]
+        );
+        assert_eq!(rent_warnings(dir.path(), "lib.rs").len(), 1);
+    }
+}
@@ -0,0 +1,247 @@
+use crate::compat::{Availability, CompatFinding};
+use crate::report::Report;
+use crate::result::{MigrationResult, Warning};
+use std::cmp::Ordering;
+
+/// How a run's findings moved since an earlier report: compatibility
+/// findings are matched by program, warnings by kind, file and message.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Comparison {
+    pub resolved: Vec<Change>,
+    pub new: Vec<Change>,
+    pub unchanged: Vec<Change>,
+}
+
+/// One finding or warning in a comparison, and how much it matters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    pub description: String,
+    pub severity: Severity,
+}
+
+/// Which way a change moves the migration, mildest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Something is gone, or the scan is less sure of it.
+    Better,
+    /// Nothing to act on.
+    Same,
+    /// Something appeared, or the scan is more sure of it.
+    Worse,
+    /// Worse, and for a program SOON does not have.
+    Blocking,
+}
+
+impl Change {
+    fn new(description: String, severity: Severity) -> Self {
+        Change {
+            description,
+            severity,
+        }
+    }
+}
+
+/// How bad it is for the project to depend on `finding` more than before.
+fn worse(finding: &CompatFinding) -> Severity {
+    match finding.availability {
+        Availability::Absent => Severity::Blocking,
+        Availability::Available => Severity::Same,
+        Availability::Moved | Availability::Unverified => Severity::Worse,
+    }
+}
+
+/// A finding seen in both runs. Confidence decides which way it moved, and
+/// the use count when confidence stayed put; reports written before either
+/// was recorded compare on what they have.
+fn moved(old: &CompatFinding, finding: &CompatFinding) -> Change {
+    let old_uses = old.uses.max(old.evidence.len());
+    let mut description = format!(
+        "{}: {} -> {} use(s)",
+        finding.program, old_uses, finding.uses
+    );
+    let mut direction = Ordering::Equal;
+    if old.confidence > 0 && old.confidence != finding.confidence {
+        description.push_str(&format!(
+            ", confidence {}% -> {}%",
+            old.confidence, finding.confidence
+        ));
+        direction = finding.confidence.cmp(&old.confidence);
+    }
+    let direction = direction.then(finding.uses.cmp(&old_uses));
+    let severity = match direction {
+        Ordering::Less => Severity::Better,
+        Ordering::Equal => Severity::Same,
+        Ordering::Greater => worse(finding),
+    };
+    Change::new(description, severity)
+}
+
+pub fn compare(baseline: &Report, result: &MigrationResult) -> Comparison {
+    let mut comparison = Comparison::default();
+
+    for finding in &result.compatibility {
+        let before = baseline
+            .compatibility
+            .iter()
+            .find(|old| old.program == finding.program);
+        match before {
+            Some(old) => comparison.unchanged.push(moved(old, finding)),
+            None => comparison.new.push(Change::new(
+                format!(
+                    "{}: {} use(s), confidence {}%",
+                    finding.program, finding.uses, finding.confidence
+                ),
+                worse(finding),
+            )),
+        }
+    }
+    for old in &baseline.compatibility {
+        if !result
+            .compatibility
+            .iter()
+            .any(|finding| finding.program == old.program)
+        {
+            comparison
+                .resolved
+                .push(Change::new(old.program.clone(), Severity::Better));
+        }
+    }
+
+    for warning in &result.warnings {
+        if baseline
+            .warnings
+            .iter()
+            .any(|old| same_warning(old, warning))
+        {
+            comparison
+                .unchanged
+                .push(Change::new(describe(warning), Severity::Same));
+        } else {
+            comparison
+                .new
+                .push(Change::new(describe(warning), Severity::Worse));
+        }
+    }
+    for old in &baseline.warnings {
+        if !result
+            .warnings
+            .iter()
+            .any(|warning| same_warning(old, warning))
+        {
+            comparison
+                .resolved
+                .push(Change::new(describe(old), Severity::Better));
+        }
+    }
+
+    comparison
+}
+
+fn same_warning(a: &Warning, b: &Warning) -> bool {
+    a.kind == b.kind && a.file == b.file && a.message == b.message
+}
+
+fn describe(warning: &Warning) -> String {
+    match &warning.file {
+        Some(file) => format!("{}: {}", file, warning.message),
+        None => warning.message.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endpoints::SoonNetwork;
+    use crate::report::Provenance;
+    use crate::result::WarningKind;
+
+    fn finding(program: &str, uses: usize) -> CompatFinding {
+        CompatFinding {
+            program: program.to_string(),
+            program_id: Some("11111111111111111111111111111111".to_string()),
+            soon_program_id: None,
+            availability: Availability::Unverified,
+            note: String::new(),
+            provider: None,
+            model: None,
+            apro: None,
+            evidence: vec!["lib.rs:1".to_string()],
+            locations: Vec::new(),
+            uses,
+            confidence: 85,
+            signals: Vec::new(),
+            dev_only: false,
+            client_only: false,
+            cpi_calls: Vec::new(),
+            crates: Vec::new(),
+            owners: Vec::new(),
+            effort: None,
+        }
+    }
+
+    #[test]
+    fn test_compare_with_baseline() {
+        let mut before = MigrationResult::new(SoonNetwork::Devnet, true);
+        before.compatibility = vec![
+            finding("Pyth Oracle (legacy)", 12),
+            finding("Metaplex Core", 2),
+            finding("Switchboard On-Demand", 1),
+        ];
+        before.warnings.push(
+            Warning::new(
+                WarningKind::LocalValidator,
+                "line 3 runs solana-test-validator",
+            )
+            .in_file("Makefile"),
+        );
+        let baseline = Report::new(&before, Provenance::default());
+
+        let mut after = MigrationResult::new(SoonNetwork::Devnet, true);
+        after.compatibility = vec![
+            finding("Pyth Oracle (legacy)", 8),
+            finding("Switchboard V2", 1),
+            finding("Switchboard On-Demand", 1),
+        ];
+        after.compatibility[2].availability = Availability::Absent;
+        after.compatibility[2].confidence = 95;
+
+        let comparison = compare(&baseline, &after);
+        let changes = |changes: &[Change]| -> Vec<(String, Severity)> {
+            changes
+                .iter()
+                .map(|change| (change.description.clone(), change.severity))
+                .collect()
+        };
+        assert_eq!(
+            changes(&comparison.resolved),
+            vec![
+                ("Metaplex Core".to_string(), Severity::Better),
+                (
+                    "Makefile: line 3 runs solana-test-validator".to_string(),
+                    Severity::Better
+                ),
+            ]
+        );
+        assert_eq!(
+            changes(&comparison.new),
+            vec![(
+                "Switchboard V2: 1 use(s), confidence 85%".to_string(),
+                Severity::Worse
+            )]
+        );
+        // More sure of a program SOON lacks blocks, even at the same count
+        assert_eq!(
+            changes(&comparison.unchanged),
+            vec![
+                (
+                    "Pyth Oracle (legacy): 12 -> 8 use(s)".to_string(),
+                    Severity::Better
+                ),
+                (
+                    "Switchboard On-Demand: 1 -> 1 use(s), confidence 85% -> 95%".to_string(),
+                    Severity::Blocking
+                ),
+            ]
+        );
+    }
+}
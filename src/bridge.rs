@@ -0,0 +1,317 @@
+use crate::compat::{idl_files, is_catalogued};
+use crate::endpoints::{url_host, SoonNetwork};
+use crate::errors::MigrationError;
+use crate::network::Client;
+use crate::settings::BridgeSettings;
+use crate::workspace::find_files;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Most `file:line` locations kept per asset.
+const MAX_EVIDENCE: usize = 5;
+
+const MINT_CATALOG: &str = include_str!("../data/mints.toml");
+
+#[derive(Deserialize)]
+struct Catalog {
+    mint: Vec<KnownMint>,
+}
+
+/// A well-known SPL mint on Solana.
+#[derive(Deserialize)]
+struct KnownMint {
+    symbol: String,
+    name: String,
+    mint: String,
+    #[serde(default)]
+    note: String,
+}
+
+fn known_mints() -> &'static [KnownMint] {
+    static MINTS: OnceLock<Vec<KnownMint>> = OnceLock::new();
+    MINTS.get_or_init(|| {
+        toml::from_str::<Catalog>(MINT_CATALOG)
+            .expect("the bundled mint catalog is valid")
+            .mint
+    })
+}
+
+/// An SPL mint the project references, which has to be bridged (or
+/// recreated) before the project works on SOON.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct BridgeAsset {
+    /// Symbol of a well-known mint; `None` for mints soon-migrate does not know.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    pub name: String,
+    /// Address on Solana.
+    pub mint: String,
+    /// Address of the bridged token on SOON, from `[bridge.mints]`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub soon_mint: Option<String>,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub note: String,
+    /// `file:line` locations that reference the mint, the first few.
+    pub evidence: Vec<String>,
+    /// Whether the bridge API lists the mint; `None` when it was not asked.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub supported: Option<bool>,
+}
+
+impl BridgeAsset {
+    /// What the report calls the asset.
+    pub fn label(&self) -> &str {
+        self.symbol.as_deref().unwrap_or(&self.mint)
+    }
+}
+
+/// Source files, Anchor.toml and test files that can reference a mint.
+fn is_scanned(name: &str) -> bool {
+    name == "Anchor.toml"
+        || [".rs", ".ts", ".tsx", ".js", ".mjs", ".py"]
+            .iter()
+            .any(|extension| name.ends_with(extension))
+}
+
+/// Whether `address` is one of the well-known mints.
+pub fn is_known_mint(address: &str) -> bool {
+    known_mints().iter().any(|known| known.mint == address)
+}
+
+/// Base58 strings on `line` that decode to 32 bytes, i.e. addresses.
+pub fn addresses(line: &str) -> Vec<&str> {
+    line.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| (32..=44).contains(&word.len()))
+        .filter(|word| {
+            bs58::decode(word)
+                .into_vec()
+                .is_ok_and(|bytes| bytes.len() == 32)
+        })
+        .collect()
+}
+
+/// Finds the SPL mints the project references in its sources, tests,
+/// Anchor.toml and IDLs: well-known mints by address, and any other address
+/// on a line that talks about a mint, unless it is a known program.
+/// `settings` supplies the SOON mints already recorded.
+pub fn scan_mints(project: &Path, settings: &BridgeSettings) -> Vec<BridgeAsset> {
+    let mut assets: Vec<BridgeAsset> = Vec::new();
+
+    for file in find_files(project, &is_scanned)
+        .into_iter()
+        .chain(idl_files(project))
+    {
+        let Ok(content) = fs::read_to_string(project.join(&file)) else {
+            continue;
+        };
+        for (index, line) in content.lines().enumerate() {
+            let about_mint = line.to_ascii_lowercase().contains("mint");
+            for address in addresses(line) {
+                let known = known_mints().iter().find(|known| known.mint == address);
+                if known.is_none() && (!about_mint || is_catalogued(address)) {
+                    continue;
+                }
+                let location = format!("{}:{}", file, index + 1);
+                match assets.iter_mut().find(|asset| asset.mint == address) {
+                    Some(asset) => {
+                        if asset.evidence.len() < MAX_EVIDENCE
+                            && !asset.evidence.contains(&location)
+                        {
+                            asset.evidence.push(location);
+                        }
+                    }
+                    None => assets.push(BridgeAsset {
+                        symbol: known.map(|known| known.symbol.clone()),
+                        name: known
+                            .map_or_else(|| "unknown mint".to_string(), |known| known.name.clone()),
+                        mint: address.to_string(),
+                        soon_mint: known
+                            .and_then(|known| settings.mints.get(&known.symbol))
+                            .or_else(|| settings.mints.get(address))
+                            .cloned(),
+                        note: known.map(|known| known.note.clone()).unwrap_or_default(),
+                        evidence: vec![location],
+                        supported: None,
+                    }),
+                }
+            }
+        }
+    }
+
+    // Well-known assets first, in catalog order
+    let rank = |asset: &BridgeAsset| {
+        known_mints()
+            .iter()
+            .position(|known| known.mint == asset.mint)
+            .unwrap_or(usize::MAX)
+    };
+    assets.sort_by_key(rank);
+    assets
+}
+
+/// Asks the bridge API at `api` which assets it supports. Any JSON or text
+/// response works: an asset counts as supported when the response mentions
+/// its Solana mint.
+pub fn check_support(api: &str, assets: &mut [BridgeAsset]) -> Result<(), MigrationError> {
+    let body = Client::default()
+        .get_text(api)
+        .map_err(|e| MigrationError::RpcError(format!("bridge API {}: {}", url_host(api), e)))?;
+
+    for asset in assets {
+        asset.supported = Some(body.contains(&asset.mint));
+    }
+    Ok(())
+}
+
+/// Step-by-step instructions for getting `asset` onto SOON `network`,
+/// whose RPC endpoint is `url`.
+pub fn steps(asset: &BridgeAsset, network: SoonNetwork, url: &str) -> Vec<String> {
+    let files: Vec<&str> = asset
+        .evidence
+        .iter()
+        .map(|location| {
+            location
+                .rsplit_once(':')
+                .map_or(location.as_str(), |(file, _)| file)
+        })
+        .fold(Vec::new(), |mut files, file| {
+            if !files.contains(&file) {
+                files.push(file);
+            }
+            files
+        });
+    let label = asset.label();
+    let key = asset.symbol.as_deref().unwrap_or(&asset.mint);
+    let mut steps = Vec::new();
+
+    if let Some(soon_mint) = &asset.soon_mint {
+        steps.push(format!(
+            "Replace {} with {} in {}.",
+            asset.mint,
+            soon_mint,
+            files.join(", ")
+        ));
+        return steps;
+    }
+
+    if asset.symbol.is_none() {
+        steps.push(format!(
+            "If the project created this mint itself, create its SOON counterpart instead of bridging: spl-token create-token --url {}",
+            url
+        ));
+    } else if network == SoonNetwork::Devnet {
+        steps.push(format!(
+            "On devnet, a test mint of your own usually does: spl-token create-token --url {}",
+            url
+        ));
+    }
+    match asset.supported {
+        Some(true) => steps.push(format!("The bridge lists {}.", label)),
+        Some(false) => steps.push(format!(
+            "The bridge does not list {}, so it cannot be bridged yet; plan for a SOON-native replacement.",
+            label
+        )),
+        None => steps.push(format!("Check that the SOON bridge supports {}.", label)),
+    }
+    if asset.supported != Some(false) {
+        steps.push(format!(
+            "Bridge a small amount of {} to SOON {} and note the mint it arrives as.",
+            label, network
+        ));
+    }
+    steps.push(format!(
+        "Record the SOON mint in soon-migrate.toml under [bridge.mints] as {} = \"<SOON mint>\".",
+        if key.contains('-') {
+            format!("\"{}\"", key)
+        } else {
+            key.to_string()
+        }
+    ));
+    steps.push(format!(
+        "Replace {} with the SOON mint in {}.",
+        asset.mint,
+        files.join(", ")
+    ));
+    steps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_mint_catalog() {
+        for known in known_mints() {
+            assert_eq!(
+                bs58::decode(&known.mint).into_vec().unwrap().len(),
+                32,
+                "{}",
+                known.symbol
+            );
+        }
+    }
+
+    #[test]
+    fn test_scan_mints() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("tests")).unwrap();
+        fs::write(
+            dir.path().join("tests").join("vault.ts"),
+            "const usdc = new PublicKey(\"EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v\");\n\
+             const rewardMint = new PublicKey(\"8wWLP4YmLwFJT9kVo3Mhjwu8tkc4JyYUaSBV3JBXbDqk\");\n\
+             const tokenProgramForMint = TOKEN_PROGRAM_ID; // TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA\n\
+             const owner = new PublicKey(\"Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS\");\n",
+        )
+        .unwrap();
+        let settings = BridgeSettings {
+            api: None,
+            mints: BTreeMap::from([(
+                "USDC".to_string(),
+                "Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS".to_string(),
+            )]),
+        };
+
+        let assets = scan_mints(dir.path(), &settings);
+
+        assert_eq!(assets.len(), 2);
+        assert_eq!(assets[0].label(), "USDC");
+        assert_eq!(
+            assets[0].soon_mint.as_deref(),
+            Some("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS")
+        );
+        assert_eq!(assets[1].name, "unknown mint");
+        assert_eq!(assets[1].evidence, vec!["tests/vault.ts:2"]);
+    }
+
+    #[test]
+    fn test_steps() {
+        let asset = BridgeAsset {
+            symbol: Some("USDT".to_string()),
+            name: "Tether USD".to_string(),
+            mint: "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB".to_string(),
+            soon_mint: None,
+            note: String::new(),
+            evidence: vec!["src/lib.rs:3".to_string(), "src/lib.rs:9".to_string()],
+            supported: Some(true),
+        };
+
+        let steps = steps(
+            &asset,
+            SoonNetwork::Mainnet,
+            "https://rpc.mainnet.soo.network/rpc",
+        );
+
+        assert_eq!(
+            steps,
+            vec![
+                "The bridge lists USDT.",
+                "Bridge a small amount of USDT to SOON mainnet and note the mint it arrives as.",
+                "Record the SOON mint in soon-migrate.toml under [bridge.mints] as USDT = \"<SOON mint>\".",
+                "Replace Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB with the SOON mint in src/lib.rs.",
+            ]
+        );
+    }
+}
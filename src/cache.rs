@@ -0,0 +1,110 @@
+use crate::workspace::STATE_DIR;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Directory under `.soon-migrate/` holding cached scan results.
+const CACHE_DIR: &str = "cache";
+
+/// Per-file scan results, stored as JSON under `.soon-migrate/cache/` and
+/// keyed by a hash of everything the result depends on: the file's content,
+/// the bundled catalogs and the tool version. An edited file, or a new
+/// release, simply misses the cache. Reading and writing it is best effort;
+/// a broken or unwritable cache only means scanning again.
+#[derive(Debug, Clone)]
+pub struct ScanCache {
+    /// `None` when caching is turned off with `--no-cache`.
+    dir: Option<PathBuf>,
+}
+
+impl ScanCache {
+    pub fn new(project: &Path) -> Self {
+        ScanCache {
+            dir: Some(project.join(STATE_DIR).join(CACHE_DIR)),
+        }
+    }
+
+    /// A cache that never has anything and keeps nothing.
+    pub fn disabled() -> Self {
+        ScanCache { dir: None }
+    }
+
+    /// Key for a result computed from `parts`.
+    pub fn key(parts: &[&str]) -> String {
+        let mut input = env!("CARGO_PKG_VERSION").to_string();
+        for part in parts {
+            // Separate the parts so ("ab", "c") and ("a", "bc") differ
+            input.push('\0');
+            input.push_str(part);
+        }
+        Sha256::digest(input.as_bytes())
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let content = fs::read_to_string(self.dir.as_ref()?.join(entry_file(key))).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    pub fn put<T: Serialize>(&self, key: &str, value: &T) {
+        let Some(dir) = &self.dir else {
+            return;
+        };
+        if !dir.is_dir() {
+            if fs::create_dir_all(dir).is_err() {
+                return;
+            }
+            // Keep the cache out of `git add -A`, such as the snapshot `--git` commits
+            let _ = fs::write(dir.join(".gitignore"), "*\n");
+        }
+        let Ok(content) = serde_json::to_string(value) else {
+            return;
+        };
+        // Files are scanned in parallel and two may share a key, so each entry
+        // is written aside and renamed into place
+        let Ok(mut temp) = tempfile::NamedTempFile::new_in(dir) else {
+            return;
+        };
+        if std::io::Write::write_all(&mut temp, content.as_bytes()).is_ok() {
+            let _ = temp.persist(dir.join(entry_file(key)));
+        }
+    }
+}
+
+fn entry_file(key: &str) -> String {
+    format!("{}.json", key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_cache() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let cache = ScanCache::new(dir.path());
+        let key = ScanCache::key(&["compat", "lib.rs", "use anchor_spl::token;"]);
+        assert_ne!(
+            key,
+            ScanCache::key(&["compat", "lib.rs", "use anchor_spl::token; "])
+        );
+        assert_ne!(ScanCache::key(&["ab", "c"]), ScanCache::key(&["a", "bc"]));
+
+        assert_eq!(cache.get::<Vec<usize>>(&key), None);
+        cache.put(&key, &vec![3, 1]);
+        assert_eq!(cache.get::<Vec<usize>>(&key), Some(vec![3, 1]));
+        let cached = dir.path().join(STATE_DIR).join(CACHE_DIR);
+        assert_eq!(
+            fs::read_to_string(cached.join(".gitignore")).unwrap(),
+            "*\n"
+        );
+
+        let disabled = ScanCache::disabled();
+        disabled.put(&key, &vec![2]);
+        assert_eq!(disabled.get::<Vec<usize>>(&key), None);
+    }
+}
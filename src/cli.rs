@@ -1,11 +1,85 @@
 use clap::{Arg, ArgAction, Command};
 
-#[derive(Debug)]
+/// Subcommands, such as the two-phase plan/apply workflow. Without one the
+/// tool plans and applies in a single step.
+#[derive(Debug, Clone)]
+pub enum Subcommand {
+    Plan {
+        out: String,
+    },
+    Apply {
+        plan: String,
+    },
+    Verify {
+        report: String,
+    },
+    VerifyProgram {
+        program: String,
+    },
+    Schema {
+        roll_up: bool,
+    },
+    Compat {
+        online: bool,
+        solana_rpc: Option<String>,
+    },
+    Why {
+        location: String,
+    },
+    CheckRpc,
+    VerifyDeploy,
+    Deploy,
+    Bridge {
+        online: bool,
+        out: Option<String>,
+    },
+    Feeds {
+        online: bool,
+        max_age: u64,
+        out: Option<String>,
+    },
+    Airdrop {
+        amount: f64,
+    },
+    BenchRpc {
+        requests: usize,
+        solana: bool,
+        solana_rpc: Option<String>,
+    },
+}
+
+#[derive(Debug, Default, Clone)]
 pub struct Config {
+    pub subcommand: Option<Subcommand>,
     pub path: String,
     pub dry_run: bool,
     pub verbose: bool,
     pub restore: bool,
+    pub yes: bool,
+    pub purge_backup: bool,
+    pub only: Vec<String>,
+    pub keep_localnet: bool,
+    pub program_ids: Vec<String>,
+    pub env_endpoints: bool,
+    pub git: bool,
+    pub recursive: bool,
+    pub config_file: Option<String>,
+    pub rpc_devnet: Option<String>,
+    pub rpc_testnet: Option<String>,
+    pub rpc_mainnet: Option<String>,
+    pub report: Option<String>,
+    pub baseline: Option<String>,
+    pub paths: Vec<String>,
+    pub exclude: Vec<String>,
+    pub skip_tests: bool,
+    pub no_cache: bool,
+    pub min_confidence: u8,
+    pub sign_key: Option<String>,
+    pub notify: Option<String>,
+    pub offline: bool,
+    pub online: bool,
+    pub notify_format: Option<String>,
+    pub preflight: Option<String>,
 }
 
 impl Config {
@@ -14,37 +88,540 @@ impl Config {
             .version("0.1.0")
             .author("Your Name <youremail@example.com>")
             .about("Migrates Solana Anchor projects to SOON Network")
-            .arg(
-                Arg::new("path")
-                    .help("Path to the Anchor project")
-                    .default_value(".")
-                    .index(1),
-            )
+            .arg(path_arg(1))
             .arg(
                 Arg::new("dry-run")
                     .long("dry-run")
                     .help("Show changes without applying them")
+                    .conflicts_with("git")
                     .action(ArgAction::SetTrue),
             )
             .arg(
                 Arg::new("verbose")
                     .long("verbose")
                     .help("Enable detailed logging")
+                    .global(true)
                     .action(ArgAction::SetTrue),
             )
             .arg(
                 Arg::new("restore")
                     .long("restore")
                     .help("Restore from backup")
+                    .conflicts_with_all(["git", "baseline"])
+                    .action(ArgAction::SetTrue),
+            )
+            .arg(
+                Arg::new("purge-backup")
+                    .long("purge-backup")
+                    .help("Delete the backup after a successful restore")
+                    .requires("restore")
+                    .action(ArgAction::SetTrue),
+            )
+            .arg(
+                Arg::new("only")
+                    .long("only")
+                    .value_name("FILE")
+                    .help("Restore only this file (repeatable)")
+                    .requires("restore")
+                    .action(ArgAction::Append),
+            )
+            .arg(
+                Arg::new("yes")
+                    .long("yes")
+                    .short('y')
+                    .help("Skip confirmation prompts")
+                    .global(true)
+                    .action(ArgAction::SetTrue),
+            )
+            .arg(
+                Arg::new("keep-localnet")
+                    .long("keep-localnet")
+                    .help("Copy [programs.localnet] to the SOON network table instead of moving it")
+                    .global(true)
+                    .action(ArgAction::SetTrue),
+            )
+            .arg(
+                Arg::new("program-id")
+                    .long("program-id")
+                    .value_name("NAME=ID")
+                    .help("Program ID to use on SOON for a program (repeatable)")
+                    .global(true)
+                    .action(ArgAction::Append),
+            )
+            .arg(
+                Arg::new("env-endpoints")
+                    .long("env-endpoints")
+                    .help("Replace RPC URLs in client code with environment variable lookups and list them in .env.example")
+                    .global(true)
                     .action(ArgAction::SetTrue),
             )
+            .arg(
+                Arg::new("git")
+                    .long("git")
+                    .help("Migrate on a new soon-migration branch and commit the result")
+                    .global(true)
+                    .action(ArgAction::SetTrue),
+            )
+            .arg(
+                Arg::new("recursive")
+                    .long("recursive")
+                    .help("Migrate every Anchor project found under the path")
+                    .conflicts_with_all(["restore", "git", "baseline"])
+                    .action(ArgAction::SetTrue),
+            )
+            .arg(
+                Arg::new("config")
+                    .long("config")
+                    .value_name("FILE")
+                    .help("Config file to use instead of <path>/soon-migrate.toml")
+                    .global(true),
+            )
+            .arg(
+                Arg::new("rpc-devnet")
+                    .long("rpc-devnet")
+                    .value_name("URL")
+                    .help("RPC endpoint to use for SOON devnet")
+                    .global(true),
+            )
+            .arg(
+                Arg::new("rpc-testnet")
+                    .long("rpc-testnet")
+                    .value_name("URL")
+                    .help("RPC endpoint to use for SOON testnet")
+                    .global(true),
+            )
+            .arg(
+                Arg::new("rpc-mainnet")
+                    .long("rpc-mainnet")
+                    .value_name("URL")
+                    .help("RPC endpoint to use for SOON mainnet")
+                    .global(true),
+            )
+            .arg(
+                Arg::new("report")
+                    .long("report")
+                    .value_name("FILE")
+                    .help("Write a JSON report with input hashes and a digest")
+                    .global(true),
+            )
+            .arg(
+                Arg::new("baseline")
+                    .long("baseline")
+                    .value_name("REPORT")
+                    .help("Compare the findings with a report from an earlier run")
+                    .global(true),
+            )
+            .arg(
+                Arg::new("paths")
+                    .long("paths")
+                    .value_name("GLOB")
+                    .help("Only look for SPL, Metaplex and oracle programs in matching files (repeatable)")
+                    .action(ArgAction::Append)
+                    .global(true),
+            )
+            .arg(
+                Arg::new("exclude")
+                    .long("exclude")
+                    .value_name("GLOB")
+                    .help("Leave matching files out of the search for SPL, Metaplex and oracle programs (repeatable)")
+                    .action(ArgAction::Append)
+                    .global(true),
+            )
+            .arg(
+                Arg::new("skip-tests")
+                    .long("skip-tests")
+                    .help("Leave test code and dev-dependencies out of the compatibility findings")
+                    .action(ArgAction::SetTrue)
+                    .global(true),
+            )
+            .arg(
+                Arg::new("no-cache")
+                    .long("no-cache")
+                    .help("Scan every file again instead of reusing results cached in .soon-migrate/cache/")
+                    .action(ArgAction::SetTrue)
+                    .global(true),
+            )
+            .arg(
+                Arg::new("min-confidence")
+                    .long("min-confidence")
+                    .value_name("0-100")
+                    .help("Leave out compatibility findings with a lower confidence score")
+                    .value_parser(clap::value_parser!(u8).range(..=100))
+                    .default_value("0")
+                    .global(true),
+            )
+            .arg(
+                Arg::new("sign-key")
+                    .long("sign-key")
+                    .value_name("KEYPAIR")
+                    .help("Sign the report with this Solana keypair file")
+                    .requires("report")
+                    .global(true),
+            )
+            .arg(
+                Arg::new("notify")
+                    .long("notify")
+                    .value_name("URL")
+                    .help("POST a summary of the run to this webhook")
+                    .global(true),
+            )
+            .arg(
+                Arg::new("offline")
+                    .long("offline")
+                    .help("Never touch the network, including webhooks and the preflight build")
+                    .conflicts_with("notify")
+                    .action(ArgAction::SetTrue)
+                    .global(true),
+            )
+            .arg(
+                Arg::new("online")
+                    .long("online")
+                    .help("Also check over RPC: classify a custom provider.cluster by its genesis hash and check the deploy wallet's balance")
+                    .conflicts_with("offline")
+                    .action(ArgAction::SetTrue),
+            )
+            .arg(
+                Arg::new("notify-format")
+                    .long("notify-format")
+                    .value_name("FORMAT")
+                    .help("Webhook message format")
+                    .value_parser(["json", "slack", "discord"])
+                    .global(true),
+            )
+            .arg(
+                Arg::new("preflight")
+                    .long("preflight")
+                    .value_name("CHECK")
+                    .help("Check the project before migrating (build: run anchor build, or cargo build-sbf)")
+                    .value_parser(["build"])
+                    .global(true),
+            )
+            .subcommand(
+                Command::new("plan")
+                    .about("Write the migration plan to a file without changing anything")
+                    .arg(
+                        Arg::new("out")
+                            .long("out")
+                            .value_name("FILE")
+                            .help("Where to write the plan")
+                            .default_value("plan.yaml"),
+                    )
+                    .arg(path_arg(1)),
+            )
+            .subcommand(
+                Command::new("apply")
+                    .about("Apply a plan written by `soon-migrate plan`")
+                    .arg(
+                        Arg::new("plan")
+                            .help("Plan file to apply")
+                            .required(true)
+                            .index(1),
+                    )
+                    .arg(path_arg(2)),
+            )
+            .subcommand(
+                Command::new("verify")
+                    .about("Check the digest and signature of a report written with --report")
+                    .arg(
+                        Arg::new("report")
+                            .help("Report file to verify")
+                            .required(true)
+                            .index(1),
+                    ),
+            )
+            .subcommand(
+                Command::new("compat")
+                    .about("List what the project uses that may behave differently on SOON, without migrating")
+                    .arg(
+                        Arg::new("online")
+                            .long("online")
+                            .help("Also compare the runtime of SOON and the Solana cluster over RPC")
+                            .conflicts_with("offline")
+                            .action(ArgAction::SetTrue),
+                    )
+                    .arg(
+                        Arg::new("solana-rpc")
+                            .long("solana-rpc")
+                            .value_name("URL")
+                            .help("Solana RPC endpoint to compare with, instead of the public one")
+                            .requires("online"),
+                    )
+                    .arg(path_arg(1)),
+            )
+            .subcommand(
+                Command::new("bridge")
+                    .about("List the tokens the project uses that need bridging to SOON, and how")
+                    .arg(
+                        Arg::new("online")
+                            .long("online")
+                            .help("Ask the bridge API in soon-migrate.toml which tokens it supports")
+                            .conflicts_with("offline")
+                            .action(ArgAction::SetTrue),
+                    )
+                    .arg(
+                        Arg::new("out")
+                            .long("out")
+                            .value_name("FILE")
+                            .help("Also write the report as JSON"),
+                    )
+                    .arg(path_arg(1)),
+            )
+            .subcommand(
+                Command::new("feeds")
+                    .about("List the oracle feeds the project reads and the APRO feeds that replace them on SOON")
+                    .arg(
+                        Arg::new("online")
+                            .long("online")
+                            .help("Check on SOON that each APRO feed exists and was updated recently")
+                            .conflicts_with("offline")
+                            .action(ArgAction::SetTrue),
+                    )
+                    .arg(
+                        Arg::new("max-age")
+                            .long("max-age")
+                            .value_name("SECONDS")
+                            .help("How long ago an APRO feed may last have been updated before it counts as stale")
+                            .value_parser(clap::value_parser!(u64))
+                            .default_value("3600")
+                            .requires("online"),
+                    )
+                    .arg(
+                        Arg::new("out")
+                            .long("out")
+                            .value_name("FILE")
+                            .help("Also write the report as JSON"),
+                    )
+                    .arg(path_arg(1)),
+            )
+            .subcommand(
+                Command::new("check-rpc")
+                    .about("Check that the SOON RPC endpoints answer, with their latency and version")
+                    .arg(path_arg(1)),
+            )
+            .subcommand(
+                Command::new("bench-rpc")
+                    .about("Measure the latency and throughput of the SOON RPC endpoints")
+                    .arg(
+                        Arg::new("requests")
+                            .long("requests")
+                            .value_name("N")
+                            .help("Requests to time per method and endpoint")
+                            .default_value("20")
+                            .value_parser(clap::value_parser!(usize)),
+                    )
+                    .arg(
+                        Arg::new("solana")
+                            .long("solana")
+                            .help("Also measure the Solana endpoint of the cluster the project targets")
+                            .action(ArgAction::SetTrue),
+                    )
+                    .arg(
+                        Arg::new("solana-rpc")
+                            .long("solana-rpc")
+                            .value_name("URL")
+                            .help("Solana RPC endpoint to measure, instead of the public one")
+                            .requires("solana"),
+                    )
+                    .arg(path_arg(1)),
+            )
+            .subcommand(
+                Command::new("deploy")
+                    .about("Deploy the migrated programs to SOON and record their program IDs")
+                    .arg(path_arg(1)),
+            )
+            .subcommand(
+                Command::new("verify-deploy")
+                    .about("Check that every program in the SOON table of Anchor.toml is deployed")
+                    .arg(path_arg(1)),
+            )
+            .subcommand(
+                Command::new("verify-program")
+                    .about("Check that a built program matches the one deployed on SOON")
+                    .arg(
+                        Arg::new("program")
+                            .help("Program whose build to compare with SOON")
+                            .required(true)
+                            .index(1),
+                    )
+                    .arg(path_arg(2)),
+            )
+            .subcommand(
+                Command::new("airdrop")
+                    .about("Request devnet SOL on SOON devnet for the project's wallet")
+                    .arg(
+                        Arg::new("amount")
+                            .help("Amount of SOL to request")
+                            .default_value("1")
+                            .value_parser(|amount: &str| {
+                                amount
+                                    .parse::<f64>()
+                                    .ok()
+                                    .filter(|sol| sol.is_finite() && *sol > 0.0)
+                                    .ok_or("expected a positive amount of SOL")
+                            })
+                            .index(1),
+                    )
+                    .arg(path_arg(2)),
+            )
+            .subcommand(
+                Command::new("schema")
+                    .about("Print the JSON Schema of reports written with --report")
+                    .arg(
+                        Arg::new("roll-up")
+                            .long("roll-up")
+                            .help("Print the schema of --recursive roll-ups instead")
+                            .action(ArgAction::SetTrue),
+                    ),
+            )
+            .subcommand(
+                Command::new("why")
+                    .about("Explain how the migration changed a line, e.g. Anchor.toml:12")
+                    .arg(
+                        Arg::new("location")
+                            .value_name("FILE:LINE")
+                            .help("File relative to the project root, and line number")
+                            .required(true)
+                            .index(1),
+                    )
+                    .arg(path_arg(2)),
+            )
             .get_matches();
 
+        let (subcommand, path) = match matches.subcommand() {
+            Some(("plan", sub)) => (
+                Some(Subcommand::Plan {
+                    out: sub.get_one::<String>("out").unwrap().to_string(),
+                }),
+                sub.get_one::<String>("path"),
+            ),
+            Some(("apply", sub)) => (
+                Some(Subcommand::Apply {
+                    plan: sub.get_one::<String>("plan").unwrap().to_string(),
+                }),
+                sub.get_one::<String>("path"),
+            ),
+            Some(("verify", sub)) => (
+                Some(Subcommand::Verify {
+                    report: sub.get_one::<String>("report").unwrap().to_string(),
+                }),
+                matches.get_one::<String>("path"),
+            ),
+            Some(("compat", sub)) => (
+                Some(Subcommand::Compat {
+                    online: sub.get_flag("online"),
+                    solana_rpc: sub.get_one::<String>("solana-rpc").cloned(),
+                }),
+                sub.get_one::<String>("path"),
+            ),
+            Some(("bridge", sub)) => (
+                Some(Subcommand::Bridge {
+                    online: sub.get_flag("online"),
+                    out: sub.get_one::<String>("out").cloned(),
+                }),
+                sub.get_one::<String>("path"),
+            ),
+            Some(("feeds", sub)) => (
+                Some(Subcommand::Feeds {
+                    online: sub.get_flag("online"),
+                    max_age: *sub.get_one::<u64>("max-age").unwrap(),
+                    out: sub.get_one::<String>("out").cloned(),
+                }),
+                sub.get_one::<String>("path"),
+            ),
+            Some(("check-rpc", sub)) => (Some(Subcommand::CheckRpc), sub.get_one::<String>("path")),
+            Some(("bench-rpc", sub)) => (
+                Some(Subcommand::BenchRpc {
+                    requests: *sub.get_one::<usize>("requests").unwrap(),
+                    solana: sub.get_flag("solana"),
+                    solana_rpc: sub.get_one::<String>("solana-rpc").cloned(),
+                }),
+                sub.get_one::<String>("path"),
+            ),
+            Some(("deploy", sub)) => (Some(Subcommand::Deploy), sub.get_one::<String>("path")),
+            Some(("verify-deploy", sub)) => (
+                Some(Subcommand::VerifyDeploy),
+                sub.get_one::<String>("path"),
+            ),
+            Some(("verify-program", sub)) => (
+                Some(Subcommand::VerifyProgram {
+                    program: sub.get_one::<String>("program").unwrap().to_string(),
+                }),
+                sub.get_one::<String>("path"),
+            ),
+            Some(("airdrop", sub)) => (
+                Some(Subcommand::Airdrop {
+                    amount: *sub.get_one::<f64>("amount").unwrap(),
+                }),
+                sub.get_one::<String>("path"),
+            ),
+            Some(("schema", sub)) => (
+                Some(Subcommand::Schema {
+                    roll_up: sub.get_flag("roll-up"),
+                }),
+                matches.get_one::<String>("path"),
+            ),
+            Some(("why", sub)) => (
+                Some(Subcommand::Why {
+                    location: sub.get_one::<String>("location").unwrap().to_string(),
+                }),
+                sub.get_one::<String>("path"),
+            ),
+            _ => (None, matches.get_one::<String>("path")),
+        };
+
         Config {
-            path: matches.get_one::<String>("path").unwrap().to_string(),
+            subcommand,
+            path: path.unwrap().to_string(),
             dry_run: matches.get_flag("dry-run"),
             verbose: matches.get_flag("verbose"),
             restore: matches.get_flag("restore"),
+            yes: matches.get_flag("yes"),
+            purge_backup: matches.get_flag("purge-backup"),
+            only: matches
+                .get_many::<String>("only")
+                .map(|files| files.cloned().collect())
+                .unwrap_or_default(),
+            keep_localnet: matches.get_flag("keep-localnet"),
+            program_ids: matches
+                .get_many::<String>("program-id")
+                .map(|ids| ids.cloned().collect())
+                .unwrap_or_default(),
+            env_endpoints: matches.get_flag("env-endpoints"),
+            git: matches.get_flag("git"),
+            recursive: matches.get_flag("recursive"),
+            config_file: matches.get_one::<String>("config").cloned(),
+            rpc_devnet: matches.get_one::<String>("rpc-devnet").cloned(),
+            rpc_testnet: matches.get_one::<String>("rpc-testnet").cloned(),
+            rpc_mainnet: matches.get_one::<String>("rpc-mainnet").cloned(),
+            report: matches.get_one::<String>("report").cloned(),
+            baseline: matches.get_one::<String>("baseline").cloned(),
+            skip_tests: matches.get_flag("skip-tests"),
+            no_cache: matches.get_flag("no-cache"),
+            min_confidence: matches
+                .get_one::<u8>("min-confidence")
+                .copied()
+                .unwrap_or_default(),
+            paths: matches
+                .get_many::<String>("paths")
+                .map(|paths| paths.cloned().collect())
+                .unwrap_or_default(),
+            exclude: matches
+                .get_many::<String>("exclude")
+                .map(|exclude| exclude.cloned().collect())
+                .unwrap_or_default(),
+            sign_key: matches.get_one::<String>("sign-key").cloned(),
+            notify: matches.get_one::<String>("notify").cloned(),
+            offline: matches.get_flag("offline"),
+            online: matches.get_flag("online"),
+            notify_format: matches.get_one::<String>("notify-format").cloned(),
+            preflight: matches.get_one::<String>("preflight").cloned(),
         }
     }
-}
\ No newline at end of file
+}
+
+fn path_arg(index: usize) -> Arg {
+    Arg::new("path")
+        .help("Path to the Anchor project")
+        .default_value(".")
+        .index(index)
+}
@@ -0,0 +1,88 @@
+use ::ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::fs;
+use std::path::Path;
+
+/// Where GitHub looks for the file, in the order it looks.
+const CODEOWNERS_FILES: &[&str] = &[".github/CODEOWNERS", "CODEOWNERS", "docs/CODEOWNERS"];
+
+/// Who owns which files, from the project's `CODEOWNERS`.
+#[derive(Debug, Default)]
+pub struct CodeOwners {
+    /// Each pattern with its owners, in file order.
+    rules: Vec<(Gitignore, Vec<String>)>,
+}
+
+impl CodeOwners {
+    /// Reads the first `CODEOWNERS` GitHub would use; without one nobody
+    /// owns anything.
+    pub fn load(project: &Path) -> Self {
+        CODEOWNERS_FILES
+            .iter()
+            .find_map(|file| fs::read_to_string(project.join(file)).ok())
+            .map(|content| CodeOwners::parse(&content))
+            .unwrap_or_default()
+    }
+
+    fn parse(content: &str) -> Self {
+        let mut rules = Vec::new();
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut words = line.split_whitespace();
+            let Some(pattern) = words.next() else {
+                continue;
+            };
+            let owners = words
+                .take_while(|word| !word.starts_with('#'))
+                .map(str::to_string)
+                .collect();
+            let mut builder = GitignoreBuilder::new("");
+            // A pattern git cannot read is skipped, as GitHub does
+            if builder.add_line(None, pattern).is_err() {
+                continue;
+            }
+            if let Ok(matcher) = builder.build() {
+                rules.push((matcher, owners));
+            }
+        }
+        CodeOwners { rules }
+    }
+
+    /// Owners of `file`, relative to the project root with `/` separators.
+    /// The last pattern that matches decides, and one without owners leaves
+    /// the file unowned.
+    pub fn owners(&self, file: &str) -> &[String] {
+        self.rules
+            .iter()
+            .rev()
+            .find(|(matcher, _)| matcher.matched_path_or_any_parents(file, false).is_ignore())
+            .map_or(&[], |(_, owners)| owners)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_last_matching_pattern_wins() {
+        let owners = CodeOwners::parse(
+            "# Everything else goes to the core team\n\
+             * @acme/core\n\
+             /programs/ @acme/onchain @alice\n\
+             *.ts @acme/frontend # client code\n\
+             programs/vault/tests/\n",
+        );
+
+        assert_eq!(owners.owners("README.md"), ["@acme/core"]);
+        assert_eq!(
+            owners.owners("programs/vault/src/lib.rs"),
+            ["@acme/onchain", "@alice"]
+        );
+        assert_eq!(owners.owners("programs/vault/app.ts"), ["@acme/frontend"]);
+        assert!(owners.owners("programs/vault/tests/vault.rs").is_empty());
+        assert!(CodeOwners::default().owners("src/lib.rs").is_empty());
+    }
+}
@@ -0,0 +1,2869 @@
+use crate::cache::ScanCache;
+use crate::codeowners::CodeOwners;
+use crate::ignore::IgnoreRules;
+use crate::workspace::find_files;
+use rayon::prelude::*;
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+/// How many places a finding lists; the rest are only counted.
+const MAX_EVIDENCE: usize = 5;
+
+/// Whether an ecosystem program can be relied on when running on SOON.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Availability {
+    /// Part of SOON's SVM at the same address as on Solana.
+    Available,
+    /// On SOON, but at a different address.
+    Moved,
+    /// Not on SOON.
+    Absent,
+    /// Not known to be deployed on SOON; check before relying on it.
+    Unverified,
+}
+
+/// Oracle network a program belongs to. Oracles on SOON are served by APRO,
+/// so each one comes with its own way of moving there.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Provider {
+    Pyth,
+    Switchboard,
+    Chainlink,
+    Stork,
+    Band,
+    Flux,
+    Ormi,
+    /// A network the catalog does not know, reported by a custom detector.
+    /// Written as its name alone, like the others.
+    #[serde(untagged)]
+    Custom(String),
+}
+
+impl fmt::Display for Provider {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Provider::Pyth => "Pyth",
+            Provider::Switchboard => "Switchboard",
+            Provider::Chainlink => "Chainlink",
+            Provider::Stork => "Stork",
+            Provider::Band => "Band Protocol",
+            Provider::Flux => "Flux Aggregator",
+            Provider::Ormi => "Ormi",
+            Provider::Custom(name) => name,
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// How an oracle integration gets its prices, which decides what moving it
+/// to APRO takes.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OracleModel {
+    /// The program reads an account the oracle keeps updated.
+    Push,
+    /// The client fetches a signed update and posts it with the transaction.
+    Pull,
+}
+
+impl fmt::Display for OracleModel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OracleModel::Push => write!(f, "push"),
+            OracleModel::Pull => write!(f, "pull"),
+        }
+    }
+}
+
+/// What kind of line a use was found on, which says how sure it is that the
+/// project really depends on the program. Comments are never scanned, so a
+/// program only named in one adds nothing.
+#[derive(
+    Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum EvidenceKind {
+    /// A dependency in `Cargo.toml` or `package.json`, which may go unused.
+    Manifest,
+    /// A `use`, `import` or `require` of the crate or package.
+    Import,
+    /// Code that calls or names the program's types and functions.
+    CallSite,
+    /// An IDL account or account type that belongs to the program.
+    Idl,
+    /// The program's address itself.
+    Address,
+}
+
+impl EvidenceKind {
+    /// How much a use of this kind counts towards a finding's confidence,
+    /// out of 100.
+    pub fn weight(self) -> u8 {
+        match self {
+            EvidenceKind::Manifest => 60,
+            EvidenceKind::Import => 70,
+            EvidenceKind::CallSite => 85,
+            EvidenceKind::Idl => 85,
+            EvidenceKind::Address => 90,
+        }
+    }
+
+    /// Confidence from every kind of use found. Each kind is an independent
+    /// sign the program is used, so a dependency that code also calls scores
+    /// higher than either alone: 100 less what all of them leave in doubt.
+    pub fn combine(kinds: &[EvidenceKind]) -> u8 {
+        EvidenceKind::combine_weights(kinds.iter().map(|kind| kind.weight()))
+    }
+
+    /// The same, from the weight each kind of use was given, as when a
+    /// detector says how sure it is.
+    pub fn combine_weights(weights: impl IntoIterator<Item = u8>) -> u8 {
+        let mut weights = weights.into_iter().peekable();
+        if weights.peek().is_none() {
+            return 0;
+        }
+        let doubt = weights.fold(100u32, |doubt, weight| {
+            doubt * (100 - weight.min(100) as u32) / 100
+        });
+        (100 - doubt) as u8
+    }
+}
+
+/// Exactly what matched in a file, for tools that point at it: SARIF and
+/// HTML renderers, editor integrations.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub file: String,
+    /// One-based line of the match.
+    pub line: usize,
+    /// One-based column of the match, in characters.
+    pub column: usize,
+    /// Byte offset of the match in the file.
+    pub start: usize,
+    /// Byte offset just past the match.
+    pub end: usize,
+    /// The matched line, with the lines just before and after it.
+    pub snippet: String,
+}
+
+/// A well-known program the project depends on, and what to expect on SOON.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq, Eq)]
+pub struct CompatFinding {
+    pub program: String,
+    /// Address on Solana; `None` for programs found only by their crates and
+    /// packages, whose address is not catalogued.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub program_id: Option<String>,
+    /// Address on SOON, when the program is there under another one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub soon_program_id: Option<String>,
+    pub availability: Availability,
+    pub note: String,
+    /// Oracle network the program belongs to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<Provider>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<OracleModel>,
+    /// How to move an oracle integration to APRO on SOON.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub apro: Option<String>,
+    /// `file:line` locations that use the program, the first few of `uses`.
+    pub evidence: Vec<String>,
+    /// The same places as `evidence`, with the column, byte offsets and
+    /// source around each match.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub locations: Vec<Location>,
+    /// Every place the program is used, listed in `evidence` or not.
+    #[serde(default)]
+    pub uses: usize,
+    /// How sure the scan is that the project depends on the program, from 0
+    /// to 100, combining the weight of every kind of use in `signals`.
+    #[serde(default)]
+    #[schemars(range(max = 100))]
+    pub confidence: u8,
+    /// Every kind of use found, weakest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub signals: Vec<EvidenceKind>,
+    /// Only tests use the program: it is a dev-dependency, or only appears
+    /// under `tests/` or `benches/`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub dev_only: bool,
+    /// Only client code uses the program: `package.json` and TypeScript or
+    /// JavaScript sources, not the on-chain programs.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub client_only: bool,
+    /// `file:line` locations that call into the program through CPI, with
+    /// the calling function. These need the program itself on SOON, not just
+    /// its accounts.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cpi_calls: Vec<String>,
+    /// Workspace crates that use the program, by package name, sorted.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub crates: Vec<String>,
+    /// Code owners of the files that use the program, from `CODEOWNERS`,
+    /// sorted.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub owners: Vec<String>,
+    /// How much moving off the program is likely to take; `None` when it is
+    /// available on SOON as it is.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub effort: Option<Effort>,
+}
+
+impl CompatFinding {
+    /// Whether this is a sysvar rather than a program. Sysvar addresses all
+    /// start with `Sysvar`.
+    pub fn is_sysvar(&self) -> bool {
+        self.program_id
+            .as_deref()
+            .is_some_and(|id| id.starts_with("Sysvar"))
+    }
+
+    /// The program's name, with its address when it is known.
+    pub fn describe(&self) -> String {
+        match &self.program_id {
+            Some(id) => format!("{} ({})", self.program, id),
+            None => self.program.clone(),
+        }
+    }
+}
+
+/// How hard a finding is to deal with, from the code that uses it.
+#[derive(
+    Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum EffortLevel {
+    /// A few lines: dependencies, imports and addresses to swap.
+    #[default]
+    Trivial,
+    /// Code that reads the program's accounts, or account constraints that
+    /// pin them.
+    Moderate,
+    /// CPI calls into the program, or a lot of code using it.
+    Significant,
+}
+
+impl fmt::Display for EffortLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            EffortLevel::Trivial => "trivial",
+            EffortLevel::Moderate => "moderate",
+            EffortLevel::Significant => "significant",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// An estimate of the work a finding needs, from every use found. Each use
+/// counts once, as the first of: a CPI call, a use in an accounts struct, a
+/// call site or import, or a dependency or address.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Effort {
+    pub level: EffortLevel,
+    /// Rough number of lines to change: 10 per CPI call, 3 per call site,
+    /// 2 per account constraint and 1 per dependency or address.
+    pub lines: usize,
+    pub cpi_calls: usize,
+    /// Uses in `#[derive(Accounts)]` structs, such as `address` and `owner`
+    /// constraints.
+    pub account_constraints: usize,
+    /// Code that calls or names the program, imports included.
+    pub call_sites: usize,
+    /// Dependencies in manifests and addresses outside accounts structs.
+    pub references: usize,
+}
+
+impl Effort {
+    /// Counts one use. IDL uses count for nothing, since IDLs are generated.
+    fn count(&mut self, detection: &Detection) {
+        let in_accounts = detection
+            .context
+            .as_deref()
+            .is_some_and(|context| context.starts_with("accounts "));
+        if detection.cpi {
+            self.cpi_calls += 1;
+        } else if in_accounts {
+            self.account_constraints += 1;
+        } else {
+            match detection.kind {
+                EvidenceKind::Import | EvidenceKind::CallSite => self.call_sites += 1,
+                EvidenceKind::Manifest | EvidenceKind::Address => self.references += 1,
+                EvidenceKind::Idl => {}
+            }
+        }
+    }
+
+    /// Sets `lines` and `level` from the uses counted.
+    fn estimate(mut self) -> Self {
+        self.lines = self.cpi_calls * 10
+            + self.call_sites * 3
+            + self.account_constraints * 2
+            + self.references;
+        self.level = if self.cpi_calls > 0 || self.lines > 50 {
+            EffortLevel::Significant
+        } else if self.account_constraints > 0 || self.lines > 10 {
+            EffortLevel::Moderate
+        } else {
+            EffortLevel::Trivial
+        };
+        self
+    }
+}
+
+/// The effort of every finding together.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq, Eq)]
+pub struct Complexity {
+    /// From 0 to 100, growing with the lines to change and levelling off:
+    /// 50 lines score 50, 150 score 75.
+    #[schemars(range(max = 100))]
+    pub score: u8,
+    /// The level of the hardest finding.
+    pub level: EffortLevel,
+    /// Rough number of lines to change across every finding.
+    pub lines: usize,
+}
+
+/// How complex moving the project to SOON looks from `findings`; `None`
+/// when none of them needs work.
+pub fn complexity(findings: &[CompatFinding]) -> Option<Complexity> {
+    let efforts: Vec<&Effort> = findings.iter().filter_map(|f| f.effort.as_ref()).collect();
+    let level = efforts.iter().map(|effort| effort.level).max()?;
+    let lines: usize = efforts.iter().map(|effort| effort.lines).sum();
+    Some(Complexity {
+        score: (100 * lines / (lines + 50)) as u8,
+        level,
+        lines,
+    })
+}
+
+/// Oracle networks the project uses side by side, such as Pyth with a
+/// Switchboard fallback. All of them are served by APRO on SOON, so moving
+/// each one on its own would leave separate integrations reading the same
+/// feeds.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq, Eq)]
+pub struct OracleRedundancy {
+    /// The networks, in the order their findings are listed.
+    pub providers: Vec<Provider>,
+    /// The findings for them, by program.
+    pub programs: Vec<String>,
+    /// Files that use more than one of the networks, where one most likely
+    /// backs up another.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shared_files: Vec<String>,
+    /// How to keep the pattern with APRO, in place of each finding's own
+    /// advice.
+    pub advice: String,
+}
+
+/// The oracle networks `findings` use outside tests, when there are at least
+/// two of them.
+pub fn oracle_redundancy(findings: &[CompatFinding]) -> Option<OracleRedundancy> {
+    let oracles: Vec<&CompatFinding> = findings
+        .iter()
+        .filter(|finding| finding.provider.is_some() && !finding.dev_only)
+        .collect();
+    let mut providers: Vec<Provider> = Vec::new();
+    for provider in oracles
+        .iter()
+        .filter_map(|finding| finding.provider.clone())
+    {
+        if !providers.contains(&provider) {
+            providers.push(provider);
+        }
+    }
+    if providers.len() < 2 {
+        return None;
+    }
+
+    // Files each network is used in, to find the ones using several
+    let mut files: Vec<(&str, Vec<&Provider>)> = Vec::new();
+    for finding in &oracles {
+        let provider = finding.provider.as_ref()?;
+        for location in &finding.locations {
+            match files.iter_mut().find(|(file, _)| *file == location.file) {
+                Some((_, using)) if !using.contains(&provider) => using.push(provider),
+                Some(_) => {}
+                None => files.push((&location.file, vec![provider])),
+            }
+        }
+    }
+    let shared_files: Vec<String> = files
+        .into_iter()
+        .filter(|(_, using)| using.len() > 1)
+        .map(|(file, _)| file.to_string())
+        .collect();
+
+    let names = providers
+        .iter()
+        .map(Provider::to_string)
+        .collect::<Vec<_>>();
+    let names = match names.split_last() {
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} and {}", rest.join(", "), last),
+        None => String::new(),
+    };
+    let mut advice = if shared_files.is_empty() {
+        format!(
+            "{} are used in separate places, and all of them are served by APRO on SOON: read \
+             every feed from its APRO feed account through one shared helper rather than \
+             moving each integration on its own.",
+            names
+        )
+    } else {
+        format!(
+            "{} are read together in {}, most likely as a primary oracle with a fallback. On \
+             SOON every side of it is APRO: read the primary from its APRO feed account, and \
+             point the fallback at a second APRO feed for the same pair or turn it into a \
+             staleness and deviation check on that account.",
+            names,
+            shared_files.join(", ")
+        )
+    };
+    advice.push_str(
+        " With every feed behind one network there is no independent fallback left; if the \
+         redundancy was there for resilience, add a fallback source the program trusts.",
+    );
+
+    Some(OracleRedundancy {
+        providers,
+        programs: oracles
+            .iter()
+            .map(|finding| finding.program.clone())
+            .collect(),
+        shared_files,
+        advice,
+    })
+}
+
+/// Bundled catalog of well-known programs and sysvars.
+const PROGRAM_CATALOG: &str = include_str!("../data/programs.toml");
+
+#[derive(Deserialize)]
+struct Catalog {
+    program: Vec<EcosystemProgram>,
+}
+
+#[derive(Deserialize)]
+struct EcosystemProgram {
+    name: String,
+    /// Left out when the address is not confirmed; the markers alone find it.
+    program_id: Option<String>,
+    /// Address on SOON, when it differs from `program_id`.
+    soon_program_id: Option<String>,
+    /// Crate paths, package names and other text that gives the program away.
+    #[serde(default)]
+    markers: Vec<String>,
+    /// Part of another entry's program, at its address: only the markers
+    /// tell this one apart.
+    #[serde(default)]
+    marker_only: bool,
+    /// Account types in an Anchor IDL that belong to the program.
+    #[serde(default)]
+    idl_types: Vec<String>,
+    availability: Availability,
+    note: String,
+    provider: Option<Provider>,
+    model: Option<OracleModel>,
+    /// How to move to APRO, for oracles.
+    apro: Option<String>,
+}
+
+/// The programs in the bundled catalog, in catalog order.
+fn programs() -> &'static [EcosystemProgram] {
+    static PROGRAMS: OnceLock<Vec<EcosystemProgram>> = OnceLock::new();
+    PROGRAMS.get_or_init(|| {
+        toml::from_str::<Catalog>(PROGRAM_CATALOG)
+            .expect("the bundled program catalog is valid")
+            .program
+    })
+}
+
+/// Whether `address` is a program or sysvar in the catalog.
+pub fn is_catalogued(address: &str) -> bool {
+    programs()
+        .iter()
+        .any(|program| program.program_id.as_deref() == Some(address))
+}
+
+/// Extensions of the TypeScript and JavaScript sources scanned.
+const CLIENT_EXTENSIONS: &[&str] = &[".ts", ".tsx", ".mts", ".cts", ".js", ".jsx", ".mjs", ".cjs"];
+
+/// Source, manifest and package files that can reference other programs.
+fn is_scanned(name: &str) -> bool {
+    // Anchor.toml clones program and feed accounts into the test validator
+    matches!(name, "Cargo.toml" | "Anchor.toml") || name.ends_with(".rs") || is_client(name)
+}
+
+/// Whether a file named `name` is client code: a package manifest or a
+/// TypeScript or JavaScript source.
+fn is_client(name: &str) -> bool {
+    name == "package.json"
+        || CLIENT_EXTENSIONS
+            .iter()
+            .any(|extension| name.ends_with(extension))
+}
+
+/// What `scan_ecosystem` looks at.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ScanOptions<'a> {
+    /// Globs a file must match to be scanned; empty means every file.
+    pub paths: &'a [String],
+    /// Globs of files to leave out, even when `paths` selects them.
+    pub exclude: &'a [String],
+    /// Scan files on every core at once rather than one after another. It
+    /// pays off on large projects; `ScanOptions::sized_for` decides.
+    pub parallel: bool,
+    /// Leave out test code rather than marking it as tests only.
+    pub skip_tests: bool,
+    /// Scan every file again rather than reusing cached results.
+    pub no_cache: bool,
+    /// Leave out findings whose confidence is below this.
+    pub min_confidence: u8,
+    /// Rules from `[[rules]]` in `soon-migrate.toml`, run alongside the
+    /// scanner's detectors.
+    pub rules: &'a [Rule],
+}
+
+impl ScanOptions<'_> {
+    /// These options for a project of `size`: a large one is scanned in
+    /// parallel, with the cache unless `no_cache` turned it off; a small one
+    /// file after file without the cache, which is as quick and always
+    /// reads what is on disk.
+    pub fn sized_for(self, size: &ProjectSize) -> Self {
+        let large = size.is_large();
+        ScanOptions {
+            parallel: large,
+            no_cache: self.no_cache || !large,
+            ..self
+        }
+    }
+}
+
+/// Files a scan reads from a project and how much they hold, from
+/// `ProjectSize::measure`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ProjectSize {
+    pub files: usize,
+    pub bytes: u64,
+}
+
+/// Files past which a project counts as large.
+const LARGE_FILES: usize = 2_000;
+
+/// Bytes past which a project counts as large.
+const LARGE_BYTES: u64 = 32 * 1024 * 1024;
+
+impl ProjectSize {
+    /// Counts the files a scan with `options` would read, without reading
+    /// them.
+    pub fn measure(project: &Path, options: &ScanOptions) -> Self {
+        let files = scanned_files(project, options);
+        ProjectSize {
+            files: files.len(),
+            bytes: files
+                .iter()
+                .filter_map(|file| fs::metadata(project.join(file)).ok())
+                .map(|metadata| metadata.len())
+                .sum(),
+        }
+    }
+
+    /// Whether there is enough to scan for parallelism and the cache to be
+    /// worth having.
+    pub fn is_large(&self) -> bool {
+        self.files >= LARGE_FILES || self.bytes >= LARGE_BYTES
+    }
+}
+
+/// Finds the SPL, Metaplex and oracle programs, and the sysvars and native
+/// programs that behave differently on SOON, that the project uses: by
+/// address, by the crates and packages that wrap them, or through its IDLs.
+pub fn scan_ecosystem(project: &Path, options: &ScanOptions) -> Vec<CompatFinding> {
+    Scanner::new().scan(project, options)
+}
+
+/// Finds uses of programs, one file at a time. `CatalogDetector` finds the
+/// programs in the bundled catalog; register others on a `Scanner` for
+/// programs it cannot know about, such as an in-house oracle wrapper.
+pub trait Detector: Send + Sync {
+    fn detect(&self, ctx: &ScanContext) -> Vec<Detection>;
+}
+
+/// One file of the project as a `Detector` sees it: Rust, TypeScript or
+/// JavaScript source, a `Cargo.toml`, `package.json` or `Anchor.toml`, or an
+/// Anchor IDL.
+pub struct ScanContext<'a> {
+    pub project: &'a Path,
+    /// Path of the file relative to the project root, with `/` separators.
+    pub file: &'a str,
+    pub content: &'a str,
+    lines: Vec<&'a str>,
+    /// Byte offset at which each line starts.
+    line_starts: Vec<usize>,
+    cache: &'a ScanCache,
+}
+
+impl<'a> ScanContext<'a> {
+    fn new(project: &'a Path, file: &'a str, content: &'a str, cache: &'a ScanCache) -> Self {
+        ScanContext {
+            project,
+            file,
+            content,
+            lines: content.lines().collect(),
+            line_starts: std::iter::once(0)
+                .chain(content.match_indices('\n').map(|(index, _)| index + 1))
+                .collect(),
+            cache,
+        }
+    }
+
+    /// The file's name, without its directory.
+    pub fn name(&self) -> &'a str {
+        self.file.rsplit('/').next().unwrap_or(self.file)
+    }
+
+    /// Whether the file is one of the project's Anchor IDLs.
+    pub fn is_idl(&self) -> bool {
+        IDL_DIRS.iter().any(|dir| {
+            self.file
+                .strip_prefix(dir)
+                .and_then(|rest| rest.strip_prefix('/'))
+                .is_some_and(|name| !name.contains('/') && name.ends_with(".json"))
+        })
+    }
+
+    /// Where the bytes `span` of the file are, with the source around them.
+    pub fn locate(&self, span: Range<usize>) -> Location {
+        let index = self
+            .line_starts
+            .partition_point(|&start| start <= span.start)
+            .saturating_sub(1);
+        let line = self.lines.get(index).copied().unwrap_or("");
+        let column = line
+            .get(..span.start - self.line_starts[index])
+            .map_or(0, |before| before.chars().count());
+        let around = index.saturating_sub(1)..(index + 2).min(self.lines.len());
+        Location {
+            file: self.file.to_string(),
+            line: index + 1,
+            column: column + 1,
+            start: span.start,
+            end: span.end,
+            snippet: self
+                .lines
+                .get(around)
+                .map_or_else(String::new, |lines| lines.join("\n")),
+        }
+    }
+}
+
+/// A use of a program that a `Detector` found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Detection {
+    /// The program's name. One in the bundled catalog takes its details from
+    /// there; any other is reported as unverified on SOON, with the
+    /// `provider`, `note` and `apro` given here.
+    pub program: String,
+    pub kind: EvidenceKind,
+    pub location: Location,
+    /// What the use is part of, such as an accounts struct or a dependency
+    /// section.
+    pub context: Option<String>,
+    /// In test code, such as a `#[cfg(test)]` module or a dev-dependency.
+    pub test: bool,
+    /// A CPI call into the program.
+    pub cpi: bool,
+    /// Function the use is in, for a CPI call.
+    pub function: Option<String>,
+    /// Crate or program the use belongs to, when the file says; the package
+    /// of the nearest `Cargo.toml` otherwise.
+    pub owner: Option<String>,
+    pub provider: Option<Provider>,
+    pub note: Option<String>,
+    pub apro: Option<String>,
+    /// How sure the detector is of this use, out of 100, in place of the
+    /// weight of its kind.
+    pub confidence: Option<u8>,
+}
+
+impl Detection {
+    pub fn new(program: impl Into<String>, kind: EvidenceKind, location: Location) -> Self {
+        Detection {
+            program: program.into(),
+            kind,
+            location,
+            context: None,
+            test: false,
+            cpi: false,
+            function: None,
+            owner: None,
+            provider: None,
+            note: None,
+            apro: None,
+            confidence: None,
+        }
+    }
+}
+
+/// What a finding for a program outside the catalog says, unless its
+/// detector says otherwise.
+const UNCATALOGUED_NOTE: &str = "Not in the bundled catalog; check that it is deployed on SOON.";
+
+/// Runs detectors over a project and merges what they find into one finding
+/// per program.
+pub struct Scanner {
+    detectors: Vec<Box<dyn Detector>>,
+}
+
+impl Default for Scanner {
+    fn default() -> Self {
+        Scanner::new()
+    }
+}
+
+impl Scanner {
+    /// A scanner with the built-in `CatalogDetector`.
+    pub fn new() -> Self {
+        Scanner {
+            detectors: vec![Box::new(CatalogDetector)],
+        }
+    }
+
+    /// Adds a detector, run on every file alongside the ones already there.
+    pub fn register(&mut self, detector: impl Detector + 'static) -> &mut Self {
+        self.detectors.push(Box::new(detector));
+        self
+    }
+
+    /// The registered detectors, then `rules` when there are any.
+    fn detectors<'s>(&'s self, rules: &'s RuleDetector) -> Vec<&'s dyn Detector> {
+        self.detectors
+            .iter()
+            .map(Box::as_ref)
+            .chain((!rules.rules.is_empty()).then_some(rules as &dyn Detector))
+            .collect()
+    }
+
+    /// Findings for every program the detectors find in the project: the
+    /// catalog's in catalog order, then the others in the order found.
+    pub fn scan(&self, project: &Path, options: &ScanOptions) -> Vec<CompatFinding> {
+        let rules = RuleDetector::new(options.rules);
+        let detectors = self.detectors(&rules);
+        let crates = Mutex::new(HashMap::new());
+        let cache = scan_cache(project, options);
+
+        // Files may be read and scanned in parallel; collecting keeps them in
+        // path order, so merging gives the same findings however the work was
+        // split
+        let files = scanned_files(project, options);
+        let scan = |file: &String| scan_file(&detectors, project, file, options, &crates, &cache);
+        let uses: Vec<Vec<Use>> = if options.parallel {
+            files.par_iter().map(scan).collect()
+        } else {
+            files.iter().map(scan).collect()
+        };
+
+        let codeowners = CodeOwners::load(project);
+        let mut merge = Merge::default();
+        for found in uses.into_iter().flatten() {
+            merge.add(found, &codeowners);
+        }
+        merge.finish(options.min_confidence)
+    }
+
+    /// Scans the project one file at a time, as the iterator is advanced,
+    /// for tools that show findings as they turn up rather than once the
+    /// whole project is done. Each file with uses yields the findings for
+    /// that file alone; `ScanIter::findings` then gives what `scan` would.
+    pub fn scan_iter<'a>(&'a self, project: &'a Path, options: &ScanOptions<'a>) -> ScanIter<'a> {
+        ScanIter {
+            scanner: self,
+            project,
+            options: *options,
+            rules: RuleDetector::new(options.rules),
+            files: scanned_files(project, options).into_iter(),
+            crates: Mutex::new(HashMap::new()),
+            cache: scan_cache(project, options),
+            codeowners: CodeOwners::load(project),
+            merge: Merge::default(),
+        }
+    }
+}
+
+/// Where scans of `project` keep what they found, unless `options` ask to
+/// scan every file again.
+fn scan_cache(project: &Path, options: &ScanOptions) -> ScanCache {
+    if options.no_cache {
+        ScanCache::disabled()
+    } else {
+        ScanCache::new(project)
+    }
+}
+
+/// The files of the project `options` selects and does not exclude, in path
+/// order; IDLs come after the sources they are built from.
+fn scanned_files(project: &Path, options: &ScanOptions) -> Vec<String> {
+    let selected = IgnoreRules::from_patterns(options.paths);
+    let excluded = IgnoreRules::from_patterns(options.exclude);
+    find_files(project, &is_scanned)
+        .into_iter()
+        .chain(idl_files(project))
+        .filter(|file| options.paths.is_empty() || selected.is_ignored(file))
+        .filter(|file| !excluded.is_ignored(file))
+        .collect()
+}
+
+/// The findings in one file of the project, from `Scanner::scan_iter`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileFindings {
+    /// Path of the file relative to the project root, with `/` separators.
+    pub file: String,
+    /// What `Scanner::scan` would find were this file the whole project.
+    pub findings: Vec<CompatFinding>,
+}
+
+/// A scan in progress, yielding each file's findings as it is scanned.
+pub struct ScanIter<'a> {
+    scanner: &'a Scanner,
+    project: &'a Path,
+    options: ScanOptions<'a>,
+    rules: RuleDetector,
+    /// Files still to scan.
+    files: std::vec::IntoIter<String>,
+    crates: Mutex<HashMap<PathBuf, Option<String>>>,
+    cache: ScanCache,
+    codeowners: CodeOwners,
+    /// Every use so far, merged as `Scanner::scan` merges them.
+    merge: Merge,
+}
+
+impl ScanIter<'_> {
+    /// Scans whatever files are left, and gives the findings for the whole
+    /// project, as `Scanner::scan` does.
+    pub fn findings(mut self) -> Vec<CompatFinding> {
+        for _ in self.by_ref() {}
+        self.merge.finish(self.options.min_confidence)
+    }
+}
+
+impl Iterator for ScanIter<'_> {
+    type Item = FileFindings;
+
+    fn next(&mut self) -> Option<FileFindings> {
+        let detectors = self.scanner.detectors(&self.rules);
+        for file in self.files.by_ref() {
+            let uses = scan_file(
+                &detectors,
+                self.project,
+                &file,
+                &self.options,
+                &self.crates,
+                &self.cache,
+            );
+            if uses.is_empty() {
+                continue;
+            }
+            let mut merge = Merge::default();
+            for found in uses {
+                merge.add(found.clone(), &self.codeowners);
+                self.merge.add(found, &self.codeowners);
+            }
+            return Some(FileFindings {
+                file,
+                findings: merge.finish(self.options.min_confidence),
+            });
+        }
+        None
+    }
+}
+
+/// Uses merged into one finding per program as they come in.
+#[derive(Default)]
+struct Merge {
+    findings: Vec<CompatFinding>,
+    /// The same place can turn up more than once, as when an IDL lists an
+    /// account type twice, or two detectors find the same use; each counts
+    /// once.
+    seen: HashSet<(String, String)>,
+    /// Each kind of use counts with the strongest weight it was given.
+    weights: HashMap<(String, EvidenceKind), u8>,
+    efforts: HashMap<String, Effort>,
+}
+
+impl Merge {
+    fn add(&mut self, found: Use, codeowners: &CodeOwners) {
+        if !self
+            .seen
+            .insert((found.detection.program.clone(), found.location.clone()))
+        {
+            return;
+        }
+        let detection = &found.detection;
+        let weight = detection.confidence.unwrap_or(detection.kind.weight());
+        let strongest = self
+            .weights
+            .entry((detection.program.clone(), detection.kind))
+            .or_insert(0);
+        *strongest = weight.max(*strongest);
+        self.efforts
+            .entry(detection.program.clone())
+            .or_default()
+            .count(detection);
+        let program = detection.program.clone();
+        let owners = codeowners.owners(&detection.location.file);
+        record(&mut self.findings, found);
+        record_owners(&mut self.findings, &program, owners);
+    }
+
+    /// The findings at or above `min_confidence`: the catalog's in catalog
+    /// order, then the others in the order found.
+    fn finish(mut self, min_confidence: u8) -> Vec<CompatFinding> {
+        let mut findings = self.findings;
+        for finding in &mut findings {
+            finding.confidence = EvidenceKind::combine_weights(
+                finding
+                    .signals
+                    .iter()
+                    .map(|kind| self.weights[&(finding.program.clone(), *kind)]),
+            );
+            finding.effort = self
+                .efforts
+                .remove(&finding.program)
+                .filter(|_| finding.availability != Availability::Available)
+                .map(Effort::estimate);
+        }
+
+        findings.retain(|finding| finding.confidence >= min_confidence);
+        // Keep the catalog's order rather than the order files happen to be read in
+        findings.sort_by_key(|finding| {
+            programs()
+                .iter()
+                .position(|program| program.name == finding.program)
+                .unwrap_or(usize::MAX)
+        });
+        findings
+    }
+}
+
+/// Every use `detectors` find in `file`, in the order they report them.
+/// `crates` caches the package each directory belongs to across files.
+fn scan_file(
+    detectors: &[&dyn Detector],
+    project: &Path,
+    file: &str,
+    options: &ScanOptions,
+    crates: &Mutex<HashMap<PathBuf, Option<String>>>,
+    cache: &ScanCache,
+) -> Vec<Use> {
+    let Ok(content) = fs::read_to_string(project.join(file)) else {
+        return Vec::new();
+    };
+    let ctx = ScanContext::new(project, file, &content, cache);
+    let detections: Vec<Detection> = detectors
+        .iter()
+        .flat_map(|detector| detector.detect(&ctx))
+        .collect();
+    if detections.is_empty() {
+        return Vec::new();
+    }
+
+    let owner = owning_crate(project, file, crates);
+    let client = is_client(ctx.name());
+    let test_file = file
+        .split('/')
+        .any(|component| matches!(component, "tests" | "benches"))
+        || file.ends_with("_test.rs")
+        || file.ends_with("_tests.rs");
+
+    detections
+        .into_iter()
+        .filter_map(|detection| {
+            let dev = test_file || detection.test;
+            if dev && options.skip_tests {
+                return None;
+            }
+            let line = detection.location.line;
+            // IDL uses are told apart by instruction and account instead
+            let place = if ctx.is_idl() {
+                file.to_string()
+            } else {
+                format!("{}:{}", file, line)
+            };
+            let location = match &detection.context {
+                Some(context) => format!("{} ({})", place, context),
+                None => place,
+            };
+            let cpi_call = detection.cpi.then(|| match &detection.function {
+                Some(function) => format!("{}:{} in {}", file, line, function),
+                None => format!("{}:{}", file, line),
+            });
+            Some(Use {
+                owner: detection.owner.clone().or_else(|| owner.clone()),
+                location,
+                cpi_call,
+                dev,
+                client,
+                detection,
+            })
+        })
+        .collect()
+}
+
+/// A detection placed in the project.
+#[derive(Clone)]
+struct Use {
+    detection: Detection,
+    /// `file:line` the use is listed under, with what it is part of.
+    location: String,
+    dev: bool,
+    client: bool,
+    /// Package the use belongs to.
+    owner: Option<String>,
+    /// The use as a CPI call, with the calling function.
+    cpi_call: Option<String>,
+}
+
+/// The built-in detector: finds the programs in the bundled catalog by
+/// address, by the crates and packages that wrap them, and through the
+/// project's IDLs. What it finds in a source file is cached under the file's
+/// content.
+pub struct CatalogDetector;
+
+impl Detector for CatalogDetector {
+    fn detect(&self, ctx: &ScanContext) -> Vec<Detection> {
+        if ctx.is_idl() {
+            return detect_in_idl(ctx);
+        }
+        let name = ctx.name();
+        let key = ScanCache::key(&["compat", HIT_FORMAT, PROGRAM_CATALOG, name, ctx.content]);
+        let hits = ctx.cache.get(&key).unwrap_or_else(|| {
+            let hits = scan_content(name, ctx.content);
+            ctx.cache.put(&key, &hits);
+            hits
+        });
+
+        hits.into_iter()
+            .filter_map(|hit| {
+                let program = programs().get(hit.program)?;
+                let start = ctx.line_starts.get(hit.line).copied().unwrap_or(0);
+                let span = start + hit.columns.start..start + hit.columns.end;
+                let mut detection = Detection::new(&program.name, hit.kind, ctx.locate(span));
+                detection.context = hit.context;
+                detection.test = hit.test;
+                detection.cpi = hit.cpi;
+                detection.function = hit.function;
+                Some(detection)
+            })
+            .collect()
+    }
+}
+
+/// A detection rule from `[[rules]]` in `soon-migrate.toml`, for programs
+/// the catalog cannot know about, such as an in-house oracle wrapper crate.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    /// Program the finding is reported under.
+    pub name: String,
+    /// Files the rule looks at, as `.gitignore` patterns; empty means every
+    /// file the scan reads.
+    #[serde(default)]
+    pub files: Vec<String>,
+    /// Regular expression matching a use of the program.
+    #[serde(deserialize_with = "regex")]
+    pub pattern: Regex,
+    /// Oracle network the program belongs to: a catalogued one such as
+    /// `pyth`, or any other name.
+    pub provider: Option<Provider>,
+    /// How sure a match makes the scan, out of 100; the weight of its kind
+    /// of use otherwise.
+    #[serde(default, deserialize_with = "percent")]
+    pub confidence: Option<u8>,
+    /// What to do about the program on SOON: how to move to APRO when a
+    /// provider is given, the finding's note otherwise.
+    pub suggestion: Option<String>,
+}
+
+fn regex<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Regex, D::Error> {
+    let pattern = String::deserialize(deserializer)?;
+    Regex::new(&pattern).map_err(serde::de::Error::custom)
+}
+
+fn percent<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u8>, D::Error> {
+    let value = u8::deserialize(deserializer)?;
+    if value > 100 {
+        return Err(serde::de::Error::custom(format!(
+            "confidence is out of 100, got {}",
+            value
+        )));
+    }
+    Ok(Some(value))
+}
+
+/// Runs the rules from the config file, each on the files it selects.
+pub struct RuleDetector {
+    rules: Vec<(Rule, IgnoreRules)>,
+}
+
+impl RuleDetector {
+    pub fn new(rules: &[Rule]) -> Self {
+        RuleDetector {
+            rules: rules
+                .iter()
+                .map(|rule| (rule.clone(), IgnoreRules::from_patterns(&rule.files)))
+                .collect(),
+        }
+    }
+}
+
+impl Detector for RuleDetector {
+    fn detect(&self, ctx: &ScanContext) -> Vec<Detection> {
+        let name = ctx.name();
+        let rules: Vec<&Rule> = self
+            .rules
+            .iter()
+            .filter(|(rule, files)| rule.files.is_empty() || files.is_ignored(ctx.file))
+            .map(|(rule, _)| rule)
+            .collect();
+        if rules.is_empty() {
+            return Vec::new();
+        }
+        let test_lines = if name.ends_with(".rs") {
+            cfg_test_lines(ctx.content)
+        } else {
+            Vec::new()
+        };
+
+        let mut detections = Vec::new();
+        for rule in rules {
+            for matched in rule.pattern.find_iter(ctx.content) {
+                let location = ctx.locate(matched.range());
+                let line = ctx.lines.get(location.line - 1).copied().unwrap_or("");
+                if is_comment(name, line) {
+                    continue;
+                }
+                let kind = if is_manifest(name) {
+                    EvidenceKind::Manifest
+                } else if is_import(line) {
+                    EvidenceKind::Import
+                } else {
+                    EvidenceKind::CallSite
+                };
+                let mut detection = Detection::new(&rule.name, kind, location);
+                detection.test = test_lines
+                    .get(detection.location.line - 1)
+                    .copied()
+                    .unwrap_or(false);
+                detection.provider = rule.provider.clone();
+                detection.confidence = rule.confidence;
+                if rule.provider.is_some() {
+                    detection.apro = rule.suggestion.clone();
+                } else {
+                    detection.note = rule.suggestion.clone();
+                }
+                detections.push(detection);
+            }
+        }
+        detections
+    }
+}
+
+/// Changes whenever `Hit` does, so results cached by an older version are
+/// not read back.
+const HIT_FORMAT: &str = "3";
+
+/// A line using a catalogued program, as far as the file's name and content
+/// tell. This is what the scan cache keeps for each file.
+#[derive(Serialize, Deserialize)]
+struct Hit {
+    /// Zero-based line number.
+    line: usize,
+    /// Position of the program in the catalog.
+    program: usize,
+    kind: EvidenceKind,
+    /// Bytes of the line that matched.
+    columns: Range<usize>,
+    /// Accounts struct or dependency section the line is in.
+    context: Option<String>,
+    /// In a `#[cfg(test)]` module or a dev-dependency section.
+    test: bool,
+    cpi: bool,
+    /// Function the line is in, for a CPI call.
+    function: Option<String>,
+}
+
+/// The lines of a file named `name` that use a catalogued program.
+fn scan_content(name: &str, content: &str) -> Vec<Hit> {
+    let test_lines = if name.ends_with(".rs") {
+        cfg_test_lines(content)
+    } else {
+        Vec::new()
+    };
+    let (contexts, functions) = if name.ends_with(".rs") {
+        let structs = accounts_structs(content);
+        let contexts = structs
+            .into_iter()
+            .map(|name| name.map(|name| format!("accounts {}", name)))
+            .collect();
+        (contexts, enclosing_fns(content))
+    } else if name == "Cargo.toml" {
+        let sections = dependency_sections(content);
+        (
+            sections
+                .into_iter()
+                .map(|section| section.map(str::to_string))
+                .collect(),
+            Vec::new(),
+        )
+    } else {
+        (Vec::new(), Vec::new())
+    };
+
+    let mut hits = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        if is_comment(name, line) {
+            continue;
+        }
+        for mention in programs_on(line) {
+            let context: Option<String> = contexts.get(index).cloned().flatten();
+            let cpi = is_cpi(line);
+            let kind = if mention.by_address {
+                EvidenceKind::Address
+            } else if is_manifest(name) {
+                EvidenceKind::Manifest
+            } else if is_import(line) {
+                EvidenceKind::Import
+            } else {
+                EvidenceKind::CallSite
+            };
+            hits.push(Hit {
+                line: index,
+                program: mention.program,
+                kind,
+                columns: mention.columns,
+                test: test_lines.get(index).copied().unwrap_or(false)
+                    || context.as_deref() == Some(DEV_DEPENDENCIES),
+                context,
+                cpi,
+                function: cpi
+                    .then(|| functions.get(index).copied().flatten().map(str::to_string))
+                    .flatten(),
+            });
+        }
+    }
+    hits
+}
+
+/// Whether a file named `name` declares dependencies rather than using them.
+fn is_manifest(name: &str) -> bool {
+    matches!(name, "Cargo.toml" | "package.json")
+}
+
+/// Whether a line of source brings a crate or package into scope: a Rust
+/// `use` or `extern crate`, or a JavaScript `import`, `export ... from` or
+/// `require`.
+fn is_import(line: &str) -> bool {
+    let code = line.trim_start();
+    let code = code
+        .strip_prefix("pub ")
+        .or_else(|| code.strip_prefix("pub(crate) "))
+        .unwrap_or(code);
+    ["use ", "extern crate ", "import ", "import{", "export "]
+        .iter()
+        .any(|keyword| code.starts_with(keyword))
+        || code.contains("require(")
+}
+
+/// A catalogued program named on a line.
+struct Mention {
+    /// Position of the program in the catalog.
+    program: usize,
+    /// The line has the program's address, not just one of its markers.
+    by_address: bool,
+    /// Bytes of the line that matched, the first match when there are several.
+    columns: Range<usize>,
+}
+
+/// The programs `line` uses, in catalog order. A package scope such as
+/// `@switchboard-xyz/*` only counts for a package no other entry of the same
+/// provider names, so `@switchboard-xyz/solana.js` is Switchboard V2 alone.
+fn programs_on(line: &str) -> Vec<Mention> {
+    let scoped = |marker: &&String| marker.ends_with("/*");
+    let first = |markers: &mut dyn Iterator<Item = &String>| {
+        markers
+            .filter_map(|marker| find_mention(line, marker))
+            .min_by_key(|columns| columns.start)
+    };
+    let mut named = Vec::new();
+    let mut in_scope = Vec::new();
+    for (position, program) in programs().iter().enumerate() {
+        let address = program
+            .program_id
+            .as_deref()
+            .filter(|_| !program.marker_only)
+            .and_then(|address| find_mention(line, address));
+        let by_address = address.is_some();
+        let named_by =
+            address.or_else(|| first(&mut program.markers.iter().filter(|m| !scoped(m))));
+        if let Some(columns) = named_by {
+            named.push(Mention {
+                program: position,
+                by_address,
+                columns,
+            });
+        } else if let Some(columns) = first(&mut program.markers.iter().filter(scoped)) {
+            in_scope.push(Mention {
+                program: position,
+                by_address: false,
+                columns,
+            });
+        }
+    }
+
+    let provider = |position: usize| programs()[position].provider.as_ref();
+    in_scope.retain(|scope| {
+        !named.iter().any(|other| {
+            provider(other.program).is_some() && provider(other.program) == provider(scope.program)
+        })
+    });
+    named.extend(in_scope);
+    named.sort_unstable_by_key(|mention| mention.program);
+    named
+}
+
+/// Whether `line` mentions `marker` as a whole identifier or path: a marker
+/// starting or ending in an identifier character must not run into another
+/// one there, so `EpochSchedule` does not match `MyEpochScheduleHelper`. A
+/// trailing `*` lets the identifier go on.
+pub fn mentions(line: &str, marker: &str) -> bool {
+    find_mention(line, marker).is_some()
+}
+
+/// Bytes of `line` where it first mentions `marker`, as `mentions` decides.
+/// With a trailing `*`, the match runs to the end of the identifier.
+pub fn find_mention(line: &str, marker: &str) -> Option<Range<usize>> {
+    let (marker, prefix) = match marker.strip_suffix('*') {
+        Some(marker) => (marker, true),
+        None => (marker, false),
+    };
+    // Package names are kebab-case: `mpl-core` is not `mpl-core-extras`
+    let kebab = marker.contains('-');
+    let identifier = |c: char| c.is_ascii_alphanumeric() || c == '_' || (kebab && c == '-');
+
+    line.match_indices(marker).find_map(|(start, _)| {
+        let end = start + marker.len();
+        let before = line[..start].chars().next_back();
+        let after = line[end..].chars().next();
+        if marker.starts_with(identifier) && before.is_some_and(identifier) {
+            return None;
+        }
+        if !prefix {
+            let runs_on = marker.ends_with(identifier) && after.is_some_and(identifier);
+            return (!runs_on).then_some(start..end);
+        }
+        let rest = line[end..].find(|c: char| !identifier(c));
+        Some(start..rest.map_or(line.len(), |length| end + length))
+    })
+}
+
+/// Whether a line of file `name` holds nothing but a comment, which may name
+/// a program without using it.
+pub fn is_comment(name: &str, line: &str) -> bool {
+    let line = line.trim_start();
+    if name.ends_with(".toml") {
+        return line.starts_with('#');
+    }
+    if name.ends_with(".json") {
+        return false;
+    }
+    line.starts_with("//")
+        || line.starts_with("/*")
+        || line.starts_with("* ")
+        || line.starts_with("*/")
+        || line == "*"
+}
+
+/// For each line of Rust source, the `#[derive(Accounts)]` struct it is
+/// part of, so a program pinned by an `#[account(address = ...)]` or
+/// `owner = ...` constraint can be traced to the instruction context.
+fn accounts_structs(source: &str) -> Vec<Option<&str>> {
+    let mut contexts = Vec::new();
+    let mut derived = false;
+    let mut current = None;
+    let mut depth = 0;
+
+    for line in source.lines() {
+        let code = line.split("//").next().unwrap_or(line);
+        if current.is_none() {
+            if code.contains("derive(")
+                && code
+                    .split(|c: char| !c.is_alphanumeric())
+                    .any(|word| word == "Accounts")
+            {
+                derived = true;
+            } else if let Some((_, rest)) = code.split_once("struct ").filter(|_| derived) {
+                let end = rest
+                    .find(|c: char| !c.is_alphanumeric() && c != '_')
+                    .unwrap_or(rest.len());
+                current = Some(&rest[..end]);
+                derived = false;
+                depth = 0;
+            }
+        }
+        contexts.push(current);
+
+        if current.is_some() {
+            depth += code.matches('{').count() as i32 - code.matches('}').count() as i32;
+            if depth <= 0 && code.contains('}') {
+                current = None;
+            }
+        }
+    }
+    contexts
+}
+
+/// For each line of Rust source, whether it is inside a `#[cfg(test)]`
+/// module.
+fn cfg_test_lines(source: &str) -> Vec<bool> {
+    let mut lines = Vec::new();
+    let mut attributed = false;
+    let mut inside = false;
+    let mut depth = 0;
+
+    for line in source.lines() {
+        let code = line.split("//").next().unwrap_or(line);
+        if !inside {
+            if code.replace(' ', "").contains("#[cfg(test)]") {
+                attributed = true;
+            } else if attributed && !code.trim_start().starts_with("#[") {
+                // Other attributes can sit between cfg(test) and the module
+                inside = code.contains("mod ") && code.contains('{');
+                attributed = false;
+                depth = 0;
+            }
+        }
+        lines.push(inside || attributed);
+
+        if inside {
+            depth += code.matches('{').count() as i32 - code.matches('}').count() as i32;
+            if depth <= 0 {
+                inside = false;
+            }
+        }
+    }
+    lines
+}
+
+/// Adds a use to the finding for its program, with the crate it is in and
+/// the CPI call it makes.
+fn record(findings: &mut Vec<CompatFinding>, found: Use) {
+    let Use {
+        detection,
+        location,
+        dev,
+        client,
+        owner,
+        cpi_call,
+    } = found;
+    let program = detection.program.clone();
+    let kind = detection.kind;
+    match findings.iter_mut().find(|f| f.program == program) {
+        Some(finding) => {
+            finding.uses += 1;
+            if let Err(index) = finding.signals.binary_search(&kind) {
+                finding.signals.insert(index, kind);
+            }
+            finding.dev_only &= dev;
+            finding.client_only &= client;
+            if finding.evidence.len() < MAX_EVIDENCE {
+                finding.evidence.push(location);
+                finding.locations.push(detection.location);
+            }
+        }
+        None => findings.push(first_finding(detection, location, dev, client)),
+    }
+    if let Some(owner) = &owner {
+        record_crate(findings, &program, owner);
+    }
+    if let Some(call) = cpi_call {
+        record_cpi(findings, &program, call);
+    }
+}
+
+/// The finding a program's first use starts: with the catalog's details, or
+/// the detection's for a program the catalog does not have.
+fn first_finding(detection: Detection, location: String, dev: bool, client: bool) -> CompatFinding {
+    let kind = detection.kind;
+    let mut finding = CompatFinding {
+        program: detection.program,
+        program_id: None,
+        soon_program_id: None,
+        availability: Availability::Unverified,
+        note: detection
+            .note
+            .unwrap_or_else(|| UNCATALOGUED_NOTE.to_string()),
+        provider: detection.provider,
+        model: None,
+        apro: detection.apro,
+        evidence: vec![location],
+        locations: vec![detection.location],
+        uses: 1,
+        // Set once every use is recorded
+        confidence: 0,
+        signals: vec![kind],
+        dev_only: dev,
+        client_only: client,
+        cpi_calls: Vec::new(),
+        crates: Vec::new(),
+        owners: Vec::new(),
+        // Set once every use is recorded
+        effort: None,
+    };
+    if let Some(program) = programs().iter().find(|p| p.name == finding.program) {
+        finding.program_id = program.program_id.clone();
+        finding.soon_program_id = program.soon_program_id.clone();
+        finding.availability = program.availability;
+        finding.note = program.note.clone();
+        finding.provider = program.provider.clone();
+        finding.model = program.model;
+        finding.apro = program.apro.clone();
+    }
+    finding
+}
+
+const DEV_DEPENDENCIES: &str = "dev-dependencies";
+const BUILD_DEPENDENCIES: &str = "build-dependencies";
+
+/// For each line of a Cargo.toml, whether it is in a dev- or build-dependency
+/// section, including target-specific ones and `[dev-dependencies.<crate>]`
+/// tables. Regular dependencies have no label.
+fn dependency_sections(manifest: &str) -> Vec<Option<&'static str>> {
+    let mut current = None;
+    manifest
+        .lines()
+        .map(|line| {
+            let line = line.trim();
+            if let Some(header) = line.strip_prefix('[') {
+                let header = header.trim_start_matches('[').trim_end_matches(']');
+                let table = header
+                    .split('.')
+                    .find(|part| part.ends_with("dependencies"))
+                    .unwrap_or("");
+                current = match table {
+                    DEV_DEPENDENCIES => Some(DEV_DEPENDENCIES),
+                    BUILD_DEPENDENCIES => Some(BUILD_DEPENDENCIES),
+                    _ => None,
+                };
+            }
+            current
+        })
+        .collect()
+}
+
+/// Adds the crate a use of `program` belongs to, keeping the list sorted.
+fn record_crate(findings: &mut [CompatFinding], program: &str, name: &str) {
+    if let Some(finding) = findings.iter_mut().find(|f| f.program == program) {
+        if let Err(index) = finding
+            .crates
+            .binary_search_by(|known| known.as_str().cmp(name))
+        {
+            finding.crates.insert(index, name.to_string());
+        }
+    }
+}
+
+fn record_owners(findings: &mut [CompatFinding], program: &str, owners: &[String]) {
+    if let Some(finding) = findings.iter_mut().find(|f| f.program == program) {
+        for owner in owners {
+            if let Err(index) = finding.owners.binary_search(owner) {
+                finding.owners.insert(index, owner.clone());
+            }
+        }
+    }
+}
+
+/// Name of the package whose Cargo.toml is nearest above `file`, looked up
+/// once per directory.
+fn owning_crate(
+    project: &Path,
+    file: &str,
+    cache: &Mutex<HashMap<PathBuf, Option<String>>>,
+) -> Option<String> {
+    let mut dir = Path::new(file).parent();
+    while let Some(current) = dir {
+        let name = cache
+            .lock()
+            .expect("no scan panicked holding the crate cache")
+            .entry(current.to_path_buf())
+            .or_insert_with(|| {
+                let manifest = fs::read_to_string(project.join(current).join("Cargo.toml")).ok()?;
+                let manifest: toml::Value = manifest.parse().ok()?;
+                Some(manifest.get("package")?.get("name")?.as_str()?.to_string())
+            })
+            .clone();
+        if name.is_some() {
+            return name;
+        }
+        dir = current.parent();
+    }
+    None
+}
+
+/// Adds a CPI call to the finding for `program`.
+fn record_cpi(findings: &mut [CompatFinding], program: &str, call: String) {
+    if let Some(finding) = findings.iter_mut().find(|f| f.program == program) {
+        if finding.cpi_calls.len() < MAX_EVIDENCE {
+            finding.cpi_calls.push(call);
+        }
+    }
+}
+
+/// Whether a line of Rust invokes another program: a raw `invoke`, an
+/// Anchor `CpiContext` or a generated `cpi` module.
+fn is_cpi(line: &str) -> bool {
+    let code = line.split("//").next().unwrap_or(line);
+    ["invoke(", "invoke_signed(", "CpiContext::new", "::cpi::"]
+        .iter()
+        .any(|call| code.contains(call))
+}
+
+/// For each line of Rust source, the function it is in, taken to be the last
+/// `fn` declared above it.
+fn enclosing_fns(source: &str) -> Vec<Option<&str>> {
+    let mut current = None;
+    source
+        .lines()
+        .map(|line| {
+            let code = line.split("//").next().unwrap_or(line).trim_start();
+            let declared = code
+                .split_once("fn ")
+                .filter(|(before, _)| before.is_empty() || before.ends_with(' '));
+            if let Some((_, rest)) = declared {
+                let end = rest
+                    .find(|c: char| !c.is_alphanumeric() && c != '_')
+                    .unwrap_or(rest.len());
+                if end > 0 {
+                    current = Some(&rest[..end]);
+                }
+            }
+            current
+        })
+        .collect()
+}
+
+/// Directories holding Anchor IDLs: the ones `anchor build` writes and the
+/// ones projects check in.
+const IDL_DIRS: &[&str] = &["target/idl", "idls"];
+
+/// IDL files of the project, relative to its root and sorted.
+pub fn idl_files(project: &Path) -> Vec<String> {
+    let mut files = Vec::new();
+    for dir in IDL_DIRS {
+        let Ok(entries) = fs::read_dir(project.join(dir)) else {
+            continue;
+        };
+        files.extend(
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter_map(|entry| entry.file_name().into_string().ok())
+                .filter(|name| name.ends_with(".json"))
+                .map(|name| format!("{}/{}", dir, name)),
+        );
+    }
+    files.sort();
+    files
+}
+
+/// Uses of catalogued programs in an IDL, each pointing at the first place
+/// the IDL has the address or account type that gave it away.
+fn detect_in_idl(ctx: &ScanContext) -> Vec<Detection> {
+    let Ok(idl) = serde_json::from_str::<Value>(ctx.content) else {
+        return Vec::new();
+    };
+    // Anchor 0.30 moved the program name under metadata
+    let owner = idl["metadata"]["name"].as_str().or(idl["name"].as_str());
+
+    scan_idl(&idl)
+        .into_iter()
+        .map(|(program, context, needle)| {
+            let span = ctx
+                .lines
+                .iter()
+                .zip(&ctx.line_starts)
+                .find_map(|(line, start)| {
+                    let columns = find_mention(line, &needle)?;
+                    Some(start + columns.start..start + columns.end)
+                })
+                .unwrap_or(0..0);
+            let mut detection = Detection::new(&program.name, EvidenceKind::Idl, ctx.locate(span));
+            detection.context = Some(context);
+            detection.owner = owner.map(str::to_string);
+            detection
+        })
+        .collect()
+}
+
+/// Programs an IDL depends on: instruction accounts pinned to a program's
+/// address, and account types that belong to one. Each comes with the
+/// instruction and account, or the account type, that gave it away, and the
+/// address or type name.
+fn scan_idl(idl: &Value) -> Vec<(&'static EcosystemProgram, String, String)> {
+    let mut found = Vec::new();
+
+    for instruction in idl["instructions"].as_array().into_iter().flatten() {
+        let name = instruction["name"].as_str().unwrap_or("?");
+        scan_idl_accounts(name, &instruction["accounts"], &mut found);
+    }
+
+    for account in idl["accounts"].as_array().into_iter().flatten() {
+        let Some(name) = account["name"].as_str() else {
+            continue;
+        };
+        for program in programs()
+            .iter()
+            .filter(|p| p.idl_types.iter().any(|t| t == name))
+        {
+            found.push((program, format!("account type {}", name), name.to_string()));
+        }
+    }
+    found
+}
+
+/// Instruction accounts can nest in composite account structs.
+fn scan_idl_accounts(
+    instruction: &str,
+    accounts: &Value,
+    found: &mut Vec<(&'static EcosystemProgram, String, String)>,
+) {
+    for account in accounts.as_array().into_iter().flatten() {
+        if account["accounts"].is_array() {
+            scan_idl_accounts(instruction, &account["accounts"], found);
+            continue;
+        }
+        let Some(address) = account["address"].as_str() else {
+            continue;
+        };
+        for program in programs()
+            .iter()
+            .filter(|p| !p.marker_only && p.program_id.as_deref() == Some(address))
+        {
+            let name = account["name"].as_str().unwrap_or("?");
+            found.push((
+                program,
+                format!("{}.{}", instruction, name),
+                address.to_string(),
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A project holding `files`, given as path and content.
+    fn project(files: &[(&str, &str)]) -> tempfile::TempDir {
+        let dir = tempfile::TempDir::new().unwrap();
+        for (file, content) in files {
+            let path = dir.path().join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        dir
+    }
+
+    fn scan(files: &[(&str, &str)]) -> Vec<CompatFinding> {
+        scan_ecosystem(project(files).path(), &ScanOptions::default())
+    }
+
+    /// Each finding as program, availability and evidence.
+    fn summarize(findings: &[CompatFinding]) -> Vec<(&str, Availability, Vec<&str>)> {
+        findings
+            .iter()
+            .map(|finding| {
+                let evidence = finding.evidence.iter().map(String::as_str).collect();
+                (finding.program.as_str(), finding.availability, evidence)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_programs_found() {
+        use Availability::{Available, Unverified};
+
+        type Expected<'a> = &'a [(&'a str, Availability, &'a [&'a str])];
+        let cases: &[(&[(&str, &str)], Expected)] = &[
+            (
+                &[
+                    (
+                        "programs/vault/src/lib.rs",
+                        "use anchor_spl::token_interface::{Mint, TokenAccount};\nuse mpl_token_metadata::ID;\n",
+                    ),
+                    (
+                        "package.json",
+                        "{ \"dependencies\": { \"@solana/spl-token\": \"^0.4\" } }\n",
+                    ),
+                ],
+                &[
+                    ("SPL Token", Available, &["package.json:1"]),
+                    ("SPL Token-2022", Available, &["programs/vault/src/lib.rs:1"]),
+                    (
+                        "Metaplex Token Metadata",
+                        Unverified,
+                        &["programs/vault/src/lib.rs:2"],
+                    ),
+                ],
+            ),
+            // Oracles referenced only by their address
+            (
+                &[
+                    (
+                        "Anchor.toml",
+                        "[[test.validator.clone]]\naddress = \"SBondMDrcV3K4kxZR1HNVT7osZxAHVHgYXL5Ze1oMUv\"\n",
+                    ),
+                    (
+                        "oracle.rs",
+                        "pub const PYTH: Pubkey = pubkey!(\"rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ\");\n",
+                    ),
+                ],
+                &[
+                    ("Pyth Solana Receiver", Unverified, &["oracle.rs:1"]),
+                    ("Switchboard On-Demand", Unverified, &["Anchor.toml:2"]),
+                ],
+            ),
+            // The Token-2022 address alone says nothing about hooks
+            (
+                &[(
+                    "client.ts",
+                    "const TOKEN_2022 = new PublicKey(\"TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb\");\n",
+                )],
+                &[("SPL Token-2022", Available, &["client.ts:1"])],
+            ),
+            // Instruction accounts and account types in an IDL
+            (
+                &[(
+                    "target/idl/vault.json",
+                    r#"{
+  "instructions": [
+    {
+      "name": "liquidate",
+      "accounts": [
+        { "name": "feed", "address": "SW1TCH7qEPTdLsDHRgPuMQjbQxKdH2aBStViMFnt64f" },
+        { "name": "oracle", "accounts": [
+          { "name": "receiver", "address": "rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ" }
+        ] }
+      ]
+    }
+  ],
+  "accounts": [{ "name": "PriceUpdateV2" }, { "name": "Vault" }]
+}"#,
+                )],
+                &[
+                    (
+                        "Pyth Solana Receiver",
+                        Unverified,
+                        &[
+                            "target/idl/vault.json (liquidate.receiver)",
+                            "target/idl/vault.json (account type PriceUpdateV2)",
+                        ],
+                    ),
+                    (
+                        "Switchboard V2",
+                        Unverified,
+                        &["target/idl/vault.json (liquidate.feed)"],
+                    ),
+                ],
+            ),
+            // Account constraints traced to their accounts struct
+            (
+                &[(
+                    "lib.rs",
+                    r#"use pyth_solana_receiver_sdk::price_update::PriceUpdateV2;
+
+#[derive(Accounts)]
+pub struct Liquidate<'info> {
+    #[account(owner = pyth_solana_receiver_sdk::ID)]
+    pub price: Account<'info, PriceUpdateV2>,
+}
+
+#[derive(Accounts)]
+pub struct Refresh<'info> {
+    #[account(address = pubkey!("SBondMDrcV3K4kxZR1HNVT7osZxAHVHgYXL5Ze1oMUv"))]
+    pub queue: UncheckedAccount<'info>,
+}
+"#,
+                )],
+                &[
+                    (
+                        "Pyth Solana Receiver",
+                        Unverified,
+                        &["lib.rs:1", "lib.rs:5 (accounts Liquidate)"],
+                    ),
+                    (
+                        "Switchboard On-Demand",
+                        Unverified,
+                        &["lib.rs:11 (accounts Refresh)"],
+                    ),
+                ],
+            ),
+            // Runtime features that behave differently on a rollup
+            (
+                &[(
+                    "lib.rs",
+                    "use solana_program::sysvar::slot_hashes::SlotHashes;\nuse solana_program::ed25519_program;\n",
+                )],
+                &[
+                    ("SlotHashes sysvar", Unverified, &["lib.rs:1"]),
+                    ("Ed25519 precompile", Unverified, &["lib.rs:2"]),
+                ],
+            ),
+            (
+                &[(
+                    "lib.rs",
+                    "let now = Clock::get()?.unix_timestamp;\nlet ix = load_instruction_at_checked(0, &sysvar)?;\nlet token = spl_token::ID;\n",
+                )],
+                &[
+                    ("SPL Token", Available, &["lib.rs:3"]),
+                    ("Clock sysvar", Available, &["lib.rs:1"]),
+                    ("Instructions sysvar", Available, &["lib.rs:2"]),
+                ],
+            ),
+            (
+                &[(
+                    "client.ts",
+                    "const tx = new Transaction();\ntx.add(ComputeBudgetProgram.setComputeUnitPrice({ microLamports: 50_000 }));\n",
+                )],
+                &[("Compute Budget program", Available, &["client.ts:2"])],
+            ),
+            (
+                &[(
+                    "lib.rs",
+                    "use spl_tlv_account_resolution::state::ExtraAccountMetaList;\nuse spl_transfer_hook_interface::instruction::ExecuteInstruction;\n",
+                )],
+                &[(
+                    "Token-2022 transfer hook",
+                    Available,
+                    &["lib.rs:1", "lib.rs:2"],
+                )],
+            ),
+            (
+                &[(
+                    "package.json",
+                    "{\n  \"dependencies\": {\n    \"@metaplex-foundation/mpl-candy-machine\": \"^6.0.0\"\n  }\n}\n",
+                )],
+                &[("Metaplex Candy Machine", Unverified, &["package.json:3"])],
+            ),
+        ];
+
+        for (files, expected) in cases {
+            let expected: Vec<(&str, Availability, Vec<&str>)> = expected
+                .iter()
+                .map(|(program, availability, evidence)| {
+                    (*program, *availability, evidence.to_vec())
+                })
+                .collect();
+            assert_eq!(summarize(&scan(files)), expected, "{:?}", files);
+        }
+    }
+
+    #[test]
+    fn test_markers_match_whole_identifiers() {
+        let findings = scan(&[(
+            "programs/vault/src/lib.rs",
+            "/// Works like SlotHashes, but for epochs.\n\
+             struct MyEpochScheduleHelper;\n\
+             // use spl_token_2022::extension::ExtensionType;\n\
+             fn build_v0::MessageCache() {}\n\
+             let is_confidential_transfer_enabled = false;\n",
+        )]);
+        assert_eq!(summarize(&findings), vec![]);
+
+        let findings = scan(&[
+            (
+                "Cargo.toml",
+                "[dependencies]\n# mpl-core = \"0.7\"\nmpl-core-extras = \"1\"\n",
+            ),
+            (
+                "client.ts",
+                "const ata = getAssociatedTokenAddressSync(mint, owner);\n",
+            ),
+        ]);
+        assert_eq!(
+            summarize(&findings),
+            vec![(
+                "Associated Token Account",
+                Availability::Available,
+                vec!["client.ts:1"]
+            )]
+        );
+    }
+
+    #[test]
+    fn test_locations_point_at_the_match() {
+        let dir = project(&[
+            (
+                "client.ts",
+                "// Связка токенов\r\nconst ata = getAssociatedTokenAddressSync(mint, owner);\r\n",
+            ),
+            (
+                "idls/vault.json",
+                r#"{
+  "name": "vault",
+  "accounts": [{ "name": "PriceUpdateV2" }]
+}"#,
+            ),
+        ]);
+        let findings = scan_ecosystem(dir.path(), &ScanOptions::default());
+        let content = fs::read_to_string(dir.path().join("client.ts")).unwrap();
+
+        let ata = &findings[0].locations[0];
+        assert_eq!((ata.line, ata.column), (2, 13));
+        assert_eq!(
+            &content[ata.start..ata.end],
+            "getAssociatedTokenAddressSync"
+        );
+        assert_eq!(
+            ata.snippet,
+            "// Связка токенов\nconst ata = getAssociatedTokenAddressSync(mint, owner);"
+        );
+
+        let pyth = &findings[1].locations[0];
+        assert_eq!(pyth.file, "idls/vault.json");
+        assert_eq!((pyth.line, pyth.column), (3, 27));
+        assert_eq!(pyth.snippet.lines().count(), 3);
+
+        assert_eq!(find_mention("use mpl_core::ID;", "mpl_core::"), Some(4..14));
+        assert_eq!(find_mention("MyEpochScheduleHelper", "EpochSchedule"), None);
+    }
+
+    #[test]
+    fn test_oracle_providers_found() {
+        let findings = scan(&[
+            (
+                "Cargo.toml",
+                "[dependencies]\nstork-solana-sdk = \"0.0.5\"\nflux-aggregator = { git = \"https://github.com/octopus-network/solana-flux-aggregator\" }\n",
+            ),
+            (
+                "package.json",
+                "{ \"dependencies\": { \"@bandprotocol/bandchain.js\": \"^2.0.0\" } }\n",
+            ),
+            ("src/feeds.rs", "use ormi_client::IndexedFeed;\n"),
+        ]);
+
+        let found: Vec<(&str, Option<&Provider>, bool)> = findings
+            .iter()
+            .map(|f| {
+                (
+                    f.program.as_str(),
+                    f.provider.as_ref(),
+                    f.program_id.is_none(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("Stork", Some(&Provider::Stork), true),
+                ("Band Protocol", Some(&Provider::Band), true),
+                ("Flux Aggregator", Some(&Provider::Flux), true),
+                ("Ormi", Some(&Provider::Ormi), true),
+            ]
+        );
+        assert!(findings.iter().all(|f| f.apro.is_some()));
+        assert_eq!(findings[0].describe(), "Stork");
+        assert_eq!(Provider::Band.to_string(), "Band Protocol");
+    }
+
+    #[test]
+    fn test_oracles_used_together_consolidated() {
+        let findings = scan(&[
+            (
+                "programs/vault/src/price.rs",
+                "use pyth_sdk_solana::load_price_feed_from_account_info;\nuse switchboard_v2::AggregatorAccountData;\n",
+            ),
+            ("programs/vault/src/lib.rs", "use pyth_sdk_solana::PriceFeed;\n"),
+            ("tests/feeds.rs", "use chainlink_solana as chainlink;\n"),
+        ]);
+        let redundancy = oracle_redundancy(&findings).unwrap();
+        // Chainlink is only used by tests, so it is no part of the pattern
+        assert_eq!(
+            redundancy.providers,
+            vec![Provider::Pyth, Provider::Switchboard]
+        );
+        assert_eq!(
+            redundancy.programs,
+            vec!["Pyth Oracle (legacy)", "Switchboard V2"]
+        );
+        assert_eq!(redundancy.shared_files, vec!["programs/vault/src/price.rs"]);
+        assert!(redundancy
+            .advice
+            .starts_with("Pyth and Switchboard are read together in programs/vault/src/price.rs"));
+        assert!(redundancy.advice.contains("no independent fallback"));
+
+        let apart = scan(&[
+            ("programs/a/src/lib.rs", "use pyth_sdk_solana::PriceFeed;\n"),
+            (
+                "programs/b/src/lib.rs",
+                "use chainlink_solana as chainlink;\n",
+            ),
+        ]);
+        let redundancy = oracle_redundancy(&apart).unwrap();
+        assert!(redundancy.shared_files.is_empty());
+        assert!(redundancy
+            .advice
+            .starts_with("Pyth and Chainlink are used in separate places"));
+
+        assert_eq!(oracle_redundancy(&findings[..1]), None);
+    }
+
+    #[test]
+    fn test_pyth_push_and_pull_told_apart() {
+        let findings = scan(&[
+            (
+                "programs/perps/Cargo.toml",
+                "[dependencies]\npyth-solana-receiver-sdk = \"0.3\"\n",
+            ),
+            (
+                "programs/lending/src/oracle.rs",
+                "use pyth_sdk_solana::load_price_feed_from_account_info;\n",
+            ),
+            (
+                "app/src/prices.ts",
+                "import { HermesClient } from \"@pythnetwork/hermes-client\";\n",
+            ),
+        ]);
+
+        let found: Vec<(&str, Option<OracleModel>, Vec<&str>)> = findings
+            .iter()
+            .map(|f| {
+                let evidence = f.evidence.iter().map(String::as_str).collect();
+                (f.program.as_str(), f.model, evidence)
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (
+                    "Pyth Solana Receiver",
+                    Some(OracleModel::Pull),
+                    vec!["app/src/prices.ts:1", "programs/perps/Cargo.toml:2"]
+                ),
+                (
+                    "Pyth Oracle (legacy)",
+                    Some(OracleModel::Push),
+                    vec!["programs/lending/src/oracle.rs:1"]
+                ),
+            ]
+        );
+        assert!(findings[0].apro.as_ref().unwrap().contains("Hermes"));
+        assert!(!findings[1].apro.as_ref().unwrap().contains("Hermes"));
+    }
+
+    #[test]
+    fn test_client_oracle_sdks_found() {
+        let findings = scan(&[
+            (
+                "app/package.json",
+                r#"{
+  "dependencies": {
+    "@pythnetwork/client": "^2.19.0",
+    "@switchboard-xyz/solana.js": "^3.2.5",
+    "@switchboard-xyz/common": "^2.5.0",
+    "@chainlink/solana-sdk": "^0.2.2"
+  }
+}
+"#,
+            ),
+            (
+                "app/src/feeds.cjs",
+                "const { OCR2Feed } = require(\"@chainlink/solana-sdk\");\n",
+            ),
+            (
+                "programs/vault/src/lib.rs",
+                "use switchboard_solana::AggregatorAccountData;\n",
+            ),
+        ]);
+
+        let found: Vec<(&str, Vec<&str>, bool)> = findings
+            .iter()
+            .map(|f| {
+                let evidence = f.evidence.iter().map(String::as_str).collect();
+                (f.program.as_str(), evidence, f.client_only)
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("Pyth Oracle (legacy)", vec!["app/package.json:3"], true),
+                ("Switchboard On-Demand", vec!["app/package.json:5"], true),
+                (
+                    "Switchboard V2",
+                    vec!["app/package.json:4", "programs/vault/src/lib.rs:1"],
+                    false
+                ),
+                (
+                    "Chainlink Data Feeds",
+                    vec!["app/package.json:6", "app/src/feeds.cjs:1"],
+                    true
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_confidence_combines_evidence() {
+        let dir = project(&[
+            ("Cargo.toml", "[dependencies]\nmpl-core = \"0.8\"\n"),
+            (
+                "programs/nft/Cargo.toml",
+                "[dependencies]\nmpl-core = \"0.8\"\n",
+            ),
+            (
+                "programs/nft/src/mint.rs",
+                "mpl_core::instructions::CreateV1CpiBuilder::new(&core_program).invoke()?;\n",
+            ),
+            ("src/lib.rs", "use pyth_sdk_solana::PriceFeed;\n"),
+            (
+                "src/oracle.rs",
+                "let feed = pyth_sdk_solana::load_price_feed_from_account_info(&account)?;\n",
+            ),
+            (
+                "app/index.ts",
+                "const CHAINLINK = \"HEvSKofvBgfaexv23kMabbYqxasxU3mQ4ibBMEmJWHny\";\n",
+            ),
+        ]);
+
+        let scores = |options: &ScanOptions| -> Vec<(String, u8)> {
+            scan_ecosystem(dir.path(), options)
+                .into_iter()
+                .map(|f| (f.program, f.confidence))
+                .collect()
+        };
+        assert_eq!(
+            scores(&ScanOptions::default()),
+            vec![
+                ("Metaplex Core".to_string(), 94),
+                ("Pyth Oracle (legacy)".to_string(), 96),
+                ("Chainlink Data Feeds".to_string(), 90),
+            ]
+        );
+        let options = ScanOptions {
+            min_confidence: 80,
+            ..ScanOptions::default()
+        };
+        assert_eq!(scores(&options).len(), 3);
+        let options = ScanOptions {
+            min_confidence: 95,
+            ..ScanOptions::default()
+        };
+        assert_eq!(
+            scores(&options),
+            vec![("Pyth Oracle (legacy)".to_string(), 96)]
+        );
+
+        use EvidenceKind::*;
+        assert_eq!(EvidenceKind::combine(&[Manifest]), 60);
+        assert_eq!(EvidenceKind::combine(&[Manifest, CallSite]), 94);
+        assert_eq!(EvidenceKind::combine(&[]), 0);
+
+        assert!(is_import("pub use anchor_spl::token::Token;"));
+        assert!(is_import(
+            "import { PythHttpClient } from \"@pythnetwork/client\";"
+        ));
+        assert!(is_import(
+            "const sb = require(\"@switchboard-xyz/solana.js\");"
+        ));
+        assert!(!is_import(
+            "let feed = load_price_feed_from_account_info(&account)?;"
+        ));
+    }
+
+    /// Finds an in-house oracle wrapper by its crate path, under `program`.
+    struct WrapperDetector {
+        program: &'static str,
+    }
+
+    impl Detector for WrapperDetector {
+        fn detect(&self, ctx: &ScanContext) -> Vec<Detection> {
+            ctx.content
+                .match_indices("acme_oracle::")
+                .map(|(start, marker)| {
+                    let location = ctx.locate(start..start + marker.len());
+                    let mut detection =
+                        Detection::new(self.program, EvidenceKind::CallSite, location);
+                    detection.note = Some("In-house wrapper around Pyth.".to_string());
+                    detection.provider = Some(Provider::Custom("Acme".to_string()));
+                    detection
+                })
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test_registered_detectors_run_with_the_catalog() {
+        let dir = project(&[(
+            "programs/vault/src/lib.rs",
+            "use pyth_sdk_solana::PriceFeed;\nlet price = acme_oracle::read(&feed)?;\n",
+        )]);
+
+        let mut scanner = Scanner::new();
+        scanner.register(WrapperDetector {
+            program: "Acme Oracle",
+        });
+        let findings = scanner.scan(dir.path(), &ScanOptions::default());
+        assert_eq!(
+            summarize(&findings),
+            vec![
+                (
+                    "Pyth Oracle (legacy)",
+                    Availability::Unverified,
+                    vec!["programs/vault/src/lib.rs:1"]
+                ),
+                (
+                    "Acme Oracle",
+                    Availability::Unverified,
+                    vec!["programs/vault/src/lib.rs:2"]
+                ),
+            ]
+        );
+        assert_eq!(findings[1].note, "In-house wrapper around Pyth.");
+        assert_eq!(findings[1].program_id, None);
+        assert_eq!(findings[1].locations[0].column, 13);
+        assert_eq!(findings[1].provider.as_ref().unwrap().to_string(), "Acme");
+
+        // Providers outside the catalog are written as their name, like the rest
+        let json = serde_json::to_value([&findings[0].provider, &findings[1].provider]).unwrap();
+        assert_eq!(json, serde_json::json!(["pyth", "Acme"]));
+        let read: Vec<Provider> = serde_json::from_value(json).unwrap();
+        assert_eq!(
+            read,
+            vec![Provider::Pyth, Provider::Custom("Acme".to_string())]
+        );
+
+        // A detector naming a catalogued program adds to that program's finding
+        let mut scanner = Scanner::new();
+        scanner.register(WrapperDetector {
+            program: "Pyth Oracle (legacy)",
+        });
+        let findings = scanner.scan(dir.path(), &ScanOptions::default());
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].uses, 2);
+        assert!(findings[0].program_id.is_some());
+        assert_ne!(findings[0].note, "In-house wrapper around Pyth.");
+    }
+
+    #[test]
+    fn test_scan_iter_yields_each_file() {
+        let dir = project(&[
+            (
+                "programs/vault/src/lib.rs",
+                "use pyth_sdk_solana::PriceFeed;\nlet price = acme_oracle::read(&feed)?;\n",
+            ),
+            (
+                "programs/vault/src/math.rs",
+                "pub fn mul(a: u64) -> u64 { a }\n",
+            ),
+            (
+                "programs/vault/src/refresh.rs",
+                "use pyth_sdk_solana::load_price_feed_from_account_info;\n",
+            ),
+        ]);
+        let mut scanner = Scanner::new();
+        scanner.register(WrapperDetector {
+            program: "Acme Oracle",
+        });
+        let options = ScanOptions {
+            no_cache: true,
+            ..ScanOptions::default()
+        };
+
+        // Files without uses are skipped, and each file reports on its own
+        let mut scan = scanner.scan_iter(dir.path(), &options);
+        let first = scan.next().unwrap();
+        assert_eq!(first.file, "programs/vault/src/lib.rs");
+        assert_eq!(
+            summarize(&first.findings),
+            vec![
+                (
+                    "Pyth Oracle (legacy)",
+                    Availability::Unverified,
+                    vec!["programs/vault/src/lib.rs:1"]
+                ),
+                (
+                    "Acme Oracle",
+                    Availability::Unverified,
+                    vec!["programs/vault/src/lib.rs:2"]
+                ),
+            ]
+        );
+        let second = scan.next().unwrap();
+        assert_eq!(second.file, "programs/vault/src/refresh.rs");
+        assert_eq!(second.findings.len(), 1);
+        assert_eq!(second.findings[0].uses, 1);
+
+        // Whatever was streamed, the end result is the whole scan's
+        assert_eq!(scan.findings(), scanner.scan(dir.path(), &options));
+        assert_eq!(scanner.scan_iter(dir.path(), &options).count(), 2);
+    }
+
+    #[test]
+    fn test_config_rules_run_with_the_catalog() {
+        let dir = project(&[
+            (
+                "programs/vault/src/lib.rs",
+                "use acme_oracle::Feed;\n// acme_oracle::legacy is gone\nlet price = acme_oracle::read(&feed)?;\n",
+            ),
+            ("app/src/index.ts", "const feed = acme_oracle::read;\n"),
+            ("programs/vault/src/keeper.rs", "let quote = AcmeQuote::latest();\n"),
+        ]);
+        let rules = toml::from_str::<HashMap<String, Vec<Rule>>>(
+            r#"
+[[rules]]
+name = "Acme Oracle"
+files = ["programs/"]
+pattern = 'acme_oracle::\w+'
+provider = "acme"
+confidence = 50
+suggestion = "Point acme_oracle at the APRO feed accounts."
+
+[[rules]]
+name = "Acme Quotes"
+pattern = 'AcmeQuote::'
+suggestion = "Check that the quote service runs on SOON."
+"#,
+        )
+        .unwrap()
+        .remove("rules")
+        .unwrap();
+
+        let findings = scan_ecosystem(
+            dir.path(),
+            &ScanOptions {
+                rules: &rules,
+                ..ScanOptions::default()
+            },
+        );
+        assert_eq!(
+            summarize(&findings),
+            vec![
+                (
+                    "Acme Quotes",
+                    Availability::Unverified,
+                    vec!["programs/vault/src/keeper.rs:1"]
+                ),
+                (
+                    "Acme Oracle",
+                    Availability::Unverified,
+                    vec!["programs/vault/src/lib.rs:1", "programs/vault/src/lib.rs:3"]
+                ),
+            ]
+        );
+        let acme = &findings[1];
+        assert_eq!(acme.provider, Some(Provider::Custom("acme".to_string())));
+        assert_eq!(
+            acme.apro.as_deref(),
+            Some("Point acme_oracle at the APRO feed accounts.")
+        );
+        assert_eq!(
+            acme.signals,
+            vec![EvidenceKind::Import, EvidenceKind::CallSite]
+        );
+        // Both kinds count at the rule's confidence rather than their own weight
+        assert_eq!(acme.confidence, 75);
+        assert_eq!(
+            findings[0].note,
+            "Check that the quote service runs on SOON."
+        );
+        assert_eq!(findings[0].confidence, EvidenceKind::CallSite.weight());
+
+        for (rule, error) in [
+            ("pattern = 'acme_oracle::('", "regex parse error"),
+            (
+                "pattern = 'acme'\nconfidence = 150",
+                "confidence is out of 100",
+            ),
+            ("pattern = 'acme'\nseverity = 3", "unknown field"),
+        ] {
+            let parsed = toml::from_str::<Rule>(&format!("name = \"Acme\"\n{}", rule));
+            assert!(parsed.unwrap_err().to_string().contains(error), "{}", rule);
+        }
+    }
+
+    #[test]
+    fn test_cpi_into_oracle_program_flagged() {
+        let findings = scan(&[(
+            "lib.rs",
+            r#"pub fn refresh(ctx: Context<Refresh>) -> Result<()> {
+    let program = ctx.accounts.switchboard.to_account_info();
+    switchboard_solana::cpi::aggregator_open_round(CpiContext::new(program, accounts), params)?;
+    Ok(())
+}
+"#,
+        )]);
+
+        assert_eq!(findings[0].program, "Switchboard V2");
+        assert_eq!(findings[0].cpi_calls, vec!["lib.rs:3 in refresh"]);
+    }
+
+    #[test]
+    fn test_effort_estimated() {
+        let findings = scan(&[
+            (
+                "Cargo.toml",
+                "[dependencies]\nswitchboard-solana = \"0.29\"\n",
+            ),
+            (
+                "lib.rs",
+                r#"use switchboard_solana::AggregatorAccountData;
+use anchor_spl::token::Token;
+
+#[derive(Accounts)]
+pub struct Refresh<'info> {
+    #[account(owner = switchboard_solana::ID)]
+    pub aggregator: AccountLoader<'info, AggregatorAccountData>,
+}
+
+pub fn refresh(ctx: Context<Refresh>) -> Result<()> {
+    switchboard_solana::cpi::aggregator_open_round(CpiContext::new(program, accounts), params)?;
+    Ok(())
+}
+"#,
+            ),
+            ("feeds.rs", "use pyth_sdk_solana::PriceFeed;\n"),
+        ]);
+        let effort = |program: &str| {
+            findings
+                .iter()
+                .find(|f| f.program == program)
+                .unwrap()
+                .effort
+                .clone()
+        };
+
+        assert_eq!(
+            effort("Switchboard V2"),
+            Some(Effort {
+                level: EffortLevel::Significant,
+                lines: 16,
+                cpi_calls: 1,
+                account_constraints: 1,
+                call_sites: 1,
+                references: 1,
+            })
+        );
+        let pyth = effort("Pyth Oracle (legacy)").unwrap();
+        assert_eq!((pyth.level, pyth.lines), (EffortLevel::Trivial, 3));
+        // Nothing to move off a program SOON has as it is
+        assert_eq!(effort("SPL Token"), None);
+
+        assert_eq!(
+            complexity(&findings),
+            Some(Complexity {
+                score: 27,
+                level: EffortLevel::Significant,
+                lines: 19,
+            })
+        );
+        assert_eq!(complexity(&findings[..0]), None);
+    }
+
+    #[test]
+    fn test_every_use_counted() {
+        let source = "use pyth_sdk_solana::load_price_feed_from_account_info;\n".repeat(8);
+        let findings = scan(&[("feeds.rs", &source)]);
+
+        assert_eq!(findings[0].uses, 8);
+        assert_eq!(findings[0].evidence.len(), MAX_EVIDENCE);
+        assert_eq!(findings[0].evidence[4], "feeds.rs:5");
+
+        // An IDL naming the same account type twice is one place
+        let findings = scan(&[(
+            "idls/vault.json",
+            r#"{ "accounts": [{ "name": "PriceUpdateV2" }, { "name": "PriceUpdateV2" }] }"#,
+        )]);
+        assert_eq!(findings[0].uses, 1);
+        assert_eq!(findings[0].locations.len(), 1);
+        assert_eq!(findings[0].signals, vec![EvidenceKind::Idl]);
+    }
+
+    #[test]
+    fn test_parallel_scan_in_path_order() {
+        let names: Vec<String> = (0..40).map(|i| format!("feed_{:02}.rs", i)).collect();
+        let files: Vec<(&str, &str)> = names
+            .iter()
+            .map(|name| (name.as_str(), "use pyth_sdk_solana::PriceFeed;\n"))
+            .collect();
+        let dir = project(&files);
+
+        for _ in 0..3 {
+            let findings = scan_ecosystem(dir.path(), &ScanOptions::default());
+            assert_eq!(findings[0].uses, 40);
+            assert_eq!(
+                findings[0].evidence,
+                names[..MAX_EVIDENCE]
+                    .iter()
+                    .map(|name| format!("{}:1", name))
+                    .collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn test_dev_dependency_marked_dev_only() {
+        let findings = scan(&[
+            (
+                "Cargo.toml",
+                "[dependencies]\nmpl-core = \"0.8\"\n\n[dev-dependencies]\nswitchboard-on-demand = \"0.1\"\n\n[target.'cfg(unix)'.build-dependencies]\nchainlink-solana = \"1\"\n",
+            ),
+            (
+                "tests/mock.rs",
+                "use switchboard_on_demand::PullFeedAccountData;\n",
+            ),
+        ]);
+
+        let dev_only: Vec<(&str, Vec<&str>, bool)> = findings
+            .iter()
+            .map(|f| {
+                let evidence = f.evidence.iter().map(String::as_str).collect();
+                (f.program.as_str(), evidence, f.dev_only)
+            })
+            .collect();
+        assert_eq!(
+            dev_only,
+            vec![
+                ("Metaplex Core", vec!["Cargo.toml:2"], false),
+                (
+                    "Switchboard On-Demand",
+                    vec!["Cargo.toml:5 (dev-dependencies)", "tests/mock.rs:1"],
+                    true
+                ),
+                (
+                    "Chainlink Data Feeds",
+                    vec!["Cargo.toml:8 (build-dependencies)"],
+                    false
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scan_restricted_to_paths() {
+        let dir = project(&[
+            (
+                "programs/amm/src/lib.rs",
+                "use pyth_sdk_solana::PriceFeed;\n",
+            ),
+            ("programs/vault/src/lib.rs", "use mpl_core::ID;\n"),
+        ]);
+
+        let cases = [
+            ("programs/amm/src/**", "programs/amm/src/lib.rs:1"),
+            ("programs/vault", "programs/vault/src/lib.rs:1"),
+        ];
+        for (pattern, evidence) in cases {
+            let options = ScanOptions {
+                paths: &[pattern.to_string()],
+                ..ScanOptions::default()
+            };
+            let findings = scan_ecosystem(dir.path(), &options);
+            assert_eq!(findings.len(), 1, "{}", pattern);
+            assert_eq!(findings[0].evidence, vec![evidence], "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn test_scan_results_cached_by_content() {
+        let dir = project(&[("lib.rs", "use mpl_core::ID;\n")]);
+        let cache = dir.path().join(".soon-migrate").join("cache");
+
+        let options = ScanOptions {
+            no_cache: true,
+            ..ScanOptions::default()
+        };
+        let fresh = scan_ecosystem(dir.path(), &options);
+        assert!(!cache.exists());
+
+        assert_eq!(scan_ecosystem(dir.path(), &ScanOptions::default()), fresh);
+        assert_eq!(fs::read_dir(&cache).unwrap().count(), 2);
+        assert_eq!(scan_ecosystem(dir.path(), &ScanOptions::default()), fresh);
+
+        fs::write(
+            dir.path().join("lib.rs"),
+            "\nuse pyth_sdk_solana::PriceFeed;\n",
+        )
+        .unwrap();
+        let findings = scan_ecosystem(dir.path(), &ScanOptions::default());
+        assert_eq!(findings[0].program, "Pyth Oracle (legacy)");
+        assert_eq!(findings[0].evidence, vec!["lib.rs:2"]);
+    }
+
+    #[test]
+    fn test_scan_sized_to_project() {
+        let dir = project(&[
+            ("programs/vault/src/lib.rs", "use mpl_core::ID;\n"),
+            ("vendor/pyth/lib.rs", "use pyth_sdk_solana::PriceFeed;\n"),
+            ("README.md", "Reads Pyth prices.\n"),
+        ]);
+        let exclude = ["vendor/".to_string()];
+        let options = ScanOptions {
+            exclude: &exclude,
+            ..ScanOptions::default()
+        };
+
+        let size = ProjectSize::measure(dir.path(), &options);
+        assert_eq!(
+            size,
+            ProjectSize {
+                files: 1,
+                bytes: 18
+            }
+        );
+        assert!(!size.is_large());
+        let findings = scan_ecosystem(dir.path(), &options.sized_for(&size));
+        let programs: Vec<&str> = findings.iter().map(|f| f.program.as_str()).collect();
+        assert_eq!(programs, vec!["Metaplex Core"]);
+        // A small project is scanned one file at a time, without the cache
+        assert!(!dir.path().join(".soon-migrate").exists());
+
+        let large = ProjectSize {
+            files: LARGE_FILES,
+            bytes: 0,
+        };
+        let tuned = options.sized_for(&large);
+        assert!(tuned.parallel && !tuned.no_cache);
+        let no_cache = ScanOptions {
+            no_cache: true,
+            ..options
+        };
+        assert!(no_cache.sized_for(&large).no_cache);
+        assert_eq!(scan_ecosystem(dir.path(), &tuned), findings);
+    }
+
+    #[test]
+    fn test_test_code_marked_or_skipped() {
+        let dir = project(&[
+            (
+                "lib.rs",
+                r#"use mpl_core::ID;
+
+#[cfg(test)]
+#[allow(unused)]
+mod tests {
+    use pyth_sdk_solana::PriceFeed;
+}
+"#,
+            ),
+            ("oracle_test.rs", "use pyth_sdk_solana::PriceFeed;\n"),
+        ]);
+
+        let findings = scan_ecosystem(dir.path(), &ScanOptions::default());
+        assert_eq!(findings[1].program, "Pyth Oracle (legacy)");
+        assert_eq!(findings[1].evidence, vec!["lib.rs:6", "oracle_test.rs:1"]);
+        assert!(findings[1].dev_only);
+        assert!(!findings[0].dev_only);
+
+        let options = ScanOptions {
+            skip_tests: true,
+            ..ScanOptions::default()
+        };
+        let findings = scan_ecosystem(dir.path(), &options);
+        let programs: Vec<&str> = findings.iter().map(|f| f.program.as_str()).collect();
+        assert_eq!(programs, vec!["Metaplex Core"]);
+    }
+
+    #[test]
+    fn test_uses_attributed_to_crates_and_owners() {
+        let findings = scan(&[
+            ("programs/amm/Cargo.toml", "[package]\nname = \"amm\"\n"),
+            (
+                "programs/amm/src/state/oracle.rs",
+                "use pyth_sdk_solana::PriceFeed;\n",
+            ),
+            (
+                "programs/lending/Cargo.toml",
+                "[package]\nname = \"lending\"\n",
+            ),
+            (
+                "programs/lending/src/state/oracle.rs",
+                "use pyth_sdk_solana::load_price_feed_from_account_info;\n",
+            ),
+            ("Cargo.toml", "[workspace]\nmembers = [\"programs/*\"]\n"),
+            (
+                "client.ts",
+                "import { PythConnection } from \"@pythnetwork/client\";\n",
+            ),
+            (
+                ".github/CODEOWNERS",
+                "*.ts @acme/frontend\n/programs/ @acme/onchain\n/programs/lending/ @acme/lending\n",
+            ),
+        ]);
+
+        assert_eq!(findings[0].uses, 3);
+        assert_eq!(findings[0].crates, vec!["amm", "lending"]);
+        assert_eq!(
+            findings[0].owners,
+            vec!["@acme/frontend", "@acme/lending", "@acme/onchain"]
+        );
+    }
+
+    #[test]
+    fn test_program_catalog() {
+        let mut names = HashMap::new();
+        let mut addresses = HashMap::new();
+        for program in programs() {
+            assert!(
+                names.insert(program.name.as_str(), ()).is_none(),
+                "{} is listed twice",
+                program.name
+            );
+            if let Some(id) = program
+                .program_id
+                .as_deref()
+                .filter(|_| !program.marker_only)
+            {
+                assert!(
+                    addresses.insert(id, &program.name).is_none(),
+                    "{} shares its program_id; mark it marker_only",
+                    program.name
+                );
+            }
+            assert!(
+                program.program_id.is_some() || !program.markers.is_empty(),
+                "{} can only be found by its markers, and has none",
+                program.name
+            );
+            assert!(
+                program.model.is_none() || program.provider.is_some(),
+                "{} has an oracle model but no provider",
+                program.name
+            );
+            assert_eq!(
+                program.provider.is_some(),
+                program.apro.is_some(),
+                "{} needs APRO guidance exactly when it is an oracle",
+                program.name
+            );
+            for id in program.program_id.iter().chain(&program.soon_program_id) {
+                let bytes = bs58::decode(id).into_vec().unwrap();
+                assert_eq!(bytes.len(), 32, "{} has a malformed address", program.name);
+            }
+            assert_eq!(
+                program.availability == Availability::Moved,
+                program.soon_program_id.is_some(),
+                "{} needs soon_program_id exactly when it has moved",
+                program.name
+            );
+        }
+    }
+}
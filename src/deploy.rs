@@ -0,0 +1,479 @@
+use crate::endpoints::{Endpoints, SoonNetwork};
+use crate::errors::MigrationError;
+use crate::migration::apply_plan;
+use crate::plan::{diff_toml, Edit, Plan, PLAN_VERSION};
+use crate::result::{Change, ChangeKind};
+use crate::settings::CONFIG_FILE_NAME;
+use crate::workspace::native_program_crates;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{BufRead, BufReader, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Deploys the project's programs to SOON `network` and records the IDs they
+/// landed at: in `[programs.<network>]` of Anchor.toml, and in
+/// `[program_ids]` of soon-migrate.toml so later migrations keep them.
+/// Anchor projects go through `anchor deploy`, which must already point at
+/// SOON; native programs through `solana program deploy`. Both get the
+/// endpoint with its `${VAR}` references expanded, but never on their
+/// command line, where other users of the machine could read it: `solana`
+/// reads it from a temporary CLI config, and Anchor from Anchor.toml,
+/// expanded for the length of the deploy. Everything shown keeps the
+/// references. Returns each program with its ID.
+pub fn deploy(
+    project: &Path,
+    network: SoonNetwork,
+    endpoints: &Endpoints,
+    verbose: bool,
+) -> Result<Vec<(String, String)>, MigrationError> {
+    let url = endpoints.url(network);
+    let resolved = endpoints.resolved_url(network)?;
+    let redact = |text: &str| text.replace(&resolved, url);
+    let deployed = if project.join("Anchor.toml").exists() {
+        let cluster = provider_cluster(project)?;
+        if cluster != url {
+            return Err(MigrationError::ConfigError(format!(
+                "provider.cluster in Anchor.toml is {}, not SOON {} ({}); migrate the project first",
+                cluster, network, url
+            )));
+        }
+        // Anchor does not expand the references Anchor.toml keeps, and takes
+        // the cluster from nowhere but the file or its command line
+        let _expanded = (resolved != url)
+            .then(|| ExpandedCluster::new(project, &resolved))
+            .transpose()?;
+        run(project, "anchor", &["deploy"], None, &redact)?
+    } else {
+        let home = std::env::var_os("HOME").map(PathBuf::from);
+        let config = solana_config(&resolved, home.as_deref())?;
+        let config_path = config.path().to_string_lossy().into_owned();
+        let mut deployed = Vec::new();
+        for program in native_program_crates(project) {
+            let binary = format!("target/deploy/{}.so", program.name);
+            if !project.join(&binary).exists() {
+                return Err(MigrationError::DeployFailed(format!(
+                    "{} is missing; build {} with cargo build-sbf first",
+                    binary, program.name
+                )));
+            }
+            deployed.extend(run(
+                project,
+                "solana",
+                &["program", "deploy", &binary, "--config", &config_path],
+                Some(&program.name),
+                &redact,
+            )?);
+        }
+        deployed
+    };
+
+    record(project, network, &deployed, verbose)?;
+    Ok(deployed)
+}
+
+fn provider_cluster(project: &Path) -> Result<String, MigrationError> {
+    let content = fs::read_to_string(project.join("Anchor.toml"))
+        .map_err(|e| MigrationError::ReadFailed(e.to_string()))?;
+    let anchor: toml::Value = content
+        .parse()
+        .map_err(|e: toml::de::Error| MigrationError::TomlParseError(e.to_string()))?;
+    Ok(anchor
+        .get("provider")
+        .and_then(|provider| provider.get("cluster"))
+        .and_then(|cluster| cluster.as_str())
+        .unwrap_or_default()
+        .to_string())
+}
+
+/// Anchor.toml with `provider.cluster` expanded while Anchor deploys. The
+/// file is put back as it was when this is dropped, whether or not the
+/// deploy succeeded; Ctrl-C stops Anchor but not soon-migrate, so it cannot
+/// leave the key behind either.
+struct ExpandedCluster {
+    path: PathBuf,
+    original: String,
+}
+
+impl ExpandedCluster {
+    fn new(project: &Path, resolved: &str) -> Result<Self, MigrationError> {
+        let path = project.join("Anchor.toml");
+        let original =
+            fs::read_to_string(&path).map_err(|e| MigrationError::ReadFailed(e.to_string()))?;
+        let mut anchor: toml::Value = original
+            .parse()
+            .map_err(|e: toml::de::Error| MigrationError::TomlParseError(e.to_string()))?;
+        if let Some(provider) = anchor.get_mut("provider").and_then(|p| p.as_table_mut()) {
+            provider.insert(
+                "cluster".to_string(),
+                toml::Value::String(resolved.to_string()),
+            );
+        }
+        let expanded =
+            toml::to_string(&anchor).map_err(|e| MigrationError::TomlParseError(e.to_string()))?;
+
+        #[cfg(unix)]
+        // SAFETY: only swaps the disposition of SIGINT; `run` restores the
+        // default in the child before it starts
+        unsafe {
+            libc::signal(libc::SIGINT, libc::SIG_IGN);
+        }
+        let guard = ExpandedCluster { path, original };
+        fs::write(&guard.path, expanded)
+            .map_err(|e| MigrationError::WriteFailed(format!("Anchor.toml: {}", e)))?;
+        Ok(guard)
+    }
+}
+
+impl Drop for ExpandedCluster {
+    fn drop(&mut self) {
+        let _ = fs::write(&self.path, &self.original);
+        #[cfg(unix)]
+        // SAFETY: as in `new`
+        unsafe {
+            libc::signal(libc::SIGINT, libc::SIG_DFL);
+        }
+    }
+}
+
+/// A temporary Solana CLI config pointing at `url`, for `--config`. The
+/// rest comes from the config under `home`, so the keypair stays the user's.
+fn solana_config(
+    url: &str,
+    home: Option<&Path>,
+) -> Result<tempfile::NamedTempFile, MigrationError> {
+    let mut config = home
+        .and_then(|home| fs::read_to_string(home.join(".config/solana/cli/config.yml")).ok())
+        .and_then(|content| serde_yaml::from_str::<serde_yaml::Mapping>(&content).ok())
+        .unwrap_or_default();
+    if !config.contains_key("keypair_path") {
+        // The Solana CLI's own default; without it the whole file is ignored
+        let home = home.ok_or_else(|| {
+            MigrationError::DeployFailed(
+                "cannot find the Solana CLI keypair without HOME".to_string(),
+            )
+        })?;
+        let keypair = home.join(".config/solana/id.json");
+        config.insert(
+            "keypair_path".into(),
+            keypair.to_string_lossy().into_owned().into(),
+        );
+    }
+    config.insert("json_rpc_url".into(), url.into());
+    // Empty means derived from the RPC URL
+    config.insert("websocket_url".into(), "".into());
+
+    let write =
+        |e: std::io::Error| MigrationError::WriteFailed(format!("Solana CLI config: {}", e));
+    // Created readable by the user only
+    let mut file = tempfile::Builder::new()
+        .prefix("soon-migrate-")
+        .suffix(".yml")
+        .tempfile()
+        .map_err(write)?;
+    let content = serde_yaml::to_string(&config)
+        .map_err(|e| MigrationError::DeployFailed(format!("Solana CLI config: {}", e)))?;
+    std::io::Write::write_all(&mut file, content.as_bytes()).map_err(write)?;
+    Ok(file)
+}
+
+/// Runs a deploy command in the project, passing its output through as it
+/// comes, and returns the programs it reports deploying. `name` names the
+/// program when the command deploys only one. Whatever is shown goes
+/// through `redact` first, so an expanded endpoint is never printed.
+fn run(
+    project: &Path,
+    program: &str,
+    args: &[&str],
+    name: Option<&str>,
+    redact: &dyn Fn(&str) -> String,
+) -> Result<Vec<(String, String)>, MigrationError> {
+    let command = redact(&format!("{} {}", program, args.join(" ")));
+    let mut command_line = Command::new(program);
+    command_line
+        .args(args)
+        .current_dir(project)
+        .stdout(Stdio::piped());
+    #[cfg(unix)]
+    // SAFETY: signal() is async-signal-safe, as pre_exec requires
+    unsafe {
+        std::os::unix::process::CommandExt::pre_exec(&mut command_line, || {
+            // Ctrl-C stops the command even while soon-migrate ignores it
+            libc::signal(libc::SIGINT, libc::SIG_DFL);
+            Ok(())
+        });
+    }
+    let mut child = command_line.spawn().map_err(|e| match e.kind() {
+        ErrorKind::NotFound => {
+            MigrationError::DeployFailed(format!("{} is not installed", program))
+        }
+        _ => MigrationError::DeployFailed(format!("{}: {}", command, e)),
+    })?;
+
+    let mut output = Vec::new();
+    if let Some(stdout) = child.stdout.take() {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            println!("{}", redact(&line));
+            output.push(line);
+        }
+    }
+
+    let status = child
+        .wait()
+        .map_err(|e| MigrationError::DeployFailed(format!("{}: {}", command, e)))?;
+    if !status.success() {
+        return Err(MigrationError::DeployFailed(format!(
+            "{} exited with {}",
+            command, status
+        )));
+    }
+    Ok(program_ids(&output, name))
+}
+
+/// Programs and their IDs from the output of `anchor deploy`, which names
+/// each program before deploying it, or of `solana program deploy`, which
+/// deploys the one program `name`.
+fn program_ids(output: &[String], name: Option<&str>) -> Vec<(String, String)> {
+    let mut current = name.map(str::to_string);
+    let mut ids = Vec::new();
+    for line in output {
+        if let Some(program) = line
+            .trim()
+            .strip_prefix("Deploying program \"")
+            .and_then(|rest| rest.split('"').next())
+        {
+            current = Some(program.to_string());
+        } else if let Some(id) = line.trim().strip_prefix("Program Id:") {
+            if let Some(program) = &current {
+                ids.push((program.clone(), id.trim().to_string()));
+            }
+        }
+    }
+    ids
+}
+
+/// Writes the deployed IDs into Anchor.toml and soon-migrate.toml, backed
+/// up like any migration so `--restore` can undo them.
+fn record(
+    project: &Path,
+    network: SoonNetwork,
+    deployed: &[(String, String)],
+    verbose: bool,
+) -> Result<(), MigrationError> {
+    let mut edits = Vec::new();
+    let mut changes = Vec::new();
+    let ids: BTreeMap<&str, &str> = deployed
+        .iter()
+        .map(|(name, id)| (name.as_str(), id.as_str()))
+        .collect();
+
+    if project.join("Anchor.toml").exists() {
+        let key = ["programs", network.as_str()];
+        edits.extend(set_keys(project, "Anchor.toml", &key, &ids, &mut changes)?);
+    }
+    if project.join(CONFIG_FILE_NAME).exists() {
+        edits.extend(set_keys(
+            project,
+            CONFIG_FILE_NAME,
+            &["program_ids"],
+            &ids,
+            &mut changes,
+        )?);
+    } else if !ids.is_empty() {
+        edits.push(Edit::append(CONFIG_FILE_NAME, "[program_ids]"));
+        for (name, id) in &ids {
+            edits.push(Edit::append(
+                CONFIG_FILE_NAME,
+                &format!("{} = \"{}\"", name, id),
+            ));
+            changes.push(recorded(
+                CONFIG_FILE_NAME,
+                &format!("program_ids.{}", name),
+                id,
+            ));
+        }
+    }
+
+    if edits.is_empty() {
+        return Ok(());
+    }
+    let plan = Plan {
+        version: PLAN_VERSION,
+        network,
+        edits,
+        changes,
+        warnings: Vec::new(),
+        compatibility: Vec::new(),
+        deployments: Vec::new(),
+        inputs: BTreeMap::new(),
+    };
+    apply_plan(&project.to_string_lossy(), &plan, verbose)
+}
+
+/// Edits setting `ids` in the table at `key` of the TOML `file`, for the
+/// programs whose recorded ID differs.
+fn set_keys(
+    project: &Path,
+    file: &str,
+    key: &[&str],
+    ids: &BTreeMap<&str, &str>,
+    changes: &mut Vec<Change>,
+) -> Result<Vec<Edit>, MigrationError> {
+    let content = fs::read_to_string(project.join(file))
+        .map_err(|e| MigrationError::ReadFailed(format!("{}: {}", file, e)))?;
+    let old: toml::Value = content
+        .parse()
+        .map_err(|e: toml::de::Error| MigrationError::TomlParseError(format!("{}: {}", file, e)))?;
+
+    let mut new = old.clone();
+    let mut table = new
+        .as_table_mut()
+        .ok_or_else(|| MigrationError::TomlParseError(file.to_string()))?;
+    for name in key {
+        table = table
+            .entry(*name)
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+            .as_table_mut()
+            .ok_or_else(|| {
+                MigrationError::TomlParseError(format!("{}: {} is not a table", file, name))
+            })?;
+    }
+    for (name, id) in ids {
+        if table.get(*name).and_then(|value| value.as_str()) != Some(*id) {
+            table.insert(name.to_string(), toml::Value::String(id.to_string()));
+            changes.push(recorded(file, &format!("{}.{}", key.join("."), name), id));
+        }
+    }
+
+    Ok(diff_toml(file, &old, &new))
+}
+
+fn recorded(file: &str, key: &str, id: &str) -> Change {
+    Change {
+        file: file.to_string(),
+        kind: ChangeKind::ProgramIdRemapped,
+        detail: format!("{} set to deployed ID {}", key, id),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_program_ids() {
+        let output: Vec<String> = [
+            "Deploying cluster: https://rpc.devnet.soo.network/rpc",
+            "Upgrade authority: ~/.config/solana/id.json",
+            "Deploying program \"counter\"...",
+            "Program path: target/deploy/counter.so...",
+            "Program Id: Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS",
+            "Deploying program \"vault\"...",
+            "Program Id: 8wWLP4YmLwFJT9kVo3Mhjwu8tkc4JyYUaSBV3JBXbDqk",
+            "Deploy success",
+        ]
+        .iter()
+        .map(|line| line.to_string())
+        .collect();
+
+        assert_eq!(
+            program_ids(&output, None),
+            vec![
+                (
+                    "counter".to_string(),
+                    "Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS".to_string()
+                ),
+                (
+                    "vault".to_string(),
+                    "8wWLP4YmLwFJT9kVo3Mhjwu8tkc4JyYUaSBV3JBXbDqk".to_string()
+                ),
+            ]
+        );
+        assert_eq!(
+            program_ids(&output[4..5], Some("native")),
+            vec![(
+                "native".to_string(),
+                "Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_record() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::write(dir.path().join("Cargo.toml"), "[workspace]\n").unwrap();
+        fs::write(
+            dir.path().join("Anchor.toml"),
+            "[provider]\ncluster = \"https://rpc.devnet.soo.network/rpc\"\nwallet = \"id.json\"\n\n\
+             [programs.devnet]\ncounter = \"Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS\"\n",
+        )
+        .unwrap();
+        let deployed = vec![(
+            "counter".to_string(),
+            "8wWLP4YmLwFJT9kVo3Mhjwu8tkc4JyYUaSBV3JBXbDqk".to_string(),
+        )];
+
+        record(dir.path(), SoonNetwork::Devnet, &deployed, false).unwrap();
+
+        let anchor = fs::read_to_string(dir.path().join("Anchor.toml")).unwrap();
+        assert!(anchor.contains("counter = \"8wWLP4YmLwFJT9kVo3Mhjwu8tkc4JyYUaSBV3JBXbDqk\""));
+        let settings = fs::read_to_string(dir.path().join(CONFIG_FILE_NAME)).unwrap();
+        assert_eq!(
+            settings,
+            "[program_ids]\ncounter = \"8wWLP4YmLwFJT9kVo3Mhjwu8tkc4JyYUaSBV3JBXbDqk\"\n"
+        );
+    }
+
+    #[test]
+    fn test_solana_config() {
+        let home = tempfile::TempDir::new().unwrap();
+        let url = "https://rpc.example.com/rpc?api-key=secret";
+
+        let config = solana_config(url, Some(home.path())).unwrap();
+        let written: serde_yaml::Mapping =
+            serde_yaml::from_str(&fs::read_to_string(config.path()).unwrap()).unwrap();
+        assert_eq!(written["json_rpc_url"].as_str(), Some(url));
+        assert_eq!(
+            written["keypair_path"].as_str().map(PathBuf::from),
+            Some(home.path().join(".config/solana/id.json"))
+        );
+
+        let cli = home.path().join(".config/solana/cli");
+        fs::create_dir_all(&cli).unwrap();
+        fs::write(
+            cli.join("config.yml"),
+            "json_rpc_url: https://api.devnet.solana.com\nwebsocket_url: ''\nkeypair_path: /keys/deployer.json\ncommitment: finalized\n",
+        )
+        .unwrap();
+        let config = solana_config(url, Some(home.path())).unwrap();
+        let written: serde_yaml::Mapping =
+            serde_yaml::from_str(&fs::read_to_string(config.path()).unwrap()).unwrap();
+        assert_eq!(written["json_rpc_url"].as_str(), Some(url));
+        assert_eq!(
+            written["keypair_path"].as_str(),
+            Some("/keys/deployer.json")
+        );
+        assert_eq!(written["commitment"].as_str(), Some("finalized"));
+    }
+
+    #[test]
+    fn test_expanded_cluster_restored() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let anchor = "[provider]\n# keyed endpoint\ncluster = \"https://rpc.example.com/rpc?api-key=${KEY}\"\nwallet = \"id.json\"\n";
+        fs::write(dir.path().join("Anchor.toml"), anchor).unwrap();
+
+        {
+            let _expanded =
+                ExpandedCluster::new(dir.path(), "https://rpc.example.com/rpc?api-key=secret")
+                    .unwrap();
+            assert_eq!(
+                provider_cluster(dir.path()).unwrap(),
+                "https://rpc.example.com/rpc?api-key=secret"
+            );
+        }
+        assert_eq!(
+            fs::read_to_string(dir.path().join("Anchor.toml")).unwrap(),
+            anchor
+        );
+    }
+}
@@ -0,0 +1,250 @@
+use crate::endpoints::SoonNetwork;
+use crate::errors::MigrationError;
+use crate::report::sha256_hex;
+use crate::rpc::Rpc;
+use crate::wallet::keypair_address;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+
+/// Loader of upgradeable programs, whose accounts point at the program data
+/// account holding the executable.
+const UPGRADEABLE_LOADER: &str = "BPFLoaderUpgradeab1e11111111111111111111111";
+
+/// Bytes before the executable in a program data account: its tag, the slot
+/// it was deployed at and the optional upgrade authority.
+const PROGRAM_DATA_HEADER: usize = 45;
+
+/// What SOON holds at a program's address.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeployStatus {
+    /// No account at all: the program was never deployed to this network.
+    Missing,
+    /// An account that is not a program, e.g. only funded.
+    NotExecutable,
+    /// A deployed program. `authority` is the upgrade authority of an
+    /// upgradeable program, `None` when it is immutable or not upgradeable.
+    Deployed { authority: Option<String> },
+}
+
+/// A program in the SOON table of Anchor.toml and what was found for it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeployedProgram {
+    pub name: String,
+    pub program_id: String,
+    pub status: DeployStatus,
+}
+
+/// Checks every program in `[programs.<network>]` of the project's
+/// Anchor.toml against SOON.
+pub fn verify(
+    project: &Path,
+    network: SoonNetwork,
+    rpc: &Rpc,
+) -> Result<Vec<DeployedProgram>, MigrationError> {
+    let content = fs::read_to_string(project.join("Anchor.toml"))
+        .map_err(|e| MigrationError::ReadFailed(e.to_string()))?;
+    let anchor: toml::Value = content
+        .parse()
+        .map_err(|e: toml::de::Error| MigrationError::TomlParseError(e.to_string()))?;
+    let Some(table) = anchor
+        .get("programs")
+        .and_then(|programs| programs.get(network.as_str()))
+        .and_then(|table| table.as_table())
+    else {
+        return Err(MigrationError::ConfigError(format!(
+            "Anchor.toml has no [programs.{}] table; migrate the project first",
+            network
+        )));
+    };
+
+    let mut programs = Vec::new();
+    for (name, id) in table {
+        let Some(id) = id.as_str() else {
+            continue;
+        };
+        let program = account(rpc, id)?;
+        let program_data = program["data"]["parsed"]["info"]["programData"]
+            .as_str()
+            .map(|address| account(rpc, address))
+            .transpose()?;
+        programs.push(DeployedProgram {
+            name: name.clone(),
+            program_id: id.to_string(),
+            status: status(&program, program_data.as_ref()),
+        });
+    }
+    Ok(programs)
+}
+
+/// A locally built program and the program SOON runs at its address.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProgramHash {
+    pub program_id: String,
+    /// SHA-256 of `target/deploy/<program>.so`.
+    pub local: String,
+    /// SHA-256 of the deployed executable, `None` when nothing is deployed.
+    pub on_chain: Option<String>,
+}
+
+impl ProgramHash {
+    pub fn matches(&self) -> bool {
+        self.on_chain.as_deref() == Some(self.local.as_str())
+    }
+}
+
+/// Compares the SHA-256 of the built `program` with that of the executable
+/// deployed on SOON. Trailing zero bytes are left out on both sides, since a
+/// program data account is usually larger than the program it holds.
+pub fn verify_program(
+    project: &Path,
+    network: SoonNetwork,
+    rpc: &Rpc,
+    program: &str,
+) -> Result<ProgramHash, MigrationError> {
+    let lib = program.replace('-', "_");
+    let binary = format!("target/deploy/{}.so", lib);
+    let local = fs::read(project.join(&binary)).map_err(|e| {
+        MigrationError::VerifyFailed(format!("{}: {}; build the program first", binary, e))
+    })?;
+    let program_id = program_id(project, network, program, &lib)?;
+
+    let on_chain = match rpc.account_data(&program_id)? {
+        Some((owner, data)) if owner == UPGRADEABLE_LOADER => {
+            // The program account holds a tag, then the program data address
+            let program_data = data
+                .get(4..36)
+                .map(|address| bs58::encode(address).into_string());
+            match program_data {
+                Some(address) => rpc
+                    .account_data(&address)?
+                    .and_then(|(_, data)| data.get(PROGRAM_DATA_HEADER..).map(<[u8]>::to_vec)),
+                None => None,
+            }
+        }
+        Some((_, data)) => Some(data),
+        None => None,
+    };
+
+    Ok(ProgramHash {
+        program_id,
+        local: sha256_hex(trim_zeros(&local)),
+        on_chain: on_chain.map(|executable| sha256_hex(trim_zeros(&executable))),
+    })
+}
+
+/// ID of `program` on SOON: its entry in `[programs.<network>]` of
+/// Anchor.toml, or else the address of its keypair in `target/deploy/`.
+fn program_id(
+    project: &Path,
+    network: SoonNetwork,
+    program: &str,
+    lib: &str,
+) -> Result<String, MigrationError> {
+    let anchor = fs::read_to_string(project.join("Anchor.toml"))
+        .ok()
+        .and_then(|content| content.parse::<toml::Value>().ok());
+    let table = anchor
+        .as_ref()
+        .and_then(|anchor| anchor.get("programs")?.get(network.as_str()));
+    if let Some(id) = [program, lib]
+        .iter()
+        .find_map(|name| table?.get(*name)?.as_str())
+    {
+        return Ok(id.to_string());
+    }
+
+    let keypair = project
+        .join("target")
+        .join("deploy")
+        .join(format!("{}-keypair.json", lib));
+    keypair_address(&keypair).map_err(|reason| {
+        MigrationError::VerifyFailed(format!(
+            "no ID for {} in [programs.{}] of Anchor.toml, and no keypair ({})",
+            program, network, reason
+        ))
+    })
+}
+
+fn trim_zeros(bytes: &[u8]) -> &[u8] {
+    let end = bytes
+        .iter()
+        .rposition(|byte| *byte != 0)
+        .map_or(0, |i| i + 1);
+    &bytes[..end]
+}
+
+/// The account at `address`, parsed where the RPC knows how; `null` when
+/// there is none.
+fn account(rpc: &Rpc, address: &str) -> Result<Value, MigrationError> {
+    let mut result = rpc.call(
+        "getAccountInfo",
+        json!([address, { "encoding": "jsonParsed" }]),
+    )?;
+    Ok(result["value"].take())
+}
+
+/// Status of a program from its account and, for upgradeable programs, its
+/// program data account, both as `getAccountInfo` returns them.
+fn status(account: &Value, program_data: Option<&Value>) -> DeployStatus {
+    if account.is_null() {
+        return DeployStatus::Missing;
+    }
+    if account["executable"].as_bool() != Some(true) {
+        return DeployStatus::NotExecutable;
+    }
+    let authority = program_data
+        .and_then(|data| data["data"]["parsed"]["info"]["authority"].as_str())
+        .map(str::to_string);
+    DeployStatus::Deployed { authority }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_program_id() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::write(
+            dir.path().join("Anchor.toml"),
+            "[programs.devnet]\nmy_program = \"Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS\"\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            program_id(dir.path(), SoonNetwork::Devnet, "my-program", "my_program").unwrap(),
+            "Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS"
+        );
+        assert!(matches!(
+            program_id(dir.path(), SoonNetwork::Mainnet, "my-program", "my_program"),
+            Err(MigrationError::VerifyFailed(_))
+        ));
+        assert_eq!(trim_zeros(&[1, 0, 2, 0, 0]), &[1, 0, 2]);
+    }
+
+    #[test]
+    fn test_status() {
+        let program = json!({
+            "executable": true,
+            "owner": "BPFLoaderUpgradeab1e11111111111111111111111",
+            "data": { "parsed": { "type": "program", "info": { "programData": "8wWLP4YmLwFJT9kVo3Mhjwu8tkc4JyYUaSBV3JBXbDqk" } } }
+        });
+        let program_data = json!({
+            "executable": false,
+            "data": { "parsed": { "type": "programData", "info": { "authority": "Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS", "slot": 42 } } }
+        });
+
+        assert_eq!(
+            status(&program, Some(&program_data)),
+            DeployStatus::Deployed {
+                authority: Some("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS".to_string())
+            }
+        );
+        assert_eq!(status(&Value::Null, None), DeployStatus::Missing);
+        assert_eq!(
+            status(&json!({ "executable": false, "lamports": 1 }), None),
+            DeployStatus::NotExecutable
+        );
+    }
+}
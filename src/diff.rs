@@ -0,0 +1,58 @@
+use crate::theme::Styled;
+use colored::*;
+use similar::{ChangeTag, TextDiff};
+
+/// Renders a colored unified diff from `old` to `new`, or `None` when the
+/// two are identical.
+pub fn render_diff(old: &str, new: &str, old_label: &str, new_label: &str) -> Option<String> {
+    unified_diff(old, new, old_label, new_label).map(|diff| colorize(&diff))
+}
+
+/// Plain unified diff from `old` to `new`, or `None` when the two are identical.
+pub fn unified_diff(old: &str, new: &str, old_label: &str, new_label: &str) -> Option<String> {
+    if old == new {
+        return None;
+    }
+
+    let diff = TextDiff::from_lines(old, new);
+    let mut out = format!("--- {}\n+++ {}\n", old_label, new_label);
+
+    for hunk in diff.unified_diff().context_radius(3).iter_hunks() {
+        out.push_str(&hunk.header().to_string());
+        out.push('\n');
+        for change in hunk.iter_changes() {
+            let line = change.to_string_lossy();
+            let sign = match change.tag() {
+                ChangeTag::Delete => '-',
+                ChangeTag::Insert => '+',
+                ChangeTag::Equal => ' ',
+            };
+            out.push(sign);
+            out.push_str(line.trim_end_matches('\n'));
+            out.push('\n');
+        }
+    }
+
+    Some(out)
+}
+
+/// Colors a unified diff for the terminal.
+pub fn colorize(diff: &str) -> String {
+    let mut out = String::new();
+
+    for line in diff.lines() {
+        let rendered = if line.starts_with('-') {
+            line.error()
+        } else if line.starts_with('+') {
+            line.success()
+        } else if line.starts_with("@@") {
+            line.info()
+        } else {
+            line.normal()
+        };
+        out.push_str(&rendered.to_string());
+        out.push('\n');
+    }
+
+    out
+}
@@ -20,6 +20,48 @@ pub enum MigrationError {
     #[error("Failed to restore from backup: {0}")]
     RestoreFailed(String),
 
-    #[error("The specified path is not a valid Anchor project: {0}")]
+    #[error("The specified path is not a valid Anchor project or Solana program: {0}")]
     NotAnAnchorProject(String),
-}
\ No newline at end of file
+
+    #[error("Failed to load config file: {0}")]
+    ConfigError(String),
+
+    #[error("Failed to update the backup journal: {0}")]
+    JournalError(String),
+
+    #[error("Invalid program ID override (expected <name>=<base58 pubkey>): {0}")]
+    InvalidProgramId(String),
+
+    #[error("Invalid migration plan: {0}")]
+    PlanError(String),
+
+    #[error("Invalid RPC endpoint (expected an http(s) URL): {0}")]
+    InvalidEndpoint(String),
+
+    #[error("Report error: {0}")]
+    ReportError(String),
+
+    #[error("Failed to send notification: {0}")]
+    NotifyError(String),
+
+    #[error("Git error: {0}")]
+    GitError(String),
+
+    #[error("Preflight check failed: {0}")]
+    PreflightFailed(String),
+
+    #[error("Project is locked: {0}")]
+    Locked(String),
+
+    #[error("RPC request failed: {0}")]
+    RpcError(String),
+
+    #[error("RPC endpoint is on the wrong chain: {0}")]
+    WrongChain(String),
+
+    #[error("Deploy failed: {0}")]
+    DeployFailed(String),
+
+    #[error("Program verification failed: {0}")]
+    VerifyFailed(String),
+}
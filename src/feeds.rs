@@ -0,0 +1,197 @@
+use crate::bridge::{addresses, is_known_mint};
+use crate::compat::{idl_files, is_catalogued};
+use crate::errors::MigrationError;
+use crate::rpc::Rpc;
+use crate::settings::OracleSettings;
+use crate::workspace::find_files;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Most `file:line` locations kept per feed.
+const MAX_EVIDENCE: usize = 5;
+
+/// Words that mark a line as being about an oracle feed.
+const FEED_WORDS: &[&str] = &["feed", "price", "oracle", "aggregator"];
+
+/// A price feed account the project reads on Solana, and the APRO feed that
+/// serves the same pair on SOON.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct OracleFeed {
+    /// Address of the feed account on Solana.
+    pub feed: String,
+    /// Address of the APRO feed on SOON, from `[oracle.feeds]`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub apro_feed: Option<String>,
+    /// `file:line` locations that reference the feed, the first few.
+    pub evidence: Vec<String>,
+    /// What SOON says about `apro_feed`; `None` when it was not asked.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<FeedStatus>,
+    /// Seconds since the APRO feed was last written, when SOON knows.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub age_secs: Option<u64>,
+}
+
+/// Whether an APRO feed can be migrated to.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FeedStatus {
+    /// The account exists and was written recently.
+    Live,
+    /// The account exists but has not been written within the allowed age.
+    Stale,
+    /// There is no such account on SOON.
+    Missing,
+}
+
+/// Source files, Anchor.toml and test files that can reference a feed.
+fn is_scanned(name: &str) -> bool {
+    name == "Anchor.toml"
+        || [".rs", ".ts", ".tsx", ".js", ".mjs"]
+            .iter()
+            .any(|extension| name.ends_with(extension))
+}
+
+/// Finds the oracle feed accounts the project references in its sources,
+/// tests, Anchor.toml and IDLs: any address on a line that talks about a
+/// feed, price, oracle or aggregator, unless it is a known program or mint,
+/// or an APRO feed already. `settings` maps each to its APRO feed.
+pub fn scan_feeds(project: &Path, settings: &OracleSettings) -> Vec<OracleFeed> {
+    let mut feeds: Vec<OracleFeed> = Vec::new();
+
+    for file in find_files(project, &is_scanned)
+        .into_iter()
+        .chain(idl_files(project))
+    {
+        let Ok(content) = fs::read_to_string(project.join(&file)) else {
+            continue;
+        };
+        for (index, line) in content.lines().enumerate() {
+            let lower = line.to_ascii_lowercase();
+            if !FEED_WORDS.iter().any(|word| lower.contains(word)) {
+                continue;
+            }
+            for address in addresses(line) {
+                if is_catalogued(address)
+                    || is_known_mint(address)
+                    || settings.feeds.values().any(|apro| apro == address)
+                {
+                    continue;
+                }
+                let location = format!("{}:{}", file, index + 1);
+                match feeds.iter_mut().find(|feed| feed.feed == address) {
+                    Some(feed) => {
+                        if feed.evidence.len() < MAX_EVIDENCE && !feed.evidence.contains(&location)
+                        {
+                            feed.evidence.push(location);
+                        }
+                    }
+                    None => feeds.push(OracleFeed {
+                        feed: address.to_string(),
+                        apro_feed: settings.feeds.get(address).cloned(),
+                        evidence: vec![location],
+                        status: None,
+                        age_secs: None,
+                    }),
+                }
+            }
+        }
+    }
+    feeds
+}
+
+/// Asks SOON whether the APRO feed of each of `feeds` exists and when it was
+/// last written, and marks it live, stale or missing. A feed written more
+/// than `max_age` seconds ago, or never, is stale.
+pub fn verify(rpc: &Rpc, feeds: &mut [OracleFeed], max_age: u64) -> Result<(), MigrationError> {
+    let mapped: Vec<String> = feeds
+        .iter()
+        .filter_map(|feed| feed.apro_feed.clone())
+        .collect();
+    let addresses: Vec<&str> = mapped.iter().map(String::as_str).collect();
+    let exist = rpc.accounts_exist(&addresses)?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+
+    for (feed, exists) in feeds
+        .iter_mut()
+        .filter(|feed| feed.apro_feed.is_some())
+        .zip(exist)
+    {
+        let written = match (&feed.apro_feed, exists) {
+            (Some(apro), true) => rpc.last_write(apro)?,
+            _ => None,
+        };
+        feed.age_secs = written.map(|time| now.saturating_sub(time));
+        feed.status = Some(status(exists, feed.age_secs, max_age));
+    }
+    Ok(())
+}
+
+fn status(exists: bool, age_secs: Option<u64>, max_age: u64) -> FeedStatus {
+    match age_secs {
+        _ if !exists => FeedStatus::Missing,
+        Some(age) if age <= max_age => FeedStatus::Live,
+        _ => FeedStatus::Stale,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_scan_feeds() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::write(
+            dir.path().join("src").join("lib.rs"),
+            "const SOL_USD_FEED: &str = \"H6ARHf6YXhGYeQfUzQNGk6rDNnLBQKrenN712K4AQJEG\";\n\
+             const BTC_PRICE: &str = \"GVXRSBjFk6e6J3NbVPXohDJetcTjaeeuykUpbQF8UoMU\";\n\
+             const USDC_PRICE_MINT: &str = \"EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v\";\n\
+             let pyth_oracle = \"FsJ3A3u2vn5cTVofAjvy6y5kwABJAqYWpe4975bi2epH\";\n\
+             let owner = \"Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS\";\n\
+             let apro_feed = \"8wWLP4YmLwFJT9kVo3Mhjwu8tkc4JyYUaSBV3JBXbDqk\";\n",
+        )
+        .unwrap();
+        let settings = OracleSettings {
+            feeds: BTreeMap::from([(
+                "H6ARHf6YXhGYeQfUzQNGk6rDNnLBQKrenN712K4AQJEG".to_string(),
+                "8wWLP4YmLwFJT9kVo3Mhjwu8tkc4JyYUaSBV3JBXbDqk".to_string(),
+            )]),
+        };
+
+        let feeds = scan_feeds(dir.path(), &settings);
+
+        // The mint, the Pyth program, the unrelated address and the APRO feed
+        // already swapped in are left out
+        let found: Vec<(&str, Option<&str>)> = feeds
+            .iter()
+            .map(|feed| (feed.feed.as_str(), feed.apro_feed.as_deref()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (
+                    "H6ARHf6YXhGYeQfUzQNGk6rDNnLBQKrenN712K4AQJEG",
+                    Some("8wWLP4YmLwFJT9kVo3Mhjwu8tkc4JyYUaSBV3JBXbDqk")
+                ),
+                ("GVXRSBjFk6e6J3NbVPXohDJetcTjaeeuykUpbQF8UoMU", None),
+            ]
+        );
+        assert_eq!(feeds[1].evidence, vec!["src/lib.rs:2"]);
+    }
+
+    #[test]
+    fn test_feed_status() {
+        assert_eq!(status(false, None, 3600), FeedStatus::Missing);
+        assert_eq!(status(true, Some(30), 3600), FeedStatus::Live);
+        assert_eq!(status(true, Some(7200), 3600), FeedStatus::Stale);
+        // An account nothing has written to since it was created
+        assert_eq!(status(true, None, 3600), FeedStatus::Stale);
+    }
+}
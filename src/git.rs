@@ -0,0 +1,231 @@
+use crate::compat::Availability;
+use crate::errors::MigrationError;
+use crate::result::MigrationResult;
+use crate::workspace::STATE_DIR;
+use std::path::Path;
+use std::process::Command;
+
+/// Branch `--git` runs the migration on.
+pub const MIGRATION_BRANCH: &str = "soon-migration";
+
+/// Runs git in `project` and returns its trimmed standard output.
+fn git(project: &Path, args: &[&str]) -> Result<String, MigrationError> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(project)
+        .args(args)
+        .output()
+        .map_err(|e| MigrationError::GitError(format!("could not run git: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(MigrationError::GitError(format!(
+            "git {}: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Where `start_branch` left from, so a failed run can go back there.
+#[derive(Debug)]
+pub struct MigrationBranch {
+    /// `refs/heads/<branch>`, or the commit of a detached HEAD.
+    original: String,
+}
+
+/// Checks that `project` is in a git repository, switches to a new
+/// `soon-migration` branch and commits uncommitted changes to tracked files
+/// there, so the migration commit holds nothing but the migration. Untracked
+/// files, such as `.env` or keypairs, and soon-migrate's own state stay out.
+pub fn start_branch(project: &Path) -> Result<MigrationBranch, MigrationError> {
+    git(project, &["rev-parse", "--is-inside-work-tree"]).map_err(|_| {
+        MigrationError::GitError(format!("{} is not in a git repository", project.display()))
+    })?;
+
+    let branch_ref = format!("refs/heads/{}", MIGRATION_BRANCH);
+    if git(project, &["rev-parse", "--verify", "--quiet", &branch_ref]).is_ok() {
+        return Err(MigrationError::GitError(format!(
+            "branch {} already exists; merge or delete it first",
+            MIGRATION_BRANCH
+        )));
+    }
+    let original = git(project, &["symbolic-ref", "--quiet", "HEAD"])
+        .or_else(|_| git(project, &["rev-parse", "HEAD"]))?;
+    git(project, &["checkout", "-b", MIGRATION_BRANCH])?;
+
+    let exclude_state = format!(":(exclude){}", STATE_DIR);
+    git(project, &["add", "-u", "--", ".", &exclude_state])?;
+    // Nothing staged exits 0, anything staged 1
+    if git(project, &["diff", "--cached", "--quiet"]).is_err() {
+        git(
+            project,
+            &["commit", "-m", "Snapshot before migrating to SOON"],
+        )?;
+    }
+    Ok(MigrationBranch { original })
+}
+
+impl MigrationBranch {
+    /// Goes back to the branch the run started on and deletes the migration
+    /// branch, after a failed run. The working tree is left as it is, so
+    /// uncommitted changes, and anything the run wrote, carry over.
+    pub fn abandon(self, project: &Path) -> Result<(), MigrationError> {
+        if self.original.starts_with("refs/") {
+            git(project, &["symbolic-ref", "HEAD", &self.original])?;
+        } else {
+            git(
+                project,
+                &["update-ref", "--no-deref", "HEAD", &self.original],
+            )?;
+        }
+        // Unstages the snapshot against the original tip
+        git(project, &["reset", "--quiet"])?;
+        git(project, &["branch", "-D", MIGRATION_BRANCH]).map(|_| ())
+    }
+}
+
+/// Commits the files the migration changed, leaving backups and the journal
+/// untracked. Returns the short hash of the commit, or `None` if nothing
+/// changed.
+pub fn commit_migration(
+    project: &Path,
+    result: &MigrationResult,
+) -> Result<Option<String>, MigrationError> {
+    let files = changed_files(result);
+    if files.is_empty() {
+        return Ok(None);
+    }
+
+    let mut add = vec!["add", "--"];
+    add.extend(files.iter().copied());
+    git(project, &add)?;
+    git(project, &["commit", "-m", &commit_message(result)])?;
+
+    git(project, &["rev-parse", "--short", "HEAD"]).map(Some)
+}
+
+fn changed_files(result: &MigrationResult) -> Vec<&str> {
+    let mut files: Vec<&str> = Vec::new();
+    for change in &result.changes {
+        if !files.contains(&change.file.as_str()) {
+            files.push(&change.file);
+        }
+    }
+    files
+}
+
+/// Commit message listing the modified files, the programs to check on SOON
+/// and how many warnings the run raised.
+pub fn commit_message(result: &MigrationResult) -> String {
+    let mut message = format!("Migrate to SOON {}\n\nModified files:\n", result.network);
+    for file in changed_files(result) {
+        message.push_str(&format!("- {}\n", file));
+    }
+
+    let to_check: Vec<_> = result
+        .compatibility
+        .iter()
+        .filter(|finding| finding.availability != Availability::Available)
+        .collect();
+    if !to_check.is_empty() {
+        message.push_str("\nPrograms to check on SOON:\n");
+        for finding in to_check {
+            message.push_str(&format!("- {}\n", finding.describe()));
+        }
+    }
+
+    if !result.warnings.is_empty() {
+        message.push_str(&format!("\nWarnings: {}\n", result.warnings.len()));
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endpoints::SoonNetwork;
+    use crate::result::{Change, ChangeKind};
+    use std::fs;
+
+    fn repository() -> tempfile::TempDir {
+        let dir = tempfile::TempDir::new().unwrap();
+        let project = dir.path();
+        git(project, &["init", "--quiet", "--initial-branch", "main"]).unwrap();
+        git(project, &["config", "user.name", "test"]).unwrap();
+        git(project, &["config", "user.email", "test@example.com"]).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_snapshot_leaves_out_untracked_files() {
+        let dir = repository();
+        let project = dir.path();
+        fs::write(project.join("Anchor.toml"), "cluster = \"Localnet\"\n").unwrap();
+        git(project, &["add", "Anchor.toml"]).unwrap();
+        git(project, &["commit", "--quiet", "-m", "init"]).unwrap();
+        fs::write(project.join("Anchor.toml"), "cluster = \"Devnet\"\n").unwrap();
+        fs::write(project.join(".env"), "KEY=secret\n").unwrap();
+        fs::create_dir(project.join(STATE_DIR)).unwrap();
+        fs::write(project.join(STATE_DIR).join("journal.json"), "{}\n").unwrap();
+
+        let branch = start_branch(project).unwrap();
+        let snapshot = git(project, &["show", "--name-only", "--format=", "HEAD"]).unwrap();
+        assert_eq!(snapshot, "Anchor.toml");
+
+        // A failed run goes back to main, uncommitted changes intact
+        branch.abandon(project).unwrap();
+        assert_eq!(
+            git(project, &["rev-parse", "--abbrev-ref", "HEAD"]).unwrap(),
+            "main"
+        );
+        assert!(git(
+            project,
+            &["rev-parse", "--verify", "--quiet", MIGRATION_BRANCH]
+        )
+        .is_err());
+        assert_eq!(
+            fs::read_to_string(project.join("Anchor.toml")).unwrap(),
+            "cluster = \"Devnet\"\n"
+        );
+        assert_eq!(
+            git(project, &["status", "--porcelain"]).unwrap(),
+            "M Anchor.toml\n?? .env\n?? .soon-migrate/"
+        );
+    }
+
+    #[test]
+    fn test_migration_committed_on_branch() {
+        let dir = repository();
+        let project = dir.path();
+        fs::write(project.join("Anchor.toml"), "cluster = \"Localnet\"\n").unwrap();
+        git(project, &["add", "Anchor.toml"]).unwrap();
+        git(project, &["commit", "--quiet", "-m", "init"]).unwrap();
+
+        start_branch(project).unwrap();
+        assert_eq!(
+            git(project, &["rev-parse", "--abbrev-ref", "HEAD"]).unwrap(),
+            MIGRATION_BRANCH
+        );
+        assert!(start_branch(project).is_err());
+
+        fs::write(project.join("Anchor.toml"), "cluster = \"soon\"\n").unwrap();
+        fs::write(project.join("Anchor.toml.bak"), "cluster = \"Localnet\"\n").unwrap();
+        let mut result = MigrationResult::new(SoonNetwork::Devnet, false);
+        result.changes.push(Change {
+            file: "Anchor.toml".to_string(),
+            kind: ChangeKind::ClusterUpdated,
+            detail: "provider.cluster: Localnet -> soon".to_string(),
+        });
+
+        assert!(commit_migration(project, &result).unwrap().is_some());
+        let message = git(project, &["log", "-1", "--format=%B"]).unwrap();
+        assert!(message.starts_with("Migrate to SOON devnet"));
+        assert!(message.contains("- Anchor.toml"));
+        // Backups stay out of the commit
+        assert_eq!(
+            git(project, &["status", "--porcelain"]).unwrap(),
+            "?? Anchor.toml.bak"
+        );
+    }
+}
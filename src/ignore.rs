@@ -0,0 +1,111 @@
+use ::ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::path::Path;
+
+/// File listing paths the tool should leave alone, in the same syntax as
+/// `.gitignore`. Honored in the project root and every directory below it.
+pub const IGNORE_FILE: &str = ".soonmigrateignore";
+
+/// Git's own ignore files, honored in every directory.
+pub const GITIGNORE_FILE: &str = ".gitignore";
+
+/// Patterns from the `.gitignore` and `.soonmigrateignore` in the project
+/// root, or from the command line, matched with git's rules. Walking the
+/// project (`workspace::find_files`) also picks up the ignore files further
+/// down; these rules are for checking a single known path.
+#[derive(Debug, Clone)]
+pub struct IgnoreRules {
+    matcher: Gitignore,
+}
+
+impl Default for IgnoreRules {
+    fn default() -> Self {
+        IgnoreRules {
+            matcher: Gitignore::empty(),
+        }
+    }
+}
+
+impl IgnoreRules {
+    /// Reads the `.gitignore` and `.soonmigrateignore` in the project root;
+    /// without them nothing is ignored.
+    pub fn load(project: &Path) -> Self {
+        let mut builder = GitignoreBuilder::new(project);
+        for file in [GITIGNORE_FILE, IGNORE_FILE] {
+            let path = project.join(file);
+            if path.is_file() {
+                // A malformed line is skipped, as git does
+                builder.add(path);
+            }
+        }
+        Self::build(builder)
+    }
+
+    /// Rules from patterns given on the command line, relative to the
+    /// project root.
+    pub fn from_patterns(patterns: &[String]) -> Self {
+        let mut builder = GitignoreBuilder::new("");
+        for pattern in patterns {
+            let _ = builder.add_line(None, pattern);
+        }
+        Self::build(builder)
+    }
+
+    fn build(builder: GitignoreBuilder) -> Self {
+        IgnoreRules {
+            matcher: builder.build().unwrap_or_else(|_| Gitignore::empty()),
+        }
+    }
+
+    /// Whether `path`, relative to the project root with `/` separators, is
+    /// ignored, directly or because a directory above it is.
+    pub fn is_ignored(&self, path: &str) -> bool {
+        self.matcher
+            .matched_path_or_any_parents(path, false)
+            .is_ignore()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gitignore_style_patterns() {
+        let patterns: Vec<String> = [
+            "# fixtures are recorded responses",
+            "fixtures/",
+            "/vendor",
+            "*.generated.ts",
+            "scripts/**/deploy-*.sh",
+            "!keep.generated.ts",
+        ]
+        .iter()
+        .map(|pattern| pattern.to_string())
+        .collect();
+        let rules = IgnoreRules::from_patterns(&patterns);
+
+        assert!(rules.is_ignored("fixtures/mainnet.json"));
+        assert!(rules.is_ignored("tests/fixtures/rpc.ts"));
+        assert!(!rules.is_ignored("fixtures"));
+        assert!(rules.is_ignored("vendor/anchor/Cargo.toml"));
+        assert!(!rules.is_ignored("programs/vendor/Cargo.toml"));
+        assert!(rules.is_ignored("app/src/idl.generated.ts"));
+        assert!(!rules.is_ignored("app/src/keep.generated.ts"));
+        assert!(rules.is_ignored("scripts/deploy-devnet.sh"));
+        assert!(rules.is_ignored("scripts/ci/deploy-devnet.sh"));
+        assert!(!rules.is_ignored("scripts/test.sh"));
+        assert!(!IgnoreRules::default().is_ignored("Anchor.toml"));
+    }
+
+    #[test]
+    fn test_root_ignore_files_loaded() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(dir.path().join(GITIGNORE_FILE), "dist/\n").unwrap();
+        std::fs::write(dir.path().join(IGNORE_FILE), "/generated\n").unwrap();
+
+        let rules = IgnoreRules::load(dir.path());
+        assert!(rules.is_ignored("app/dist/index.js"));
+        assert!(rules.is_ignored("generated/client.ts"));
+        assert!(!rules.is_ignored("app/generated/client.ts"));
+    }
+}
@@ -0,0 +1,111 @@
+use crate::errors::MigrationError;
+use crate::result::Change;
+use crate::workspace::STATE_DIR;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const JOURNAL_FILE: &str = "journal.toml";
+
+/// Record of every file the tool modified and where its backup lives, so a
+/// restore can put all of them back.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Journal {
+    #[serde(default)]
+    pub entries: Vec<JournalEntry>,
+}
+
+/// A modified file and its backup, both relative to the project root, with
+/// the changes made to it since the backup was taken.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct JournalEntry {
+    pub file: String,
+    pub backup: String,
+    /// The migration created the file, so there is no backup and restoring
+    /// removes it.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub created: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub changes: Vec<Change>,
+}
+
+impl Journal {
+    pub fn path(project: &Path) -> PathBuf {
+        project.join(STATE_DIR).join(JOURNAL_FILE)
+    }
+
+    /// Loads the project's journal, or an empty one if none was written yet.
+    pub fn load(project: &Path) -> Result<Self, MigrationError> {
+        let path = Journal::path(project);
+        if !path.exists() {
+            return Ok(Journal::default());
+        }
+
+        let content =
+            fs::read_to_string(&path).map_err(|e| MigrationError::JournalError(e.to_string()))?;
+        toml::from_str(&content)
+            .map_err(|e| MigrationError::JournalError(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Writes the journal, removing it entirely once it has no entries left.
+    pub fn save(&self, project: &Path) -> Result<(), MigrationError> {
+        let path = Journal::path(project);
+
+        if self.entries.is_empty() {
+            if path.exists() {
+                fs::remove_file(&path).map_err(|e| MigrationError::JournalError(e.to_string()))?;
+            }
+            return Ok(());
+        }
+
+        fs::create_dir_all(project.join(STATE_DIR))
+            .map_err(|e| MigrationError::JournalError(e.to_string()))?;
+        let content = toml::to_string_pretty(self)
+            .map_err(|e| MigrationError::JournalError(e.to_string()))?;
+        fs::write(&path, content).map_err(|e| MigrationError::JournalError(e.to_string()))
+    }
+
+    pub fn entry(&self, file: &str) -> Option<&JournalEntry> {
+        self.entries.iter().find(|entry| entry.file == file)
+    }
+
+    /// Appends changes made to an already backed up file.
+    pub fn record(&mut self, file: &str, changes: impl Iterator<Item = Change>) {
+        if let Some(entry) = self.entries.iter_mut().find(|entry| entry.file == file) {
+            entry.changes.extend(changes);
+        }
+    }
+
+    pub fn remove(&mut self, file: &str) {
+        self.entries.retain(|entry| entry.file != file);
+    }
+
+    /// Copies `file` to `<file>.bak` and records it. A file that already has
+    /// a backup from an earlier run keeps it, so the backup always holds the
+    /// content from before the first migration. A file that does not exist
+    /// yet is recorded as created instead.
+    pub fn backup(&mut self, project: &Path, file: &str) -> Result<(), MigrationError> {
+        let backup = format!("{}.bak", file);
+
+        if let Some(entry) = self.entry(file) {
+            if entry.created || project.join(&backup).exists() {
+                return Ok(());
+            }
+        }
+
+        let created = !project.join(file).exists();
+        if !created {
+            fs::copy(project.join(file), project.join(&backup))
+                .map_err(|e| MigrationError::BackupFailed(format!("{}: {}", file, e)))?;
+        }
+
+        self.remove(file);
+        self.entries.push(JournalEntry {
+            file: file.to_string(),
+            backup,
+            created,
+            changes: Vec::new(),
+        });
+        Ok(())
+    }
+}
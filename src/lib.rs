@@ -0,0 +1,15 @@
+//! soon-migrate is a command-line tool. The library exposes its compatibility
+//! scanner, so tools built around it can run it with detectors of their own,
+//! and, behind the `testkit` feature, helpers for testing those tools.
+
+mod cache;
+mod codeowners;
+pub mod compat;
+#[cfg(feature = "testkit")]
+pub mod testkit;
+
+// What the scanner builds on, shared with the binary
+#[doc(hidden)]
+pub mod ignore;
+#[doc(hidden)]
+pub mod workspace;
@@ -0,0 +1,126 @@
+use crate::errors::MigrationError;
+use crate::workspace::STATE_DIR;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{Duration, SystemTime};
+
+const LOCK_FILE: &str = "lock";
+
+/// A lock older than this is assumed to be left over from a run that was
+/// killed, even if its process cannot be checked.
+const STALE_AFTER: Duration = Duration::from_secs(60 * 60);
+
+/// Exclusive hold on a project while its files and backups are written, so
+/// two runs cannot interleave. Released when dropped.
+#[derive(Debug)]
+pub struct Lock {
+    path: PathBuf,
+}
+
+impl Lock {
+    /// Creates `.soon-migrate/lock` holding this process's ID. A lock left by
+    /// a process that is gone, or older than an hour, is taken over.
+    pub fn acquire(project: &Path) -> Result<Lock, MigrationError> {
+        let dir = project.join(STATE_DIR);
+        fs::create_dir_all(&dir).map_err(|e| MigrationError::Locked(e.to_string()))?;
+        let path = dir.join(LOCK_FILE);
+
+        // Second attempt is after removing a stale lock
+        for _ in 0..2 {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    writeln!(file, "{}", process::id())
+                        .map_err(|e| MigrationError::Locked(e.to_string()))?;
+                    return Ok(Lock { path });
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                    let holder = fs::read_to_string(&path).unwrap_or_default();
+                    let pid = holder.trim().parse::<u32>().ok();
+                    if !is_stale(&path, pid) {
+                        return Err(MigrationError::Locked(format!(
+                            "another soon-migrate run (pid {}) is using {}; remove {} if it is no longer running",
+                            pid.map_or("unknown".to_string(), |pid| pid.to_string()),
+                            project.display(),
+                            path.display()
+                        )));
+                    }
+                    let _ = fs::remove_file(&path);
+                }
+                Err(e) => return Err(MigrationError::Locked(e.to_string())),
+            }
+        }
+        Err(MigrationError::Locked(format!(
+            "could not take over the stale lock {}",
+            path.display()
+        )))
+    }
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+        // Only succeeds if the lock was all there was
+        if let Some(dir) = self.path.parent() {
+            let _ = fs::remove_dir(dir);
+        }
+    }
+}
+
+fn is_stale(path: &Path, pid: Option<u32>) -> bool {
+    if let Some(pid) = pid {
+        if !is_running(pid) {
+            return true;
+        }
+    }
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|age| age > STALE_AFTER)
+}
+
+#[cfg(target_os = "linux")]
+fn is_running(pid: u32) -> bool {
+    Path::new("/proc").join(pid.to_string()).exists()
+}
+
+/// Without a portable way to check, only the lock's age can make it stale.
+#[cfg(not(target_os = "linux"))]
+fn is_running(_pid: u32) -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_excludes_second_run() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let lock = Lock::acquire(dir.path()).unwrap();
+        assert!(matches!(
+            Lock::acquire(dir.path()),
+            Err(MigrationError::Locked(_))
+        ));
+
+        drop(lock);
+        assert!(!dir.path().join(STATE_DIR).exists());
+        Lock::acquire(dir.path()).unwrap();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_stale_lock_taken_over() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join(STATE_DIR)).unwrap();
+        fs::write(
+            dir.path().join(STATE_DIR).join(LOCK_FILE),
+            format!("{}\n", u32::MAX),
+        )
+        .unwrap();
+
+        Lock::acquire(dir.path()).unwrap();
+    }
+}
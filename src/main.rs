@@ -1,19 +1,72 @@
 use colored::*;
 use indicatif::{ProgressBar, ProgressStyle};
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
 use std::process::exit;
 use std::time::Duration;
 
+mod assumptions;
+mod baseline;
+mod bridge;
 mod cli;
+mod deploy;
+mod deployed;
+mod diff;
+mod endpoints;
 mod errors;
+mod feeds;
+mod git;
+mod journal;
+mod lock;
 mod migration;
-use cli::Config;
-use migration::{restore_backup, run_migration};
+mod network;
+mod notify;
+mod plan;
+mod preflight;
+mod report;
+mod result;
+mod rewrite;
+mod rpc;
+mod runtime;
+mod settings;
+mod theme;
+mod toolchain;
+mod wallet;
+mod why;
+
+#[cfg(test)]
+mod testkit;
+use soon_migrate::{compat, ignore, workspace};
+
+use baseline::{Comparison, Severity};
+use cli::{Config, Subcommand};
+use compat::{complexity, oracle_redundancy, scan_ecosystem, Availability, CompatFinding};
+use deployed::DeployStatus;
+use endpoints::{url_host, Endpoints, SoonNetwork};
+use errors::MigrationError;
+use feeds::FeedStatus;
+use migration::{
+    build_plan, execute_plan, preview_restore, restore_backup, restore_targets, run_migration,
+    run_migration_for_report, scan_options, RestoreTarget,
+};
+use plan::Plan;
+use report::{Report, RollUp};
+use result::{MigrationResult, Warning};
+use rewrite::solana_url;
+use rpc::Rpc;
+use runtime::RuntimeComparison;
+use settings::Settings;
+use theme::{Role, Styled};
 
 fn main() {
     let config = Config::new();
+    // A broken config file is reported by the run itself
+    let settings = Settings::load(&config.path, config.config_file.as_deref()).unwrap_or_default();
+    theme::set(settings.output.theme);
 
     if config.verbose {
-        println!("{}", "Starting soon-migrate...".cyan());
+        println!("{}", "Starting soon-migrate...".info());
     }
 
     let progress = ProgressBar::new_spinner();
@@ -24,39 +77,1213 @@ fn main() {
             .tick_chars("/|\\- "),
     );
 
+    if let Some(Subcommand::Compat { online, solana_rpc }) = &config.subcommand {
+        // Custom rules in a broken config file would otherwise go unnoticed
+        let settings = match Settings::load(&config.path, config.config_file.as_deref()) {
+            Ok(settings) => settings,
+            Err(e) => {
+                eprintln!("{}", e.to_string().error());
+                exit(1);
+            }
+        };
+        let findings = scan_ecosystem(
+            Path::new(&config.path),
+            &scan_options(&config, &settings.rules),
+        );
+        if findings.is_empty() {
+            println!(
+                "{}",
+                "Nothing found that behaves differently on SOON.".success()
+            );
+        }
+        print_compatibility(&findings);
+        if *online {
+            match compare_runtime(&config, solana_rpc.as_deref(), &findings) {
+                Ok(comparison) => print_runtime(&comparison),
+                Err(e) => {
+                    eprintln!("{}", e.to_string().error());
+                    exit(1);
+                }
+            }
+        }
+        return;
+    }
+
+    if let Some(Subcommand::Schema { roll_up }) = &config.subcommand {
+        if *roll_up {
+            print!("{}", report::roll_up_schema());
+        } else {
+            print!("{}", report::report_schema());
+        }
+        return;
+    }
+
+    if let Some(Subcommand::Verify { report }) = &config.subcommand {
+        match Report::load(report)
+            .and_then(|loaded| loaded.verify().map(|signer| signer.map(str::to_string)))
+        {
+            Ok(Some(signer)) => {
+                println!(
+                    "{}",
+                    format!("{} is intact and signed by {}", report, signer).success()
+                )
+            }
+            Ok(None) => println!("{}", format!("{} is intact (unsigned)", report).success()),
+            Err(e) => {
+                eprintln!("{}", e.to_string().error());
+                exit(1);
+            }
+        }
+        return;
+    }
+
+    if let Some(Subcommand::VerifyProgram { program }) = &config.subcommand {
+        if let Err(e) = verify_program(&config, program) {
+            eprintln!("{}", e.to_string().error());
+            exit(1);
+        }
+        return;
+    }
+
+    if let Some(Subcommand::Bridge { online, out }) = &config.subcommand {
+        if let Err(e) = bridge(&config, *online, out.as_deref()) {
+            eprintln!("{}", e.to_string().error());
+            exit(1);
+        }
+        return;
+    }
+
+    if let Some(Subcommand::Feeds {
+        online,
+        max_age,
+        out,
+    }) = &config.subcommand
+    {
+        if let Err(e) = feeds(&config, *online, *max_age, out.as_deref()) {
+            eprintln!("{}", e.to_string().error());
+            exit(1);
+        }
+        return;
+    }
+
+    if let Some(Subcommand::CheckRpc) = &config.subcommand {
+        if let Err(e) = check_rpc(&config) {
+            eprintln!("{}", e.to_string().error());
+            exit(1);
+        }
+        return;
+    }
+
+    if let Some(Subcommand::BenchRpc {
+        requests,
+        solana,
+        solana_rpc,
+    }) = &config.subcommand
+    {
+        let solana = solana.then_some(solana_rpc.as_deref());
+        if let Err(e) = bench_rpc(&config, *requests, solana) {
+            eprintln!("{}", e.to_string().error());
+            exit(1);
+        }
+        return;
+    }
+
+    if let Some(Subcommand::Deploy) = &config.subcommand {
+        if let Err(e) = deploy(&config) {
+            eprintln!("{}", e.to_string().error());
+            exit(1);
+        }
+        return;
+    }
+
+    if let Some(Subcommand::VerifyDeploy) = &config.subcommand {
+        if let Err(e) = verify_deploy(&config) {
+            eprintln!("{}", e.to_string().error());
+            exit(1);
+        }
+        return;
+    }
+
+    if let Some(Subcommand::Airdrop { amount }) = &config.subcommand {
+        if let Err(e) = airdrop(&config, *amount) {
+            eprintln!("{}", e.to_string().error());
+            exit(1);
+        }
+        return;
+    }
+
+    if let Some(Subcommand::Why { location }) = &config.subcommand {
+        let explanation = why::parse_location(location)
+            .and_then(|(file, line)| why::explain(Path::new(&config.path), &file, line));
+        match explanation {
+            Ok(explanation) => print_explanation(&explanation),
+            Err(e) => {
+                eprintln!("{}", e.to_string().error());
+                exit(1);
+            }
+        }
+        return;
+    }
+
     if config.restore {
+        match preview_restore(&config.path, &config.only) {
+            Ok(Some(diff)) => {
+                println!("{}", "Restoring will apply these changes:".warning());
+                println!("{}", diff);
+            }
+            Ok(None) => println!("{}", "All files already match their backups.".info()),
+            Err(e) => {
+                eprintln!("{}", e.to_string().error());
+                exit(1);
+            }
+        }
+
+        if config.dry_run {
+            match restore_targets(&config.path, &config.only) {
+                Ok(targets) => print_restore_targets(&targets, config.purge_backup),
+                Err(e) => {
+                    eprintln!("{}", e.to_string().error());
+                    exit(1);
+                }
+            }
+            println!("{}", "Dry run enabled. Nothing restored.".warning());
+            return;
+        }
+
+        if !config.yes && !confirm("Restore these files from backup?") {
+            println!("{}", "Restore cancelled.".warning());
+            return;
+        }
+
         progress.set_message("Restoring from backup...");
         progress.enable_steady_tick(Duration::from_millis(100)); // Using Duration
-        match restore_backup(&config.path) {
-            Ok(_) => {
-                progress.finish_with_message("Backup restored successfully.".green().to_string());
-                println!("{}", "Restore complete.".green());
+        match restore_backup(&config.path, config.purge_backup, &config.only) {
+            Ok(files) => {
+                progress.finish_with_message("Backup restored successfully.".success().to_string());
+                for file in files {
+                    println!("  restored {}", file);
+                }
+                println!("{}", "Restore complete.".success());
+            }
+            Err(e) => {
+                progress.finish_with_message("Restore failed.".error().to_string());
+                eprintln!("{}", e.to_string().error());
+                exit(1);
+            }
+        }
+        return;
+    }
+
+    if config.recursive {
+        if config.subcommand.is_some() {
+            eprintln!(
+                "{}",
+                "--recursive cannot be combined with a subcommand".error()
+            );
+            exit(1);
+        }
+        run_recursive(&config);
+        return;
+    }
+
+    if config.preflight.is_some() {
+        if let Err(e) = run_preflight(Path::new(&config.path), config.offline) {
+            eprintln!("{}", e.to_string().error());
+            exit(1);
+        }
+    }
+
+    if let Some(Subcommand::Plan { out }) = &config.subcommand {
+        match build_plan(&config).and_then(|plan| plan.save(out).map(|_| plan)) {
+            Ok(plan) => {
+                print_warnings(&plan.warnings);
+                print_compatibility(&plan.compatibility);
+                println!(
+                    "{}",
+                    format!("Plan with {} edit(s) written to {}", plan.edits.len(), out).success()
+                );
+                println!("Review it, then run: soon-migrate apply {}", out);
             }
             Err(e) => {
-                progress.finish_with_message("Restore failed.".red().to_string());
-                eprintln!("{}", e.to_string().red());
+                eprintln!("{}", e.to_string().error());
                 exit(1);
             }
         }
         return;
     }
 
+    // A baseline that cannot be read should stop the run before it writes
+    let baseline = match config.baseline.as_deref().map(Report::load).transpose() {
+        Ok(baseline) => baseline,
+        Err(e) => {
+            eprintln!("{}", e.to_string().error());
+            exit(1);
+        }
+    };
+
     progress.set_message("Migrating project...");
     progress.enable_steady_tick(Duration::from_millis(100)); // Using Duration
 
-    match run_migration(&config) {
-        Ok(_) => {
-            progress.finish_with_message("Migration completed successfully.".green().to_string());
-            println!("{}", "Migration successful!".green());
-            println!("{}", "Next steps:".yellow());
-            println!("1. Update your dependencies.");
-            println!("2. Test your project.");
-            println!("3. Deploy to SOON Network.");
+    let project = Path::new(&config.path);
+    let mut commit = None;
+    let mut branch = None;
+    let outcome = if config.git {
+        git::start_branch(project).map(|started| branch = Some(started))
+    } else {
+        Ok(())
+    }
+    .and_then(|_| match &config.subcommand {
+        Some(Subcommand::Apply { plan }) => {
+            Plan::load(plan).and_then(|plan| execute_plan(&config, plan))
+        }
+        _ => run_migration(&config),
+    })
+    .and_then(|result| {
+        if config.git {
+            commit = git::commit_migration(project, &result)?;
+        }
+        Ok(result)
+    });
+
+    let notified = notify::notify_run(&config, &outcome);
+
+    let succeeded = match outcome {
+        Ok(result) => {
+            progress.finish_with_message("Migration completed successfully.".success().to_string());
+            print_result(&result);
+            if let Some(baseline) = &baseline {
+                print_comparison(&baseline::compare(baseline, &result));
+            }
+            if let Some(report) = &config.report {
+                println!("{}", format!("Report written to {}", report).info());
+            }
+            if let Some(commit) = &commit {
+                println!(
+                    "{}",
+                    format!("Committed {} on branch {}", commit, git::MIGRATION_BRANCH).info()
+                );
+            }
+            true
         }
         Err(e) => {
-            progress.finish_with_message("Migration failed.".red().to_string());
-            eprintln!("{}", e.to_string().red());
+            progress.finish_with_message("Migration failed.".error().to_string());
+            eprintln!("{}", e.to_string().error());
+            if let Some(branch) = branch {
+                match branch.abandon(project) {
+                    Ok(()) => println!(
+                        "{}",
+                        format!("Deleted branch {}", git::MIGRATION_BRANCH).info()
+                    ),
+                    Err(e) => eprintln!("{}", format!("Warning: {}", e).warning()),
+                }
+            }
+            false
+        }
+    };
+
+    if let Err(e) = notified {
+        eprintln!("{}", format!("Warning: {}", e).warning());
+    }
+    if !succeeded {
+        exit(1);
+    }
+}
+
+/// Builds the project before migrating it, for `--preflight build`.
+fn run_preflight(project: &Path, offline: bool) -> Result<(), MigrationError> {
+    println!("{}", "Preflight: building the project as it is...".info());
+    let command = preflight::build(project, offline)?;
+    println!("{}", format!("Preflight: {} succeeded.", command).success());
+    Ok(())
+}
+
+/// Migrates every Anchor project under `config.path` in turn, then prints a
+/// roll-up: each project's readiness and the blockers they share. With
+/// `--report`, the roll-up is written as one JSON file holding every
+/// project's report. Exits with an error if any project failed.
+fn run_recursive(config: &Config) {
+    let root = Path::new(&config.path);
+    let projects = workspace::anchor_projects(root);
+    if projects.is_empty() {
+        eprintln!(
+            "{}",
+            format!("No Anchor.toml found under {}", config.path).error()
+        );
+        exit(1);
+    }
+    println!(
+        "{}",
+        format!(
+            "Found {} Anchor project(s) under {}",
+            projects.len(),
+            config.path
+        )
+        .info()
+    );
+
+    let mut outcomes = Vec::new();
+    for project in projects {
+        let project_config = Config {
+            path: root.join(&project).to_string_lossy().into_owned(),
+            report: None,
+            ..config.clone()
+        };
+        println!();
+        println!("{}", format!("== {} ==", project).bold());
+
+        let outcome = if config.preflight.is_some() {
+            run_preflight(Path::new(&project_config.path), config.offline)
+                .and_then(|_| run_migration_for_report(&project_config))
+        } else {
+            run_migration_for_report(&project_config)
+        };
+        let (outcome, report) = match outcome {
+            Ok((result, report)) => (Ok(result), Ok(report)),
+            Err(e) => {
+                let message = e.to_string();
+                (Err(e), Err(message))
+            }
+        };
+        if let Err(e) = notify::notify_run(&project_config, &outcome) {
+            eprintln!("{}", format!("Warning: {}", e).warning());
+        }
+        match &outcome {
+            Ok(result) => print_result(result),
+            Err(e) => eprintln!("{}", e.to_string().error()),
+        }
+        outcomes.push((project, report));
+    }
+
+    let roll_up = RollUp::new(outcomes);
+    println!();
+    println!("{}", "Summary:".warning());
+    for project in &roll_up.projects {
+        match (&project.report, &project.error) {
+            (Some(report), _) => println!(
+                "{}",
+                format!(
+                    "  {}: readiness {}/100, {} change(s), {} warning(s)",
+                    project.path,
+                    project.readiness,
+                    report.changes.len(),
+                    report.warnings.len()
+                )
+                .success()
+            ),
+            (None, error) => println!(
+                "{}",
+                format!(
+                    "  {}: failed: {}",
+                    project.path,
+                    error.as_deref().unwrap_or_default()
+                )
+                .error()
+            ),
+        }
+    }
+    println!(
+        "  {} project(s), {} failed, {} change(s), {} warning(s)",
+        roll_up.totals.projects,
+        roll_up.totals.failed,
+        roll_up.totals.changes,
+        roll_up.totals.warnings
+    );
+    let common: Vec<_> = roll_up
+        .blockers
+        .iter()
+        .filter(|blocker| blocker.projects.len() > 1)
+        .collect();
+    if !common.is_empty() {
+        println!("{}", "Common blockers:".warning());
+        for blocker in common {
+            println!(
+                "  {} ({} projects: {})",
+                blocker.blocker,
+                blocker.projects.len(),
+                blocker.projects.join(", ")
+            );
+        }
+    }
+
+    if let Some(path) = &config.report {
+        if let Err(e) = roll_up.save(path) {
+            eprintln!("{}", e.to_string().error());
             exit(1);
         }
+        println!("{}", format!("Report written to {}", path).info());
+    }
+    if roll_up.totals.failed > 0 {
+        exit(1);
+    }
+}
+
+fn print_comparison(comparison: &Comparison) {
+    println!("{}", "Since the baseline:".warning());
+    let sections = [
+        ("resolved", '-', &comparison.resolved),
+        ("new", '+', &comparison.new),
+        ("unchanged", '=', &comparison.unchanged),
+    ];
+    for (label, marker, changes) in sections {
+        println!("  {} {}", changes.len(), label);
+        for change in changes {
+            let role = match change.severity {
+                Severity::Better => Role::Success,
+                Severity::Same => Role::Muted,
+                Severity::Worse => Role::Warning,
+                Severity::Blocking => Role::Error,
+            };
+            println!(
+                "{}",
+                format!("    {} {}", marker, change.description).styled(role)
+            );
+        }
+    }
+}
+
+fn print_warnings(warnings: &[Warning]) {
+    for warning in warnings {
+        println!("{}", format!("Warning: {}", warning).warning());
+    }
+}
+
+/// Compares SOON with the Solana cluster the project targets, for the
+/// network Anchor.toml points at. `solana_rpc` replaces the public Solana
+/// endpoint.
+fn compare_runtime(
+    config: &Config,
+    solana_rpc: Option<&str>,
+    findings: &[CompatFinding],
+) -> Result<RuntimeComparison, MigrationError> {
+    let endpoints = configured_endpoints(config)?;
+    let network = runtime::project_network(Path::new(&config.path), &endpoints);
+    runtime::compare(
+        findings,
+        &runtime::syscalls_used(Path::new(&config.path)),
+        &Rpc::new(&solana_rpc.map_or_else(|| solana_url(network), str::to_string)),
+        &Rpc::connect(&endpoints, network)?,
+    )
+}
+
+/// SOON endpoints from the defaults, `[rpc]` in the config file and the
+/// `--rpc-*` flags.
+fn configured_endpoints(config: &Config) -> Result<Endpoints, MigrationError> {
+    let settings = Settings::load(&config.path, config.config_file.as_deref())?;
+    Endpoints::resolve(
+        &settings,
+        config.rpc_devnet.as_deref(),
+        config.rpc_testnet.as_deref(),
+        config.rpc_mainnet.as_deref(),
+    )
+}
+
+/// Checks every SOON endpoint and prints what each one reports. Fails if
+/// any of them is misconfigured or cannot be reached, after trying the rest.
+fn check_rpc(config: &Config) -> Result<(), MigrationError> {
+    if config.offline {
+        return Err(MigrationError::RpcError(
+            "--offline is set, so no endpoint was contacted".to_string(),
+        ));
+    }
+
+    let endpoints = configured_endpoints(config)?;
+    let mut failed = Vec::new();
+    println!("{}", "SOON RPC endpoints:".info());
+    for network in [
+        SoonNetwork::Devnet,
+        SoonNetwork::Testnet,
+        SoonNetwork::Mainnet,
+    ] {
+        let rpc = match Rpc::connect(&endpoints, network) {
+            Ok(rpc) => rpc,
+            Err(e) => {
+                println!(
+                    "  {:<8} {}: {}",
+                    network.as_str(),
+                    url_host(endpoints.url(network)),
+                    format!("not checked ({})", e).error()
+                );
+                failed.push(network.to_string());
+                continue;
+            }
+        };
+        match rpc::check(&rpc) {
+            Ok(health) => {
+                let status = if health.status == "ok" {
+                    health.status.success()
+                } else {
+                    health.status.warning()
+                };
+                println!(
+                    "  {:<8} {}: {}, {} ms, solana-core {}, slot {}",
+                    network.as_str(),
+                    rpc.host(),
+                    status,
+                    health.latency.as_millis(),
+                    health.version,
+                    health.slot
+                );
+            }
+            Err(e) => {
+                println!(
+                    "  {:<8} {}: {}",
+                    network.as_str(),
+                    rpc.host(),
+                    format!("unreachable ({})", e).error()
+                );
+                failed.push(network.to_string());
+            }
+        }
+    }
+
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(MigrationError::RpcError(format!(
+            "SOON {} could not be checked",
+            failed.join(", ")
+        )))
+    }
+}
+
+/// Times every SOON endpoint and, when `solana` is given, the Solana one
+/// for the project's cluster (or the URL it holds), and prints a table.
+/// Fails if any endpoint is misconfigured or cannot be reached, after
+/// timing the rest.
+fn bench_rpc(
+    config: &Config,
+    requests: usize,
+    solana: Option<Option<&str>>,
+) -> Result<(), MigrationError> {
+    if config.offline {
+        return Err(MigrationError::RpcError(
+            "--offline is set, so no endpoint was contacted".to_string(),
+        ));
+    }
+
+    let endpoints = configured_endpoints(config)?;
+    // Each endpoint with its host, which a misconfigured one still has
+    let mut targets: Vec<(String, String, Result<Rpc, MigrationError>)> = Vec::new();
+    for network in [
+        SoonNetwork::Devnet,
+        SoonNetwork::Testnet,
+        SoonNetwork::Mainnet,
+    ] {
+        targets.push((
+            format!("soon {}", network.as_str()),
+            url_host(endpoints.url(network)).to_string(),
+            Rpc::connect(&endpoints, network),
+        ));
+    }
+    if let Some(solana_rpc) = solana {
+        let network = runtime::project_network(Path::new(&config.path), &endpoints);
+        let rpc = Rpc::new(&solana_rpc.map_or_else(|| solana_url(network), str::to_string));
+        targets.push((
+            format!("solana {}", network.as_str()),
+            rpc.host().to_string(),
+            Ok(rpc),
+        ));
+    }
+
+    println!(
+        "{}",
+        format!("RPC latency over {} request(s) per method:", requests).info()
+    );
+    println!(
+        "  {:<16} {:<28} {:<20} {:>8} {:>8} {:>8} {:>8}",
+        "endpoint", "host", "method", "min ms", "p50 ms", "max ms", "req/s"
+    );
+    let mut failed = Vec::new();
+    for (name, host, rpc) in &targets {
+        let rpc = match rpc {
+            Ok(rpc) => rpc,
+            Err(e) => {
+                println!(
+                    "  {:<16} {:<28} {}",
+                    name,
+                    host,
+                    format!("not timed ({})", e).error()
+                );
+                failed.push(name.as_str());
+                continue;
+            }
+        };
+        for method in rpc::BENCH_METHODS {
+            match rpc::bench(rpc, method, requests) {
+                Ok(bench) => println!(
+                    "  {:<16} {:<28} {:<20} {:>8} {:>8} {:>8} {:>8}",
+                    name,
+                    host,
+                    method,
+                    bench.min.as_millis(),
+                    bench.median.as_millis(),
+                    bench.max.as_millis(),
+                    bench.per_second
+                ),
+                Err(e) => {
+                    println!(
+                        "  {:<16} {:<28} {}",
+                        name,
+                        host,
+                        format!("unreachable ({})", e).error()
+                    );
+                    failed.push(name.as_str());
+                    break;
+                }
+            }
+        }
+    }
+
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(MigrationError::RpcError(format!(
+            "{} could not be timed",
+            failed.join(", ")
+        )))
+    }
+}
+
+/// Deploys the project to the SOON network it was migrated to, and lists
+/// the program IDs recorded for it.
+fn deploy(config: &Config) -> Result<(), MigrationError> {
+    if config.offline {
+        return Err(MigrationError::DeployFailed(
+            "--offline is set, so nothing was deployed".to_string(),
+        ));
+    }
+
+    let endpoints = configured_endpoints(config)?;
+    let project = Path::new(&config.path);
+    let network = runtime::project_network(project, &endpoints);
+    let deployed = deploy::deploy(project, network, &endpoints, config.verbose)?;
+
+    println!("{}", format!("Deployed to SOON {}:", network).success());
+    for (name, id) in &deployed {
+        println!("  {}: {}", name, id);
+    }
+    if project.join("Anchor.toml").exists() {
+        println!(
+            "{}",
+            "Program IDs recorded in Anchor.toml and soon-migrate.toml; check them with soon-migrate verify-deploy."
+                .muted()
+        );
+    } else {
+        println!("{}", "Program IDs recorded in soon-migrate.toml.".muted());
+    }
+    Ok(())
+}
+
+/// Compares the build of `program` with what SOON runs at its address, and
+/// fails unless they are the same.
+fn verify_program(config: &Config, program: &str) -> Result<(), MigrationError> {
+    if config.offline {
+        return Err(MigrationError::RpcError(
+            "--offline is set, so SOON was not contacted".to_string(),
+        ));
+    }
+
+    let endpoints = configured_endpoints(config)?;
+    let project = Path::new(&config.path);
+    let network = runtime::project_network(project, &endpoints);
+    let rpc = Rpc::connect(&endpoints, network)?;
+    let hash = deployed::verify_program(project, network, &rpc, program)?;
+
+    println!(
+        "{}",
+        format!("{} ({}) on SOON {}:", program, hash.program_id, network).info()
+    );
+    println!("  local build   sha256 {}", hash.local);
+    match &hash.on_chain {
+        Some(on_chain) => println!("  deployed      sha256 {}", on_chain),
+        None => println!("  deployed      {}", "nothing deployed".error()),
+    }
+
+    if hash.matches() {
+        println!("{}", "Match: the deployed program is this build.".success());
+        Ok(())
+    } else if hash.on_chain.is_none() {
+        Err(MigrationError::VerifyFailed(format!(
+            "{} is not deployed on SOON {}",
+            program, network
+        )))
+    } else {
+        Err(MigrationError::VerifyFailed(format!(
+            "mismatch: the program deployed at {} is not this build of {}",
+            hash.program_id, program
+        )))
+    }
+}
+
+/// Reports the tokens the project references and how to bring each one to
+/// the SOON network it targets. With `online`, the bridge API from the
+/// config file says which of them it supports.
+fn bridge(config: &Config, online: bool, out: Option<&str>) -> Result<(), MigrationError> {
+    let settings = Settings::load(&config.path, config.config_file.as_deref())?;
+    let endpoints = configured_endpoints(config)?;
+    let project = Path::new(&config.path);
+    let network = runtime::project_network(project, &endpoints);
+    let mut assets = bridge::scan_mints(project, &settings.bridge);
+
+    if online {
+        let api = settings.bridge.api.as_deref().ok_or_else(|| {
+            MigrationError::ConfigError(
+                "--online needs the bridge API under [bridge] api in soon-migrate.toml".to_string(),
+            )
+        })?;
+        bridge::check_support(api, &mut assets)?;
+    }
+
+    if assets.is_empty() {
+        println!("{}", "No SPL mints found; nothing to bridge.".success());
+    } else {
+        println!(
+            "{}",
+            format!("Tokens to bring to SOON {}:", network).warning()
+        );
+        for asset in &assets {
+            match &asset.symbol {
+                Some(symbol) => println!("  {} ({}): {}", symbol, asset.name, asset.mint),
+                None => println!("  {} {}", asset.name, asset.mint),
+            }
+            println!(
+                "{}",
+                format!("    used at {}", asset.evidence.join(", ")).muted()
+            );
+            if !asset.note.is_empty() {
+                println!("    {}", asset.note);
+            }
+            for (step, text) in bridge::steps(asset, network, endpoints.url(network))
+                .iter()
+                .enumerate()
+            {
+                println!("    {}. {}", step + 1, text);
+            }
+        }
+    }
+
+    if let Some(out) = out {
+        let json = serde_json::to_string_pretty(&assets)
+            .map_err(|e| MigrationError::ReportError(e.to_string()))?;
+        fs::write(out, json + "\n")
+            .map_err(|e| MigrationError::ReportError(format!("{}: {}", out, e)))?;
+    }
+    Ok(())
+}
+
+/// Reports the oracle feeds the project reads and the APRO feeds recorded for
+/// them. With `online`, SOON says whether each APRO feed exists and was
+/// written within `max_age` seconds.
+fn feeds(
+    config: &Config,
+    online: bool,
+    max_age: u64,
+    out: Option<&str>,
+) -> Result<(), MigrationError> {
+    let settings = Settings::load(&config.path, config.config_file.as_deref())?;
+    let project = Path::new(&config.path);
+    let mut feeds = feeds::scan_feeds(project, &settings.oracle);
+
+    let mut network = None;
+    if online {
+        let endpoints = configured_endpoints(config)?;
+        let target = runtime::project_network(project, &endpoints);
+        feeds::verify(&Rpc::connect(&endpoints, target)?, &mut feeds, max_age)?;
+        network = Some(target);
+    }
+
+    if feeds.is_empty() {
+        println!("{}", "No oracle feeds found.".success());
+    } else {
+        println!("{}", "Oracle feeds:".warning());
+    }
+    for feed in &feeds {
+        let Some(apro) = &feed.apro_feed else {
+            println!("  {}: {}", feed.feed, "no APRO feed recorded".warning());
+            println!(
+                "{}",
+                format!("    used at {}", feed.evidence.join(", ")).muted()
+            );
+            println!(
+                "    Record the APRO feed for the same pair in soon-migrate.toml under [oracle.feeds] as \"{}\" = \"<APRO feed>\".",
+                feed.feed
+            );
+            continue;
+        };
+        let status = match (feed.status, feed.age_secs) {
+            (Some(FeedStatus::Live), Some(age)) => format!("live, updated {}s ago", age).success(),
+            (Some(FeedStatus::Stale), Some(age)) => {
+                format!("stale, last updated {}s ago", age).error()
+            }
+            (Some(FeedStatus::Stale), None) => "stale, never updated".error(),
+            (Some(FeedStatus::Missing), _) => match network {
+                Some(network) => format!("not found on SOON {}", network).error(),
+                None => "not found on SOON".error(),
+            },
+            _ => "not checked".muted(),
+        };
+        println!("  {} -> APRO {}: {}", feed.feed, apro, status);
+        println!(
+            "{}",
+            format!("    used at {}", feed.evidence.join(", ")).muted()
+        );
+        if matches!(feed.status, Some(FeedStatus::Stale | FeedStatus::Missing)) {
+            println!(
+                "    {}",
+                "Check the APRO feed address before migrating to it.".warning()
+            );
+        }
+    }
+
+    if let Some(out) = out {
+        let json = serde_json::to_string_pretty(&feeds)
+            .map_err(|e| MigrationError::ReportError(e.to_string()))?;
+        fs::write(out, json + "\n")
+            .map_err(|e| MigrationError::ReportError(format!("{}: {}", out, e)))?;
+    }
+    Ok(())
+}
+
+/// Checks the programs of the SOON table the project points at, and fails
+/// unless every one of them is deployed.
+fn verify_deploy(config: &Config) -> Result<(), MigrationError> {
+    if config.offline {
+        return Err(MigrationError::RpcError(
+            "--offline is set, so SOON was not contacted".to_string(),
+        ));
+    }
+
+    let endpoints = configured_endpoints(config)?;
+    let project = Path::new(&config.path);
+    let network = runtime::project_network(project, &endpoints);
+    let rpc = Rpc::connect(&endpoints, network)?;
+    let programs = deployed::verify(project, network, &rpc)?;
+
+    println!(
+        "{}",
+        format!("Programs on SOON {} ({}):", network, rpc.host()).info()
+    );
+    let mut undeployed = Vec::new();
+    for program in &programs {
+        let status = match &program.status {
+            DeployStatus::Deployed {
+                authority: Some(authority),
+            } => format!("deployed, upgrade authority {}", authority).success(),
+            DeployStatus::Deployed { authority: None } => "deployed, not upgradeable".success(),
+            DeployStatus::NotExecutable => "account exists but is not a program".error(),
+            DeployStatus::Missing => "not deployed".error(),
+        };
+        println!("  {} ({}): {}", program.name, program.program_id, status);
+        if !matches!(program.status, DeployStatus::Deployed { .. }) {
+            undeployed.push(program.name.as_str());
+        }
+    }
+
+    if undeployed.is_empty() {
+        Ok(())
+    } else {
+        Err(MigrationError::RpcError(format!(
+            "not deployed on SOON {}: {}; run anchor deploy",
+            network,
+            undeployed.join(", ")
+        )))
+    }
+}
+
+/// Requests `amount` SOL on SOON devnet for the project's wallet and
+/// prints its balance once the airdrop is confirmed.
+fn airdrop(config: &Config, amount: f64) -> Result<(), MigrationError> {
+    if config.offline {
+        return Err(MigrationError::RpcError(
+            "--offline is set, so no airdrop was requested".to_string(),
+        ));
+    }
+
+    let address = wallet::wallet_address(Path::new(&config.path))?;
+    let endpoints = configured_endpoints(config)?;
+    let rpc = Rpc::connect(&endpoints, SoonNetwork::Devnet)?;
+
+    println!(
+        "{}",
+        format!(
+            "Requesting {} SOL for {} on SOON devnet...",
+            amount, address
+        )
+        .info()
+    );
+    let signature = wallet::airdrop(&rpc, &address, wallet::lamports(amount))?;
+
+    let balance = wallet::balance(&rpc, &address)?;
+    println!("{}", format!("Airdrop confirmed ({})", signature).success());
+    println!(
+        "  {} now holds {} SOL on SOON devnet",
+        address,
+        balance as f64 / wallet::LAMPORTS_PER_SOL as f64
+    );
+    Ok(())
+}
+
+fn print_runtime(comparison: &RuntimeComparison) {
+    println!("{}", "Runtime:".warning());
+    for (chain, node) in [("Solana", &comparison.solana), ("SOON", &comparison.soon)] {
+        let feature_set = node
+            .feature_set
+            .map_or("unknown".to_string(), |id| id.to_string());
+        println!(
+            "  {} ({}): solana-core {}, feature set {}",
+            chain, node.host, node.core, feature_set
+        );
+    }
+    if comparison.feature_sets_differ() {
+        println!(
+            "  {}",
+            "The feature sets differ; test on SOON before relying on recently activated features."
+                .warning()
+        );
+    }
+    if comparison.missing.is_empty() {
+        println!(
+            "  {}",
+            "Everything found above exists on SOON as well.".success()
+        );
+    }
+    for program in &comparison.missing {
+        println!(
+            "  {}",
+            format!("{} exists on Solana but not on SOON", program).error()
+        );
+    }
+    if !comparison.features_missing.is_empty() {
+        println!("  Features active on Solana but not on SOON:");
+    }
+    for feature in &comparison.features_missing {
+        println!(
+            "    {}",
+            format!("{} ({})", feature.name, feature.id).error()
+        );
+    }
+    if !comparison.features_extra.is_empty() {
+        println!("  Features active on SOON but not on Solana:");
+    }
+    for feature in &comparison.features_extra {
+        println!(
+            "    {}",
+            format!("{} ({})", feature.name, feature.id).warning()
+        );
+    }
+    if comparison.features_unrelated > 0 {
+        println!(
+            "  {}",
+            format!(
+                "{} other feature(s) differ, none of them tied to a program or syscall the project uses.",
+                comparison.features_unrelated
+            )
+            .muted()
+        );
+    }
+}
+
+fn print_compatibility(findings: &[CompatFinding]) {
+    let (sysvars, programs): (Vec<&CompatFinding>, Vec<&CompatFinding>) =
+        findings.iter().partition(|finding| finding.is_sysvar());
+    // Oracles used together get advice for the pattern instead of their own
+    let redundancy = oracle_redundancy(findings);
+    let consolidated = |finding: &CompatFinding| {
+        redundancy
+            .as_ref()
+            .is_some_and(|redundancy| redundancy.programs.contains(&finding.program))
+    };
+    if !programs.is_empty() {
+        println!("{}", "SOON compatibility:".warning());
+        for finding in programs {
+            print_finding(finding, consolidated(finding));
+        }
+    }
+    if !sysvars.is_empty() {
+        println!("{}", "Sysvars on SOON:".warning());
+        for finding in sysvars {
+            print_finding(finding, false);
+        }
+    }
+    if let Some(redundancy) = redundancy {
+        println!("{}", "Oracles used together:".warning());
+        println!("  {}", redundancy.programs.join(", "));
+        println!("    {}", format!("APRO: {}", redundancy.advice).info());
+    }
+    if let Some(complexity) = complexity(findings) {
+        println!(
+            "{} {}, about {} line(s) to change {}",
+            "Migration effort:".warning(),
+            complexity.level,
+            complexity.lines,
+            format!("(complexity {}/100)", complexity.score).muted()
+        );
+    }
+}
+
+/// Prints one finding; `consolidated` leaves out its APRO advice, given for
+/// all the oracles used together instead.
+fn print_finding(finding: &CompatFinding, consolidated: bool) {
+    let availability = match finding.availability {
+        Availability::Available => "available".success(),
+        Availability::Moved => match &finding.soon_program_id {
+            Some(id) => format!("at {} on SOON", id).warning(),
+            None => "moved".warning(),
+        },
+        Availability::Absent => "not on SOON".error(),
+        Availability::Unverified if finding.dev_only => "unverified, tests only".muted(),
+        Availability::Unverified if finding.client_only => "unverified, client code only".warning(),
+        Availability::Unverified => "unverified".warning(),
+    };
+    println!(
+        "  {}: {} {}",
+        finding.describe(),
+        availability,
+        format!("(confidence {})", finding.confidence).muted()
+    );
+    println!("    {}", finding.note);
+    if let Some(apro) = finding.apro.as_ref().filter(|_| !consolidated) {
+        let label = match finding.model {
+            Some(model) => format!("APRO, from a {} integration", model),
+            None => "APRO".to_string(),
+        };
+        println!("    {}", format!("{}: {}", label, apro).info());
+    }
+    let mut used_at = format!("used at {}", finding.evidence.join(", "));
+    if finding.uses > finding.evidence.len() {
+        used_at.push_str(&format!(
+            " and {} more place(s)",
+            finding.uses - finding.evidence.len()
+        ));
+    }
+    if !finding.crates.is_empty() {
+        println!("    used by {}", finding.crates.join(", "));
+    }
+    if !finding.owners.is_empty() {
+        println!("    owned by {}", finding.owners.join(", "));
+    }
+    println!("    {}", used_at.muted());
+    if let Some(effort) = &finding.effort {
+        println!(
+            "    {}",
+            format!(
+                "effort: {}, about {} line(s): {} CPI call(s), {} account constraint(s), {} call site(s)",
+                effort.level,
+                effort.lines,
+                effort.cpi_calls,
+                effort.account_constraints,
+                effort.call_sites
+            )
+            .muted()
+        );
+    }
+    // CPIs into programs that are part of SOON's SVM need no attention
+    let missing = matches!(
+        finding.availability,
+        Availability::Unverified | Availability::Absent
+    );
+    if missing && !finding.cpi_calls.is_empty() {
+        println!(
+            "    {}",
+            format!(
+                "called through CPI at {}; the program itself must exist on SOON",
+                finding.cpi_calls.join(", ")
+            )
+            .warning()
+        );
+    }
+}
+
+fn print_result(result: &MigrationResult) {
+    print_warnings(&result.warnings);
+    print_compatibility(&result.compatibility);
+    println!("{}", "Migration successful!".success());
+    println!("{}", "Next steps:".warning());
+    for (i, step) in result.next_steps().iter().enumerate() {
+        println!("{}. {}", i + 1, step.action);
+        println!("   {}", format!("because {}", step.evidence).muted());
+    }
+}
+
+fn print_restore_targets(targets: &[RestoreTarget], purge_backup: bool) {
+    for target in targets {
+        let current = target
+            .current_sha256
+            .as_deref()
+            .map_or("missing", |hash| &hash[..12]);
+        let Some(restored) = &target.restored_sha256 else {
+            // Created by the migration, so there is no backup to restore
+            if target.is_unchanged() {
+                println!(
+                    "  {} was created by the migration and is already gone",
+                    target.file
+                );
+            } else {
+                println!("  would remove {} (sha256 {})", target.file, current);
+            }
+            continue;
+        };
+        if target.is_unchanged() {
+            println!(
+                "  {} already matches {} ({})",
+                target.file, target.backup, current
+            );
+        } else {
+            println!(
+                "  would restore {} from {} (sha256 {} -> {})",
+                target.file,
+                target.backup,
+                current,
+                &restored[..12]
+            );
+        }
+        if purge_backup {
+            println!("  would delete {}", target.backup);
+        }
+    }
+}
+
+fn print_explanation(explanation: &why::Explanation) {
+    let location = format!("{}:{}", explanation.file, explanation.line);
+    let Some(original) = &explanation.original else {
+        println!("{} was not changed by soon-migrate.", location);
+        return;
+    };
+
+    println!("{}", location.bold());
+    println!("  now: {}", explanation.current.success());
+    if original.is_empty() {
+        println!("  was: {}", "(added by the migration)".muted());
+    }
+    for line in original {
+        println!("  was: {}", line.error());
+    }
+
+    if explanation.changes.is_empty() {
+        println!(
+            "  {}",
+            "No recorded change explains this line; it may have been edited after the migration."
+                .warning()
+        );
+    }
+    for change in &explanation.changes {
+        println!(
+            "  changed by {}: {}",
+            change.kind.as_str().info(),
+            change.detail
+        );
+    }
+}
+
+/// Asks a yes/no question on stdin. Anything but an explicit yes
+/// (including EOF on a non-interactive stdin) counts as no.
+fn confirm(question: &str) -> bool {
+    print!("{} [y/N] ", question);
+    let _ = io::stdout().flush();
+
+    let mut answer = String::new();
+    if io::stdin().lock().read_line(&mut answer).is_err() {
+        return false;
     }
+    matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes")
 }
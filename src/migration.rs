@@ -1,48 +1,403 @@
+use crate::assumptions::{rent_warnings, slot_timing_warnings};
 use crate::cli::Config;
+use crate::compat::{scan_ecosystem, ProjectSize, Rule, ScanOptions};
+use crate::diff::{colorize, render_diff, unified_diff};
+use crate::endpoints::{classify_url, is_url, redact_urls, url_host, Endpoints, SoonNetwork};
 use crate::errors::MigrationError;
-use colored::*;
-use serde::{Deserialize, Serialize};
+use crate::journal::{Journal, JournalEntry};
+use crate::lock::Lock;
+use crate::plan::{diff_toml, render_file, Edit, EditOp, Plan, PLAN_VERSION};
+use crate::preflight;
+use crate::report::{load_keypair, sha256_hex, Provenance, Report};
+use crate::result::{Change, ChangeKind, Deployment, MigrationResult, Warning, WarningKind};
+use crate::rewrite::{
+    build_files, client_files, docker_files, inject_env_endpoints, leftover_endpoint_warnings,
+    local_validator_warnings, lookup_table_warnings, rewrite_file, workflow_files,
+};
+use crate::rpc::{self, Rpc};
+use crate::settings::Settings;
+use crate::theme::Styled;
+use crate::toolchain::{
+    client_library_warnings, dependency_matrix_advice, native_toolchain_advice, parse_version,
+    pinned_version, toolchain_advice,
+};
+use crate::workspace::{
+    find_declare_id, find_seahorse_declare_id, find_solang_program_id, native_program_crates,
+    program_crates, solang_programs,
+};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::Path;
 
-#[derive(Deserialize, Serialize, Debug)]
-struct Provider {
-    cluster: String,
-    wallet: String,
+/// Maps a Solana cluster name or RPC URL to the SOON network and RPC endpoint
+/// it should use. URLs that are already one of the SOON endpoints keep their
+/// network; other URLs go by `custom_cluster`.
+fn map_cluster_to_soon<'a>(
+    cluster: &str,
+    endpoints: &'a Endpoints,
+    custom: Option<SoonNetwork>,
+) -> (SoonNetwork, &'a str) {
+    let network = soon_endpoint_network(cluster, endpoints)
+        .or(custom)
+        .unwrap_or_else(|| SoonNetwork::from_cluster(cluster));
+    (network, endpoints.url(network))
 }
 
-#[derive(Deserialize, Serialize, Debug)]
-struct Programs {
-    #[serde(rename = "localnet")]
-    localnet: std::collections::HashMap<String, String>,
+fn soon_endpoint_network(cluster: &str, endpoints: &Endpoints) -> Option<SoonNetwork> {
+    [
+        SoonNetwork::Devnet,
+        SoonNetwork::Testnet,
+        SoonNetwork::Mainnet,
+    ]
+    .into_iter()
+    .find(|network| endpoints.url(*network) == cluster)
 }
 
-#[derive(Deserialize, Serialize, Debug)]
-struct AnchorToml {
-    provider: Provider,
-    programs: Programs,
-    #[serde(flatten)]
-    extra: std::collections::HashMap<String, toml::Value>,
+/// Classifies a custom RPC URL in `provider.cluster`, with a warning that
+/// explains how. Online, the endpoint's genesis hash says which Solana
+/// cluster it serves; offline, or when it cannot answer, the network named
+/// in its host is the best guess, and devnet the fallback. Only the host is
+/// echoed since provider URLs usually carry API keys.
+fn custom_cluster(
+    cluster: &str,
+    endpoints: &Endpoints,
+    online: bool,
+) -> Option<(SoonNetwork, Warning)> {
+    if !is_url(cluster) || soon_endpoint_network(cluster, endpoints).is_some() {
+        return None;
+    }
+
+    let host = url_host(cluster);
+    let mut unanswered = String::new();
+    if online {
+        match Rpc::new(cluster).genesis_hash() {
+            Ok(hash) => {
+                let (network, message) = match rpc::solana_cluster(&hash) {
+                    Some((solana, network)) => (
+                        network,
+                        format!(
+                            "provider.cluster is a custom RPC URL ({}) serving Solana {}; treating it as a {} endpoint",
+                            host, solana, network
+                        ),
+                    ),
+                    None => (
+                        SoonNetwork::Devnet,
+                        format!(
+                            "provider.cluster is a custom RPC URL ({}) whose genesis hash matches no Solana cluster; defaulting to SOON devnet",
+                            host
+                        ),
+                    ),
+                };
+                return Some((
+                    network,
+                    Warning::new(WarningKind::CustomCluster, message).in_file("Anchor.toml"),
+                ));
+            }
+            Err(e) => unanswered = format!(" (its genesis hash could not be read: {})", e),
+        }
+    }
+
+    let (network, message) = match classify_url(cluster) {
+        Some(network) => (
+            network,
+            format!(
+                "provider.cluster is a custom RPC URL ({}){}; treating it as a {} endpoint by its host name",
+                host, unanswered, network
+            ),
+        ),
+        None => (
+            SoonNetwork::Devnet,
+            format!(
+                "provider.cluster is a custom RPC URL ({}){} on an unrecognised network; defaulting to SOON devnet",
+                host, unanswered
+            ),
+        ),
+    };
+    let mut warning = Warning::new(WarningKind::CustomCluster, message).in_file("Anchor.toml");
+    if !online {
+        warning = warning.recommend(
+            "Run with --online to classify the endpoint by its genesis hash instead of its host name.",
+        );
+    }
+    Some((network, warning))
+}
+
+/// Options for the compatibility scan of `config.path`, sized to the
+/// project. What was picked is printed for a large project, and for a small
+/// one with `--verbose`.
+pub fn scan_options<'a>(config: &'a Config, rules: &'a [Rule]) -> ScanOptions<'a> {
+    let options = ScanOptions {
+        paths: &config.paths,
+        exclude: &config.exclude,
+        skip_tests: config.skip_tests,
+        no_cache: config.no_cache,
+        min_confidence: config.min_confidence,
+        rules,
+        parallel: false,
+    };
+    let size = ProjectSize::measure(Path::new(&config.path), &options);
+    let options = options.sized_for(&size);
+    let measured = format!(
+        "{} file(s), {:.1} MB",
+        size.files,
+        size.bytes as f64 / (1024.0 * 1024.0)
+    );
+    if size.is_large() {
+        let cached = if options.no_cache {
+            ""
+        } else {
+            " and caching what each contains"
+        };
+        println!(
+            "{}",
+            format!(
+                "Large project ({}): scanning files in parallel{}. Leave out vendored or generated code with --exclude to scan faster.",
+                measured, cached
+            )
+            .info()
+        );
+    } else if config.verbose {
+        println!(
+            "{}",
+            format!(
+                "Small project ({}): scanning files one at a time, without the cache.",
+                measured
+            )
+            .muted()
+        );
+    }
+    options
+}
+
+pub fn run_migration(config: &Config) -> Result<MigrationResult, MigrationError> {
+    execute_plan(config, build_plan(config)?)
 }
 
-fn map_cluster_to_soon(cluster: &str) -> &'static str {
-    // Always return devnet RPC for now, regardless of input cluster
-    "https://rpc.devnet.soo.network/rpc"
+/// Migrates like `run_migration`, but hands back the report instead of
+/// writing it to `config.report`, for a roll-up of several projects.
+pub fn run_migration_for_report(
+    config: &Config,
+) -> Result<(MigrationResult, Report), MigrationError> {
+    let (result, report) = migrate(config, build_plan(config)?, true)?;
+    Ok((result, report.expect("a report was asked for")))
 }
 
-pub fn run_migration(config: &Config) -> Result<(), MigrationError> {
-    validate_anchor_project(&config.path)?;
+/// Applies `plan`, or in a dry run prints the diff it would make, and writes
+/// the report if one was asked for.
+pub fn execute_plan(config: &Config, mut plan: Plan) -> Result<MigrationResult, MigrationError> {
+    // Saved plans have their URLs redacted; the endpoints hold the real ones
+    let settings = Settings::load(&config.path, config.config_file.as_deref())?;
+    let endpoints = Endpoints::resolve(
+        &settings,
+        config.rpc_devnet.as_deref(),
+        config.rpc_testnet.as_deref(),
+        config.rpc_mainnet.as_deref(),
+    )?;
+    plan.restore_urls(Path::new(&config.path), &endpoints)?;
 
-    let anchor_toml_path = Path::new(&config.path).join("Anchor.toml");
+    let (result, report) = migrate(config, plan, config.report.is_some())?;
+    if let (Some(path), Some(report)) = (&config.report, report) {
+        report.save(path)?;
+    }
+    Ok(result)
+}
 
-    // Backup original Anchor.toml
-    let backup_path = anchor_toml_path.with_extension("toml.bak");
-    fs::copy(&anchor_toml_path, &backup_path)
-        .map_err(|e| MigrationError::BackupFailed(e.to_string()))?;
+/// Applies `plan` and, when `with_report` is set, builds the signed report.
+fn migrate(
+    config: &Config,
+    plan: Plan,
+    with_report: bool,
+) -> Result<(MigrationResult, Option<Report>), MigrationError> {
+    // Held until the backups, the journal and the report are all written
+    let _lock = if config.dry_run {
+        None
+    } else {
+        Some(Lock::acquire(Path::new(&config.path))?)
+    };
+    plan.check_inputs(Path::new(&config.path))?;
 
-    if config.verbose {
-        println!("{}", "Backup created successfully.".cyan());
+    // Hash the inputs and load the key before anything is written
+    let provenance = match with_report {
+        true => {
+            let config_file = Settings::path(&config.path, config.config_file.as_deref());
+            let mut files = plan.files();
+            if !files.contains(&"Anchor.toml") {
+                files.insert(0, "Anchor.toml");
+            }
+            // Files the run creates have nothing to hash yet
+            let project = Path::new(&config.path);
+            Some(Provenance::collect(
+                project,
+                config_file.as_deref(),
+                files.into_iter().filter(|file| project.join(file).exists()),
+            )?)
+        }
+        false => None,
+    };
+    let signing_key = config.sign_key.as_deref().map(load_keypair).transpose()?;
+
+    // Write the planned changes unless dry_run
+    if !config.dry_run {
+        apply_plan(&config.path, &plan, config.verbose)?;
+    } else {
+        println!("{}", "Dry run enabled. Changes not written.".warning());
+        print_planned_changes(&planned_changes(&config.path, &plan)?);
+    }
+
+    let result = plan.into_result(config.dry_run);
+
+    let report = provenance.map(|provenance| {
+        let mut report = Report::new(&result, provenance);
+        if let Some(key) = &signing_key {
+            report.sign(key);
+        }
+        report
+    });
+
+    Ok((result, report))
+}
+
+/// Works out every edit the migration would make, without touching the project.
+pub fn build_plan(config: &Config) -> Result<Plan, MigrationError> {
+    let kind = validate_project(&config.path)?;
+
+    let settings = Settings::load(&config.path, config.config_file.as_deref())?;
+    let endpoints = Endpoints::resolve(
+        &settings,
+        config.rpc_devnet.as_deref(),
+        config.rpc_testnet.as_deref(),
+        config.rpc_mainnet.as_deref(),
+    )?;
+
+    let project = Path::new(&config.path);
+    let (mut edits, mut result) = match kind {
+        ProjectKind::Anchor => plan_anchor(config, project, &settings, &endpoints)?,
+        ProjectKind::Native => plan_native(config, project),
+    };
+
+    // A custom endpoint must serve the SOON network it is written in for
+    if !config.offline {
+        for network in [
+            SoonNetwork::Devnet,
+            SoonNetwork::Testnet,
+            SoonNetwork::Mainnet,
+        ]
+        .into_iter()
+        .filter(|network| endpoints.is_custom(*network))
+        {
+            if cfg!(feature = "network") {
+                rpc::verify_genesis(
+                    network,
+                    &Rpc::connect(&endpoints, network)?,
+                    settings.rpc.genesis.hash(network),
+                )?;
+            } else {
+                result.warnings.push(
+                    Warning::new(
+                        WarningKind::CustomCluster,
+                        format!(
+                            "the SOON {} endpoint ({}) was not checked against the SOON genesis hash: soon-migrate was built without the `network` feature",
+                            network,
+                            url_host(endpoints.url(network))
+                        ),
+                    )
+                    .recommend(
+                        "Make sure the endpoint serves SOON, or run a build with the `network` feature to check it.",
+                    ),
+                );
+            }
+        }
+    }
+
+    // The key stays in the environment, but Anchor cannot read it from there
+    let url = endpoints.url(result.network);
+    if kind == ProjectKind::Anchor && url.contains("${") {
+        result.warnings.push(
+            Warning::new(
+                WarningKind::UnresolvedEndpoint,
+                format!(
+                    "provider.cluster keeps the environment variables of the SOON {} endpoint ({}), which Anchor does not expand",
+                    result.network,
+                    url_host(url)
+                ),
+            )
+            .in_file("Anchor.toml")
+            .recommend(
+                "Pass the expanded URL with anchor --provider.cluster \"<URL>\", or deploy with soon-migrate deploy, which expands it.",
+            ),
+        );
+    }
+
+    // CI, containers and build scripts keep talking to Solana unless they move too
+    for file in workflow_files(project)
+        .into_iter()
+        .chain(build_files(project))
+    {
+        rewrite_file(project, &file, &endpoints, &mut edits, &mut result);
+    }
+    if config.env_endpoints {
+        inject_env_endpoints(project, &endpoints, &mut edits, &mut result);
+    }
+    for file in docker_files(project) {
+        rewrite_file(project, &file, &endpoints, &mut edits, &mut result);
+        result
+            .warnings
+            .extend(local_validator_warnings(project, &file));
     }
+    for file in client_files(project) {
+        result
+            .warnings
+            .extend(lookup_table_warnings(project, &file));
+        result.warnings.extend(slot_timing_warnings(project, &file));
+        result.warnings.extend(rent_warnings(project, &file));
+    }
+    result
+        .warnings
+        .extend(dependency_matrix_advice(project, config.offline));
+    result.warnings.extend(client_library_warnings(project));
+    result
+        .warnings
+        .extend(preflight::program_size_warnings(project));
+    result
+        .warnings
+        .extend(leftover_endpoint_warnings(project, &edits));
+    if config.online {
+        // Nothing is deployed yet, so an unreachable endpoint only costs the check
+        match Rpc::connect(&endpoints, result.network) {
+            Ok(rpc) => result.warnings.extend(preflight::wallet_balance_warnings(
+                project,
+                result.network,
+                &rpc,
+            )),
+            Err(e) => result.warnings.push(Warning::new(
+                WarningKind::WalletBalance,
+                format!("could not check the deploy wallet's balance: {}", e),
+            )),
+        }
+    }
+
+    let mut plan = Plan {
+        version: PLAN_VERSION,
+        network: result.network,
+        edits,
+        changes: result.changes,
+        warnings: result.warnings,
+        compatibility: scan_ecosystem(project, &scan_options(config, &settings.rules)),
+        deployments: result.deployments,
+        inputs: BTreeMap::new(),
+    };
+    plan.hash_inputs(project);
+    Ok(plan)
+}
+
+/// Edits to Anchor.toml, and to `declare_id!` in the programs it lists.
+fn plan_anchor(
+    config: &Config,
+    project: &Path,
+    settings: &Settings,
+    endpoints: &Endpoints,
+) -> Result<(Vec<Edit>, MigrationResult), MigrationError> {
+    let anchor_toml_path = project.join("Anchor.toml");
 
     // Read Anchor.toml
     let content = fs::read_to_string(&anchor_toml_path)
@@ -52,149 +407,710 @@ pub fn run_migration(config: &Config) -> Result<(), MigrationError> {
     let mut toml_value: toml::Value = content
         .parse()
         .map_err(|e: toml::de::Error| MigrationError::TomlParseError(e.to_string()))?;
+    let original = toml_value.clone();
+
+    // Programs move to the table of whichever SOON network the cluster maps to,
+    // so the result starts out on devnet until the provider says otherwise
+    let mut result = MigrationResult::new(SoonNetwork::Devnet, config.dry_run);
 
     // Update the cluster value in the provider section
     if let Some(provider) = toml_value.get_mut("provider") {
         if let Some(table) = provider.as_table_mut() {
             // Store cluster value first before modifying table
-            let cluster_value = table.get("cluster")
+            let cluster_value = table
+                .get("cluster")
                 .and_then(|c| c.as_str())
                 .map(|c| c.to_string());
-            
+
             if let Some(cluster) = cluster_value {
-                let soon_rpc = map_cluster_to_soon(&cluster);
-                table.insert("cluster".to_string(), toml::Value::String(soon_rpc.to_string()));
-                
+                let custom =
+                    custom_cluster(&cluster, endpoints, config.online).map(|(network, warning)| {
+                        result.warnings.push(warning);
+                        network
+                    });
+
+                let (soon_network, soon_rpc) = map_cluster_to_soon(&cluster, endpoints, custom);
+                result.network = soon_network;
+                table.insert(
+                    "cluster".to_string(),
+                    toml::Value::String(soon_rpc.to_string()),
+                );
+
+                if cluster != soon_rpc {
+                    result.changes.push(Change {
+                        file: "Anchor.toml".to_string(),
+                        kind: ChangeKind::ClusterUpdated,
+                        detail: format!("provider.cluster set to {}", url_host(soon_rpc)),
+                    });
+                }
+
                 if config.verbose {
-                    println!("{}", format!("Updating cluster from '{}' to '{}'", cluster, soon_rpc).cyan());
+                    let from = if is_url(&cluster) {
+                        url_host(&cluster)
+                    } else {
+                        &cluster
+                    };
+                    println!(
+                        "{}",
+                        format!(
+                            "Updating cluster from '{}' to '{}'",
+                            from,
+                            url_host(soon_rpc)
+                        )
+                        .info()
+                    );
                 }
             }
         }
     }
 
-    // Update programs section: change programs.localnet to programs.devnet
+    // Update programs section: change programs.localnet to programs.<network>,
+    // or copy it there when the localnet table has to stay for `anchor test`
     if let Some(programs) = toml_value.get_mut("programs") {
         if let Some(table) = programs.as_table_mut() {
-            if let Some(localnet) = table.remove("localnet") {
-                table.insert("devnet".to_string(), localnet);
+            let localnet = if config.keep_localnet {
+                table.get("localnet").cloned()
+            } else {
+                table.remove("localnet")
+            };
+
+            if let Some(localnet) = localnet {
+                let network = result.network;
+                let target = table
+                    .entry(network.as_str())
+                    .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+                let (Some(target), Some(source)) = (target.as_table_mut(), localnet.as_table())
+                else {
+                    return Err(MigrationError::TomlParseError(format!(
+                        "programs.localnet and programs.{} must be tables",
+                        network
+                    )));
+                };
+
+                result
+                    .warnings
+                    .extend(merge_program_ids(target, source).into_iter().map(|conflict| {
+                        Warning::new(WarningKind::ProgramIdConflict, conflict)
+                            .in_file("Anchor.toml")
+                            .recommend("Check which program ID is actually deployed on SOON and pass it with --program-id.")
+                    }));
+                result.changes.push(Change {
+                    file: "Anchor.toml".to_string(),
+                    kind: if config.keep_localnet {
+                        ChangeKind::ProgramsCopied
+                    } else {
+                        ChangeKind::ProgramsMoved
+                    },
+                    detail: format!(
+                        "{} program(s) from [programs.localnet] now in [programs.{}]",
+                        source.len(),
+                        network
+                    ),
+                });
+
                 if config.verbose {
-                    println!("{}", "Updated programs.localnet to programs.devnet".cyan());
+                    let action = if config.keep_localnet {
+                        "Copied"
+                    } else {
+                        "Updated"
+                    };
+                    println!(
+                        "{}",
+                        format!("{} programs.localnet to programs.{}", action, network).info()
+                    );
+                }
+            }
+        }
+    }
+
+    // Pin an existing [toolchain] section to versions validated on SOON; a
+    // configured version also creates the section
+    let configured = &settings.toolchain;
+    if configured.anchor_version.is_some()
+        || configured.solana_version.is_some()
+        || toml_value.get("toolchain").is_some()
+    {
+        let Some(table) = toml_value.as_table_mut().and_then(|root| {
+            root.entry("toolchain")
+                .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+                .as_table_mut()
+        }) else {
+            return Err(MigrationError::TomlParseError(
+                "toolchain must be a table".to_string(),
+            ));
+        };
+
+        for (key, configured) in [
+            ("anchor_version", configured.anchor_version.as_deref()),
+            ("solana_version", configured.solana_version.as_deref()),
+        ] {
+            if configured.is_some_and(|version| parse_version(version).is_none()) {
+                return Err(MigrationError::ConfigError(format!(
+                    "toolchain.{}: not a version: {}",
+                    key,
+                    configured.unwrap_or_default()
+                )));
+            }
+
+            let current = table.get(key).and_then(|v| v.as_str());
+            if let Some(version) = pinned_version(key, current, configured) {
+                result.changes.push(Change {
+                    file: "Anchor.toml".to_string(),
+                    kind: ChangeKind::ToolchainUpdated,
+                    detail: match current {
+                        Some(current) => format!("toolchain.{} {} -> {}", key, current, version),
+                        None => format!("toolchain.{} set to {}", key, version),
+                    },
+                });
+                table.insert(key.to_string(), toml::Value::String(version));
+            }
+        }
+    }
+
+    // Point the SOON table at the program IDs actually deployed there
+    let program_ids = program_id_overrides(settings, &config.program_ids)?;
+    if !program_ids.is_empty() {
+        let network = result.network;
+        let mut table = toml_value
+            .get_mut("programs")
+            .and_then(|programs| programs.get_mut(network.as_str()))
+            .and_then(|table| table.as_table_mut());
+
+        for (name, id) in program_ids {
+            match table.as_deref_mut().and_then(|table| table.get_mut(&name)) {
+                None => result.warnings.push(
+                    Warning::new(
+                        WarningKind::UnknownProgram,
+                        format!(
+                            "program ID override for '{}' ignored: no such program in [programs.{}]",
+                            name, network
+                        ),
+                    )
+                    .in_file("Anchor.toml"),
+                ),
+                Some(current) if current.as_str() == Some(id.as_str()) => {}
+                Some(current) => {
+                    *current = toml::Value::String(id.clone());
+                    result.changes.push(Change {
+                        file: "Anchor.toml".to_string(),
+                        kind: ChangeKind::ProgramIdRemapped,
+                        detail: format!("programs.{}.{} set to {}", network, name, id),
+                    });
                 }
             }
         }
     }
 
     if config.verbose {
-        println!("{}", "Configuration updated successfully.".cyan());
+        println!("{}", "Configuration updated successfully.".info());
     }
 
-    // Write back to Anchor.toml unless dry_run
-    if !config.dry_run {
-        let toml_string = toml::to_string_pretty(&toml_value)
-            .map_err(|e| MigrationError::TomlParseError(e.to_string()))?;
+    let programs = program_crates(project);
+    result
+        .warnings
+        .extend(toolchain_advice(project, &toml_value, &programs));
+
+    let mut edits = diff_toml("Anchor.toml", &original, &toml_value);
+
+    // Keep declare_id! in each program's source in line with the SOON table
+    let network = result.network;
+    if let Some(table) = toml_value
+        .get("programs")
+        .and_then(|programs| programs.get(network.as_str()))
+        .and_then(|table| table.as_table())
+    {
+        for program in &programs {
+            let Some(id) = table.get(&program.name).and_then(|id| id.as_str()) else {
+                continue;
+            };
+            // Seahorse regenerates lib.rs from the Python source, so the ID
+            // has to change there too or the next build reverts it
+            for (path, call) in [
+                (program.lib_rs(), "declare_id!"),
+                (program.seahorse_source(), "declare_id"),
+            ] {
+                let Ok(source) = fs::read_to_string(project.join(&path)) else {
+                    continue;
+                };
+                let found = if path.extension().is_some_and(|extension| extension == "py") {
+                    find_seahorse_declare_id(&source)
+                } else {
+                    find_declare_id(&source)
+                };
+                let Some((line, declared)) = found else {
+                    continue;
+                };
 
-        fs::write(&anchor_toml_path, toml_string)
-            .map_err(|e| MigrationError::WriteFailed(e.to_string()))?;
+                if declared != id {
+                    let file = path.to_string_lossy().replace('\\', "/");
+                    edits.push(Edit::replace(&file, line, &declared, id));
+                    result.changes.push(Change {
+                        file,
+                        kind: ChangeKind::DeclareIdSynced,
+                        detail: format!(
+                            "{} of {} changed from {} to {}",
+                            call, program.name, declared, id
+                        ),
+                    });
+                }
+            }
+        }
+
+        // Solang programs carry their ID in an annotation on the contract
+        for (name, file) in solang_programs(project) {
+            let Some(id) = table.get(&name).and_then(|id| id.as_str()) else {
+                continue;
+            };
+            let Some((line, declared)) = fs::read_to_string(project.join(&file))
+                .ok()
+                .and_then(|source| find_solang_program_id(&source))
+            else {
+                continue;
+            };
 
-        if config.verbose {
-            println!("{}", "Anchor.toml written successfully.".cyan());
+            if declared != id {
+                edits.push(Edit::replace(&file, line, &declared, id));
+                result.changes.push(Change {
+                    file,
+                    kind: ChangeKind::DeclareIdSynced,
+                    detail: format!(
+                        "@program_id of {} changed from {} to {}",
+                        name, declared, id
+                    ),
+                });
+            }
         }
-    } else {
-        println!("{}", "Dry run enabled. Changes not written.".yellow());
+    }
+
+    Ok((edits, result))
+}
+
+/// A native program has no Anchor.toml to migrate, so this only checks its
+/// Solana dependency and works out how to deploy it to SOON.
+fn plan_native(config: &Config, project: &Path) -> (Vec<Edit>, MigrationResult) {
+    let mut result = MigrationResult::new(SoonNetwork::Devnet, config.dry_run);
+    let programs = native_program_crates(project);
+
+    result
+        .warnings
+        .extend(native_toolchain_advice(project, &programs));
+    for program in &programs {
+        result.deployments.push(Deployment {
+            program: program.name.clone(),
+            manifest: program
+                .dir
+                .join("Cargo.toml")
+                .to_string_lossy()
+                .replace('\\', "/"),
+            // The endpoint may carry a key, so the command leaves it to
+            // `deploy`, which expands it without showing it
+            command: "soon-migrate deploy".to_string(),
+        });
+    }
+
+    if config.verbose {
         println!(
             "{}",
-            toml::to_string_pretty(&toml_value)
-                .map_err(|e| MigrationError::TomlParseError(e.to_string()))?
-                .cyan()
+            format!(
+                "No Anchor.toml; found {} native program(s).",
+                programs.len()
+            )
+            .info()
         );
     }
 
-    Ok(())
+    (Vec::new(), result)
 }
 
-pub fn restore_backup(path: &str) -> Result<(), MigrationError> {
-    let anchor_toml_path = Path::new(path).join("Anchor.toml");
-    let backup_path = anchor_toml_path.with_extension("toml.bak");
+/// Current and new content of every file the plan edits, computed in memory.
+fn render_plan(path: &str, plan: &Plan) -> Result<Vec<(String, String, String)>, MigrationError> {
+    let project = Path::new(path);
 
-    if !backup_path.exists() {
-        return Err(MigrationError::BackupNotFound(
-            backup_path.to_string_lossy().into_owned(),
-        ));
+    plan.files()
+        .into_iter()
+        .map(|file| {
+            let file_path = project.join(file);
+            let creates = !file_path.exists()
+                && plan
+                    .edits_for(file)
+                    .all(|edit| matches!(edit.op, EditOp::Append { .. }));
+            let content = if creates {
+                String::new()
+            } else {
+                fs::read_to_string(&file_path)
+                    .map_err(|e| MigrationError::ReadFailed(format!("{}: {}", file, e)))?
+            };
+            let rendered = render_file(file, &content, plan.edits_for(file))?;
+            Ok((file.to_string(), content, rendered))
+        })
+        .collect()
+}
+
+/// A file the plan would change: the kinds of change it gets and the diff.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedChange {
+    pub file: String,
+    pub kinds: Vec<ChangeKind>,
+    pub diff: String,
+}
+
+/// Every file the plan would change, in plan order, with its diff.
+pub fn planned_changes(path: &str, plan: &Plan) -> Result<Vec<PlannedChange>, MigrationError> {
+    let mut planned = Vec::new();
+
+    for (file, old, new) in render_plan(path, plan)? {
+        let Some(diff) = unified_diff(&old, &new, &file, &file) else {
+            continue;
+        };
+        let mut kinds = Vec::new();
+        for change in plan.changes.iter().filter(|change| change.file == file) {
+            if !kinds.contains(&change.kind) {
+                kinds.push(change.kind);
+            }
+        }
+        planned.push(PlannedChange { file, kinds, diff });
+    }
+
+    Ok(planned)
+}
+
+fn print_planned_changes(planned: &[PlannedChange]) {
+    print!("{}", render_planned_changes(planned));
+}
+
+/// The dry-run listing of `planned`, URLs redacted.
+fn render_planned_changes(planned: &[PlannedChange]) -> String {
+    let mut out = format!("{} file(s) would change:\n", planned.len());
+    for change in planned {
+        let kinds: Vec<&str> = change.kinds.iter().map(|kind| kind.as_str()).collect();
+        out.push_str(&format!("  {} ({})\n", change.file, kinds.join(", ")));
+    }
+    for change in planned {
+        out.push('\n');
+        out.push_str(&colorize(&redact_urls(&change.diff)));
+    }
+    out
+}
+
+/// Backs up and rewrites every file in the plan. All new contents are worked
+/// out before the first write so a bad edit leaves the project untouched.
+pub fn apply_plan(path: &str, plan: &Plan, verbose: bool) -> Result<(), MigrationError> {
+    validate_project(path)?;
+
+    let project = Path::new(path);
+    let rendered = render_plan(path, plan)?;
+    preflight::check_writable(project, &rendered)?;
+
+    let mut journal = Journal::load(project)?;
+    for (file, _, _) in &rendered {
+        journal.backup(project, file)?;
+        journal.record(
+            file,
+            plan.changes
+                .iter()
+                .filter(|change| change.file == *file)
+                .cloned(),
+        );
+    }
+    journal.save(project)?;
+
+    if verbose {
+        println!("{}", "Backup created successfully.".info());
     }
 
-    fs::copy(&backup_path, &anchor_toml_path)
-        .map_err(|e| MigrationError::RestoreFailed(e.to_string()))?;
+    for (file, _, content) in rendered {
+        fs::write(project.join(&file), content)
+            .map_err(|e| MigrationError::WriteFailed(format!("{}: {}", file, e)))?;
 
-    if Path::new(&backup_path).exists() {
-        fs::remove_file(backup_path)
-            .map_err(|e| MigrationError::RestoreFailed(e.to_string()))?;
+        if verbose {
+            println!("{}", format!("{} written successfully.", file).info());
+        }
     }
 
     Ok(())
 }
 
-fn validate_anchor_project(path: &str) -> Result<(), MigrationError> {
-    let anchor_toml_path = Path::new(path).join("Anchor.toml");
-    if !anchor_toml_path.exists() {
-        return Err(MigrationError::NotAnAnchorProject(path.to_string()));
+/// Collects `name=id` program ID overrides from the `[program_ids]` config
+/// section and the `--program-id` flags, flags winning, sorted by name.
+fn program_id_overrides(
+    settings: &Settings,
+    flags: &[String],
+) -> Result<Vec<(String, String)>, MigrationError> {
+    let mut overrides: BTreeMap<String, String> =
+        settings.program_ids.clone().into_iter().collect();
+
+    for flag in flags {
+        let Some((name, id)) = flag.split_once('=') else {
+            return Err(MigrationError::InvalidProgramId(flag.clone()));
+        };
+        overrides.insert(name.trim().to_string(), id.trim().to_string());
     }
 
-    let cargo_toml_path = Path::new(path).join("Cargo.toml");
-    if !cargo_toml_path.exists() {
-        return Err(MigrationError::NotAnAnchorProject(path.to_string()));
+    for (name, id) in &overrides {
+        if name.is_empty() || !is_pubkey(id) {
+            return Err(MigrationError::InvalidProgramId(format!("{}={}", name, id)));
+        }
     }
 
-    Ok(())
+    Ok(overrides.into_iter().collect())
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::fs;
-    use tempfile::TempDir;
+/// Cheap shape check for a base58-encoded 32-byte public key.
+fn is_pubkey(value: &str) -> bool {
+    const BASE58: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+    (32..=44).contains(&value.len()) && value.chars().all(|c| BASE58.contains(c))
+}
 
-    fn create_test_anchor_project() -> TempDir {
-        let temp_dir = TempDir::new().unwrap();
-        let anchor_toml_content = r#"
-[toolchain]
+/// Adds the `source` program IDs to `target`, keeping any entry `target`
+/// already has. Returns a warning for every program whose IDs disagree.
+fn merge_program_ids(target: &mut toml::Table, source: &toml::Table) -> Vec<String> {
+    let mut conflicts = Vec::new();
 
-[features]
-resolution = true
-skip-lint = false
+    for (name, id) in source {
+        match target.get(name) {
+            Some(existing) if existing != id => conflicts.push(format!(
+                "program '{}' already has ID {} on SOON; keeping it instead of the localnet ID {}",
+                name, existing, id
+            )),
+            Some(_) => {}
+            None => {
+                target.insert(name.clone(), id.clone());
+            }
+        }
+    }
 
-[programs.localnet]
-migration = "EtQdsPNDckBhME3gRjcj9Z4Z9tGEYAoHjWKv7aHJgBua"
+    conflicts
+}
 
-[registry]
-url = "https://api.apr.dev"
+/// Journal entries a restore covers: every journaled file, narrowed down to
+/// `only` when given. Projects migrated before the journal existed fall back
+/// to the lone Anchor.toml backup.
+fn restore_entries(
+    project: &Path,
+    journal: &Journal,
+    only: &[String],
+) -> Result<Vec<JournalEntry>, MigrationError> {
+    let mut entries = journal.entries.clone();
+    if entries.is_empty() {
+        entries.push(JournalEntry {
+            file: "Anchor.toml".to_string(),
+            backup: "Anchor.toml.bak".to_string(),
+            created: false,
+            changes: Vec::new(),
+        });
+    }
 
-[provider]
-cluster = "Localnet"
-wallet = "~/.config/solana/id.json"
+    if !only.is_empty() {
+        let mut selected = Vec::new();
+        for file in only {
+            let file = file.trim_start_matches("./");
+            match entries.iter().find(|entry| entry.file == file) {
+                Some(entry) => selected.push(entry.clone()),
+                None => return Err(MigrationError::BackupNotFound(file.to_string())),
+            }
+        }
+        entries = selected;
+    }
 
-[scripts]
-test = "yarn run ts-mocha -p ./tsconfig.json -t 1000000 tests/**/*.ts"
-"#;
+    for entry in entries.iter().filter(|entry| !entry.created) {
+        let backup_path = project.join(&entry.backup);
+        if !backup_path.exists() {
+            return Err(MigrationError::BackupNotFound(
+                backup_path.to_string_lossy().into_owned(),
+            ));
+        }
+    }
 
-        fs::write(temp_dir.path().join("Anchor.toml"), anchor_toml_content).unwrap();
+    Ok(entries)
+}
 
-        fs::write(
-            temp_dir.path().join("Cargo.toml"),
-            "[package]\nname = \"test\"\nversion = \"0.1.0\"\n",
-        )
-        .unwrap();
+/// Returns a diff from each modified file to the backup that `restore_backup`
+/// would put back, or `None` when everything already matches.
+pub fn preview_restore(path: &str, only: &[String]) -> Result<Option<String>, MigrationError> {
+    let project = Path::new(path);
+    let journal = Journal::load(project)?;
+    let mut preview = String::new();
+
+    for entry in restore_entries(project, &journal, only)? {
+        let file_path = project.join(&entry.file);
+        let backup_path = project.join(&entry.backup);
+
+        // A created file goes back to not existing, shown as all removed
+        let backup = if entry.created {
+            String::new()
+        } else {
+            fs::read_to_string(&backup_path)
+                .map_err(|e| MigrationError::ReadFailed(e.to_string()))?
+        };
+        // A missing file simply shows the whole backup as added
+        let current = fs::read_to_string(&file_path).unwrap_or_default();
+
+        if let Some(diff) = render_diff(
+            &current,
+            &backup,
+            &file_path.to_string_lossy(),
+            &backup_path.to_string_lossy(),
+        ) {
+            preview.push_str(&redact_urls(&diff));
+        }
+    }
+
+    Ok(if preview.is_empty() {
+        None
+    } else {
+        Some(preview)
+    })
+}
+
+/// A file a restore would put back, with the SHA-256 of its content now and
+/// after the restore (`None` where the file is missing).
+#[derive(Debug, PartialEq, Eq)]
+pub struct RestoreTarget {
+    pub file: String,
+    pub backup: String,
+    pub current_sha256: Option<String>,
+    pub restored_sha256: Option<String>,
+}
+
+impl RestoreTarget {
+    pub fn is_unchanged(&self) -> bool {
+        self.current_sha256 == self.restored_sha256
+    }
+}
 
-        temp_dir
+/// What `restore_backup` would do, without doing it.
+pub fn restore_targets(path: &str, only: &[String]) -> Result<Vec<RestoreTarget>, MigrationError> {
+    let project = Path::new(path);
+    let journal = Journal::load(project)?;
+
+    restore_entries(project, &journal, only)?
+        .into_iter()
+        .map(|entry| {
+            let backup =
+                if entry.created {
+                    None
+                } else {
+                    Some(fs::read(project.join(&entry.backup)).map_err(|e| {
+                        MigrationError::ReadFailed(format!("{}: {}", entry.backup, e))
+                    })?)
+                };
+            let current = fs::read(project.join(&entry.file)).ok();
+
+            Ok(RestoreTarget {
+                current_sha256: current.as_deref().map(sha256_hex),
+                restored_sha256: backup.as_deref().map(sha256_hex),
+                file: entry.file,
+                backup: entry.backup,
+            })
+        })
+        .collect()
+}
+
+/// Copies the journaled backups back over the files they were taken from and
+/// returns the restored files. TOML backups must parse before anything is
+/// overwritten, and backups are kept afterwards unless `purge_backup` is set.
+pub fn restore_backup(
+    path: &str,
+    purge_backup: bool,
+    only: &[String],
+) -> Result<Vec<String>, MigrationError> {
+    let project = Path::new(path);
+    let _lock = Lock::acquire(project)?;
+    let mut journal = Journal::load(project)?;
+    let entries = restore_entries(project, &journal, only)?;
+
+    // Refuse to overwrite anything with a backup that is itself broken
+    let mut backups = Vec::new();
+    for entry in &entries {
+        if entry.created {
+            backups.push(String::new());
+            continue;
+        }
+        let backup = fs::read_to_string(project.join(&entry.backup))
+            .map_err(|e| MigrationError::RestoreFailed(format!("{}: {}", entry.backup, e)))?;
+        if entry.file.ends_with(".toml") {
+            backup.parse::<toml::Value>().map_err(|e| {
+                MigrationError::RestoreFailed(format!("{} is not valid TOML: {}", entry.backup, e))
+            })?;
+        }
+        backups.push(backup);
+    }
+
+    for (entry, backup) in entries.iter().zip(&backups) {
+        let file_path = project.join(&entry.file);
+        if entry.created {
+            if file_path.exists() {
+                fs::remove_file(&file_path)
+                    .map_err(|e| MigrationError::RestoreFailed(format!("{}: {}", entry.file, e)))?;
+            }
+            continue;
+        }
+        fs::copy(project.join(&entry.backup), &file_path)
+            .map_err(|e| MigrationError::RestoreFailed(format!("{}: {}", entry.file, e)))?;
+
+        let restored = fs::read_to_string(&file_path)
+            .map_err(|e| MigrationError::RestoreFailed(format!("{}: {}", entry.file, e)))?;
+        if &restored != backup {
+            return Err(MigrationError::RestoreFailed(format!(
+                "restored {} does not match its backup",
+                entry.file
+            )));
+        }
+    }
+
+    if purge_backup {
+        for entry in &entries {
+            if !entry.created {
+                fs::remove_file(project.join(&entry.backup))
+                    .map_err(|e| MigrationError::RestoreFailed(e.to_string()))?;
+            }
+            journal.remove(&entry.file);
+        }
+        journal.save(project)?;
+    }
+
+    Ok(entries.into_iter().map(|entry| entry.file).collect())
+}
+
+/// What kind of project is being migrated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ProjectKind {
+    Anchor,
+    /// Solana programs built without Anchor, so there is no Anchor.toml.
+    Native,
+}
+
+fn validate_project(path: &str) -> Result<ProjectKind, MigrationError> {
+    let project = Path::new(path);
+    if !project.join("Cargo.toml").exists() {
+        return Err(MigrationError::NotAnAnchorProject(path.to_string()));
     }
 
+    if project.join("Anchor.toml").exists() {
+        Ok(ProjectKind::Anchor)
+    } else if !native_program_crates(project).is_empty() {
+        Ok(ProjectKind::Native)
+    } else {
+        Err(MigrationError::NotAnAnchorProject(path.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testkit::{AnchorProject, DEFAULT_PROGRAM_ID};
+    use std::fs;
+    use tempfile::TempDir;
+
     #[test]
     fn test_migration_dry_run() {
-        let test_dir = create_test_anchor_project();
+        let test_dir = AnchorProject::new();
         let config = Config {
             path: test_dir.path().to_str().unwrap().to_string(),
             dry_run: true,
             verbose: false,
             restore: false,
+            ..Config::default()
         };
 
         let result = run_migration(&config);
@@ -207,12 +1123,13 @@ test = "yarn run ts-mocha -p ./tsconfig.json -t 1000000 tests/**/*.ts"
 
     #[test]
     fn test_migration_actual() {
-        let test_dir = create_test_anchor_project();
+        let test_dir = AnchorProject::new();
         let config = Config {
             path: test_dir.path().to_str().unwrap().to_string(),
             dry_run: false,
             verbose: false,
             restore: false,
+            ..Config::default()
         };
 
         let result = run_migration(&config);
@@ -229,7 +1146,7 @@ test = "yarn run ts-mocha -p ./tsconfig.json -t 1000000 tests/**/*.ts"
 
     #[test]
     fn test_restore_backup() {
-        let test_dir = create_test_anchor_project();
+        let test_dir = AnchorProject::new();
 
         // First run migration
         let config = Config {
@@ -237,16 +1154,50 @@ test = "yarn run ts-mocha -p ./tsconfig.json -t 1000000 tests/**/*.ts"
             dry_run: false,
             verbose: false,
             restore: false,
+            ..Config::default()
         };
         run_migration(&config).unwrap();
 
         // Then restore
-        let restore_result = restore_backup(test_dir.path().to_str().unwrap());
+        let restore_result = restore_backup(test_dir.path().to_str().unwrap(), false, &[]);
         assert!(restore_result.is_ok());
 
         // Verify content was restored
         let content = fs::read_to_string(test_dir.path().join("Anchor.toml")).unwrap();
         assert!(content.contains("cluster = \"Localnet\""));
+
+        // The backup survives, so restoring twice works
+        assert!(test_dir.path().join("Anchor.toml.bak").exists());
+        assert!(restore_backup(test_dir.path().to_str().unwrap(), false, &[]).is_ok());
+    }
+
+    #[test]
+    fn test_restore_purge_backup() {
+        let test_dir = AnchorProject::new();
+        let path = test_dir.path().to_str().unwrap().to_string();
+        let config = Config {
+            path: path.clone(),
+            ..Config::default()
+        };
+        run_migration(&config).unwrap();
+
+        restore_backup(&path, true, &[]).unwrap();
+
+        assert!(!test_dir.path().join("Anchor.toml.bak").exists());
+    }
+
+    #[test]
+    fn test_restore_rejects_invalid_backup() {
+        let test_dir = AnchorProject::new();
+        let path = test_dir.path().to_str().unwrap().to_string();
+        fs::write(test_dir.path().join("Anchor.toml.bak"), "[provider\n").unwrap();
+
+        let result = restore_backup(&path, true, &[]);
+
+        assert!(matches!(result, Err(MigrationError::RestoreFailed(_))));
+        assert!(test_dir.path().join("Anchor.toml.bak").exists());
+        let content = fs::read_to_string(test_dir.path().join("Anchor.toml")).unwrap();
+        assert!(content.contains("cluster = \"Localnet\""));
     }
 
     #[test]
@@ -256,9 +1207,1003 @@ test = "yarn run ts-mocha -p ./tsconfig.json -t 1000000 tests/**/*.ts"
             dry_run: false,
             verbose: false,
             restore: false,
+            ..Config::default()
         };
 
         let result = run_migration(&config);
         assert!(matches!(result, Err(MigrationError::NotAnAnchorProject(_))));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_rpc_override_from_config_file() {
+        let test_dir = AnchorProject::builder()
+            .file(
+                "soon-migrate.toml",
+                "[rpc]\ndevnet = \"https://rpc.internal.example/soon\"\n",
+            )
+            .build();
+        let config = Config {
+            path: test_dir.path().to_str().unwrap().to_string(),
+            offline: true,
+            ..Config::default()
+        };
+
+        run_migration(&config).unwrap();
+
+        let content = fs::read_to_string(test_dir.path().join("Anchor.toml")).unwrap();
+        assert!(content.contains("https://rpc.internal.example/soon"));
+    }
+
+    #[test]
+    fn test_rpc_flag_overrides_config_file() {
+        let test_dir = AnchorProject::builder()
+            .file(
+                "soon-migrate.toml",
+                "[rpc]\ndevnet = \"https://rpc.internal.example/soon\"\n",
+            )
+            .build();
+        let config = Config {
+            path: test_dir.path().to_str().unwrap().to_string(),
+            offline: true,
+            rpc_devnet: Some("https://flag.example/rpc".to_string()),
+            ..Config::default()
+        };
+
+        run_migration(&config).unwrap();
+
+        let content = fs::read_to_string(test_dir.path().join("Anchor.toml")).unwrap();
+        assert!(content.contains("https://flag.example/rpc"));
+        assert!(!content.contains("rpc.internal.example"));
+    }
+
+    #[test]
+    fn test_invalid_rpc_override() {
+        let test_dir = AnchorProject::new();
+        let config = Config {
+            path: test_dir.path().to_str().unwrap().to_string(),
+            rpc_devnet: Some("rpc.devnet.example".to_string()),
+            ..Config::default()
+        };
+
+        let result = run_migration(&config);
+        assert!(matches!(result, Err(MigrationError::InvalidEndpoint(_))));
+    }
+
+    #[test]
+    fn test_mainnet_cluster_maps_to_soon_mainnet() {
+        let test_dir = AnchorProject::builder().cluster("mainnet-beta").build();
+        let anchor_toml = test_dir.path().join("Anchor.toml");
+        let config = Config {
+            path: test_dir.path().to_str().unwrap().to_string(),
+            ..Config::default()
+        };
+
+        run_migration(&config).unwrap();
+
+        let content = fs::read_to_string(&anchor_toml).unwrap();
+        assert!(content.contains("https://rpc.mainnet.soo.network/rpc"));
+        assert!(content.contains("[programs.mainnet]"));
+    }
+
+    #[test]
+    fn test_keep_localnet() {
+        let test_dir = AnchorProject::new();
+        let config = Config {
+            path: test_dir.path().to_str().unwrap().to_string(),
+            keep_localnet: true,
+            ..Config::default()
+        };
+
+        run_migration(&config).unwrap();
+
+        let content = fs::read_to_string(test_dir.path().join("Anchor.toml")).unwrap();
+        let value: toml::Value = content.parse().unwrap();
+        let programs = value["programs"].as_table().unwrap();
+        assert_eq!(programs["localnet"], programs["devnet"]);
+        assert_eq!(
+            programs["localnet"]["migration"].as_str(),
+            Some(DEFAULT_PROGRAM_ID)
+        );
+    }
+
+    #[test]
+    fn test_preview_restore() {
+        let test_dir = AnchorProject::new();
+        let path = test_dir.path().to_str().unwrap().to_string();
+
+        assert!(matches!(
+            preview_restore(&path, &[]),
+            Err(MigrationError::BackupNotFound(_))
+        ));
+
+        let config = Config {
+            path: path.clone(),
+            ..Config::default()
+        };
+        run_migration(&config).unwrap();
+
+        let preview = preview_restore(&path, &[]).unwrap().unwrap();
+        assert!(preview.contains("cluster = \"Localnet\""));
+        assert!(preview.contains("https://rpc.devnet.soo.network/rpc"));
+
+        // Previewing must not touch anything
+        let content = fs::read_to_string(test_dir.path().join("Anchor.toml")).unwrap();
+        assert!(content.contains("https://rpc.devnet.soo.network/rpc"));
+        assert!(test_dir.path().join("Anchor.toml.bak").exists());
+    }
+
+    #[test]
+    fn test_custom_cluster_url_is_classified() {
+        let test_dir = AnchorProject::builder()
+            .cluster("https://mainnet.helius-rpc.com/?api-key=secret")
+            .build();
+        let anchor_toml = test_dir.path().join("Anchor.toml");
+        let config = Config {
+            path: test_dir.path().to_str().unwrap().to_string(),
+            ..Config::default()
+        };
+
+        run_migration(&config).unwrap();
+
+        let content = fs::read_to_string(&anchor_toml).unwrap();
+        assert!(content.contains("cluster = \"https://rpc.mainnet.soo.network/rpc\""));
+        assert!(content.contains("[programs.mainnet]"));
+    }
+
+    #[test]
+    fn test_endpoint_shown_by_host_only() {
+        let test_dir = AnchorProject::new();
+        std::env::set_var("SOON_MIGRATE_TEST_MIGRATION_KEY", "secret");
+        let url = "https://rpc.example.com/rpc?api-key=${SOON_MIGRATE_TEST_MIGRATION_KEY}";
+        let config = Config {
+            path: test_dir.path().to_str().unwrap().to_string(),
+            rpc_devnet: Some(url.to_string()),
+            offline: true,
+            ..Config::default()
+        };
+
+        let result = run_migration(&config).unwrap();
+
+        let content = fs::read_to_string(test_dir.path().join("Anchor.toml")).unwrap();
+        assert!(content.contains(&format!("cluster = \"{}\"", url)));
+        let cluster = result
+            .changes
+            .iter()
+            .find(|change| change.kind == ChangeKind::ClusterUpdated)
+            .unwrap();
+        assert_eq!(cluster.detail, "provider.cluster set to rpc.example.com");
+        let unresolved = result
+            .warnings
+            .iter()
+            .find(|warning| warning.kind == WarningKind::UnresolvedEndpoint)
+            .unwrap()
+            .to_string();
+        assert!(unresolved.contains("(rpc.example.com)"));
+        assert!(!unresolved.contains("api-key"));
+    }
+
+    #[test]
+    fn test_custom_cluster() {
+        let endpoints = Endpoints::default();
+        assert!(custom_cluster("Devnet", &endpoints, false).is_none());
+        assert!(custom_cluster("https://rpc.devnet.soo.network/rpc", &endpoints, false).is_none());
+
+        let (network, warning) =
+            custom_cluster("https://rpc.example.com/?api-key=secret", &endpoints, false).unwrap();
+        let warning = warning.to_string();
+        assert_eq!(network, SoonNetwork::Devnet);
+        assert!(warning.contains("rpc.example.com"));
+        assert!(warning.contains("defaulting to SOON devnet"));
+        assert!(!warning.contains("secret"));
+
+        let (network, _) = custom_cluster(
+            "https://x.solana-mainnet.quiknode.pro/t/",
+            &endpoints,
+            false,
+        )
+        .unwrap();
+        assert_eq!(network, SoonNetwork::Mainnet);
+    }
+
+    #[test]
+    fn test_merge_into_existing_network_table() {
+        let test_dir = AnchorProject::new();
+        let anchor_toml = test_dir.path().join("Anchor.toml");
+        test_dir.write(
+            "Anchor.toml",
+            &test_dir.read("Anchor.toml").replace(
+                "[registry]",
+                "[programs.devnet]\nmigration = \"DevnetMigrationId1111111111111111111111111\"\n\
+                 oracle = \"DevnetOracleId11111111111111111111111111111\"\n\n[registry]",
+            ),
+        );
+        let config = Config {
+            path: test_dir.path().to_str().unwrap().to_string(),
+            ..Config::default()
+        };
+
+        run_migration(&config).unwrap();
+
+        let value: toml::Value = fs::read_to_string(&anchor_toml).unwrap().parse().unwrap();
+        let programs = value["programs"].as_table().unwrap();
+        assert!(!programs.contains_key("localnet"));
+        // The pre-existing devnet IDs win over the localnet ones
+        assert_eq!(
+            programs["devnet"]["migration"].as_str(),
+            Some("DevnetMigrationId1111111111111111111111111")
+        );
+        assert_eq!(
+            programs["devnet"]["oracle"].as_str(),
+            Some("DevnetOracleId11111111111111111111111111111")
+        );
+    }
+
+    #[test]
+    fn test_merge_program_ids_reports_conflicts() {
+        let mut target: toml::Table = "a = \"A1\"\nb = \"B\"".parse().unwrap();
+        let source: toml::Table = "a = \"A2\"\nb = \"B\"\nc = \"C\"".parse().unwrap();
+
+        let conflicts = merge_program_ids(&mut target, &source);
+
+        assert_eq!(conflicts.len(), 1);
+        assert!(conflicts[0].contains("'a'"));
+        assert_eq!(target["a"].as_str(), Some("A1"));
+        assert_eq!(target["c"].as_str(), Some("C"));
+    }
+
+    #[test]
+    fn test_api_keys_not_shown() {
+        let test_dir = AnchorProject::builder()
+            .cluster("https://devnet.helius-rpc.com/?api-key=SOLANA_SECRET")
+            .build();
+        let anchor_toml = test_dir.path().join("Anchor.toml");
+        let config = Config {
+            path: test_dir.path().to_str().unwrap().to_string(),
+            rpc_devnet: Some("https://rpc.example/?api-key=SECRET".to_string()),
+            dry_run: true,
+            offline: true,
+            ..Config::default()
+        };
+
+        let plan = build_plan(&config).unwrap();
+        let dry_run = render_planned_changes(&planned_changes(&config.path, &plan).unwrap());
+        assert!(dry_run.contains("rpc.example"), "{}", dry_run);
+        assert!(!dry_run.contains("SECRET"), "{}", dry_run);
+
+        run_migration(&Config {
+            dry_run: false,
+            ..config.clone()
+        })
+        .unwrap();
+        let preview = preview_restore(&config.path, &[]).unwrap().unwrap();
+        assert!(!preview.contains("SECRET"), "{}", preview);
+
+        let migrated = fs::read_to_string(&anchor_toml).unwrap();
+        let line = migrated
+            .lines()
+            .position(|line| line.starts_with("cluster"))
+            .unwrap();
+        let explanation = crate::why::explain(test_dir.path(), "Anchor.toml", line + 1).unwrap();
+        assert!(!format!("{:?}", explanation).contains("SECRET"));
+    }
+
+    #[cfg(not(feature = "network"))]
+    #[test]
+    fn test_genesis_check_skipped_without_network() {
+        let test_dir = AnchorProject::new();
+        let config = Config {
+            path: test_dir.path().to_str().unwrap().to_string(),
+            rpc_devnet: Some("https://rpc.example.com/rpc".to_string()),
+            dry_run: true,
+            ..Config::default()
+        };
+
+        let plan = build_plan(&config).unwrap();
+
+        let skipped = plan
+            .warnings
+            .iter()
+            .find(|warning| warning.kind == WarningKind::CustomCluster)
+            .unwrap();
+        assert!(skipped.message.contains("rpc.example.com"));
+        assert!(skipped.message.contains("`network` feature"));
+    }
+
+    #[test]
+    fn test_dry_run_creates_no_backup() {
+        let test_dir = AnchorProject::new();
+        let config = Config {
+            path: test_dir.path().to_str().unwrap().to_string(),
+            dry_run: true,
+            ..Config::default()
+        };
+
+        run_migration(&config).unwrap();
+
+        assert!(!test_dir.path().join("Anchor.toml.bak").exists());
+        assert!(!Journal::path(test_dir.path()).exists());
+    }
+
+    #[test]
+    fn test_repeated_migration_keeps_original_backup() {
+        let test_dir = AnchorProject::new();
+        let config = Config {
+            path: test_dir.path().to_str().unwrap().to_string(),
+            ..Config::default()
+        };
+
+        run_migration(&config).unwrap();
+        run_migration(&config).unwrap();
+
+        let backup = fs::read_to_string(test_dir.path().join("Anchor.toml.bak")).unwrap();
+        assert!(backup.contains("cluster = \"Localnet\""));
+    }
+
+    #[test]
+    fn test_restore_all_journaled_files() {
+        let test_dir = AnchorProject::new();
+        let project = test_dir.path();
+        let path = project.to_str().unwrap().to_string();
+
+        let mut journal = Journal::load(project).unwrap();
+        journal.backup(project, "Anchor.toml").unwrap();
+        journal.backup(project, "Cargo.toml").unwrap();
+        journal.save(project).unwrap();
+        fs::write(project.join("Anchor.toml"), "changed = true\n").unwrap();
+        fs::write(project.join("Cargo.toml"), "changed = true\n").unwrap();
+
+        let restored = restore_backup(&path, true, &[]).unwrap();
+
+        assert_eq!(restored, vec!["Anchor.toml", "Cargo.toml"]);
+        let cargo = fs::read_to_string(project.join("Cargo.toml")).unwrap();
+        assert!(cargo.contains("[workspace]"));
+        assert!(!project.join("Cargo.toml.bak").exists());
+        assert!(!Journal::path(project).exists());
+    }
+
+    #[test]
+    fn test_restore_only_selected_files() {
+        let test_dir = AnchorProject::new();
+        let project = test_dir.path();
+        let path = project.to_str().unwrap().to_string();
+
+        let mut journal = Journal::load(project).unwrap();
+        journal.backup(project, "Anchor.toml").unwrap();
+        journal.backup(project, "Cargo.toml").unwrap();
+        journal.save(project).unwrap();
+        fs::write(project.join("Anchor.toml"), "changed = true\n").unwrap();
+        fs::write(project.join("Cargo.toml"), "changed = true\n").unwrap();
+
+        let only = vec!["./Cargo.toml".to_string()];
+        let restored = restore_backup(&path, true, &only).unwrap();
+
+        assert_eq!(restored, vec!["Cargo.toml"]);
+        let anchor = fs::read_to_string(project.join("Anchor.toml")).unwrap();
+        assert_eq!(anchor, "changed = true\n");
+        let journal = Journal::load(project).unwrap();
+        assert!(journal.entry("Anchor.toml").is_some());
+        assert!(journal.entry("Cargo.toml").is_none());
+
+        let unknown = vec!["Xargo.toml".to_string()];
+        assert!(matches!(
+            restore_backup(&path, false, &unknown),
+            Err(MigrationError::BackupNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_result_records_changes_and_next_steps() {
+        let test_dir = AnchorProject::new();
+        let config = Config {
+            path: test_dir.path().to_str().unwrap().to_string(),
+            ..Config::default()
+        };
+
+        let result = run_migration(&config).unwrap();
+
+        let kinds: Vec<ChangeKind> = result.changes.iter().map(|change| change.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ChangeKind::ClusterUpdated,
+                ChangeKind::ProgramsMoved,
+                ChangeKind::ToolchainUpdated,
+                ChangeKind::ToolchainUpdated
+            ]
+        );
+        assert!(result.warnings.is_empty());
+        let steps = result.next_steps();
+        assert!(steps
+            .iter()
+            .any(|step| step.action.contains("anchor deploy")
+                && step.evidence.contains("[programs.devnet]")));
+
+        // A second run finds nothing left to change
+        let result = run_migration(&config).unwrap();
+        assert!(result.changes.is_empty());
+        assert_eq!(result.next_steps().len(), 1);
+    }
+
+    #[test]
+    fn test_oracles_used_together_get_one_next_step() {
+        let test_dir = AnchorProject::builder()
+            .file(
+                "programs/migration/src/price.rs",
+                "use pyth_sdk_solana::PriceFeed;\nuse switchboard_v2::AggregatorAccountData;\n",
+            )
+            .build();
+        let config = Config {
+            path: test_dir.path().to_str().unwrap().to_string(),
+            ..Config::default()
+        };
+
+        let result = run_migration(&config).unwrap();
+        let oracle_steps: Vec<_> = result
+            .next_steps()
+            .into_iter()
+            .filter(|step| step.action.contains("APRO"))
+            .collect();
+        assert_eq!(oracle_steps.len(), 1);
+        assert!(oracle_steps[0]
+            .action
+            .starts_with("Pyth and Switchboard are read together"));
+        assert_eq!(
+            oracle_steps[0].evidence,
+            "oracles Pyth Oracle (legacy), Switchboard V2"
+        );
+    }
+
+    #[test]
+    fn test_program_id_remapping() {
+        let test_dir = AnchorProject::builder()
+            .file(
+                "soon-migrate.toml",
+                "[program_ids]\nmigration = \"Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS\"\n",
+            )
+            .build();
+        let config = Config {
+            path: test_dir.path().to_str().unwrap().to_string(),
+            keep_localnet: true,
+            program_ids: vec!["missing=Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS".to_string()],
+            ..Config::default()
+        };
+
+        let result = run_migration(&config).unwrap();
+
+        let value: toml::Value = fs::read_to_string(test_dir.path().join("Anchor.toml"))
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert_eq!(
+            value["programs"]["devnet"]["migration"].as_str(),
+            Some("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS")
+        );
+        // Localnet keeps the Solana-side ID
+        assert_eq!(
+            value["programs"]["localnet"]["migration"].as_str(),
+            Some(DEFAULT_PROGRAM_ID)
+        );
+        assert!(result
+            .changes
+            .iter()
+            .any(|change| change.kind == ChangeKind::ProgramIdRemapped));
+        assert_eq!(result.warnings.len(), 1);
+        assert!(result.warnings[0].message.contains("'missing'"));
+    }
+
+    #[test]
+    fn test_invalid_program_id_override() {
+        let test_dir = AnchorProject::new();
+        for flag in [
+            "migration",
+            "migration=not-a-pubkey",
+            "=Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS",
+        ] {
+            let config = Config {
+                path: test_dir.path().to_str().unwrap().to_string(),
+                program_ids: vec![flag.to_string()],
+                ..Config::default()
+            };
+
+            let result = run_migration(&config);
+            assert!(
+                matches!(result, Err(MigrationError::InvalidProgramId(_))),
+                "{}",
+                flag
+            );
+        }
+    }
+
+    #[test]
+    fn test_plan_then_apply() {
+        let test_dir = AnchorProject::new();
+        let path = test_dir.path().to_str().unwrap().to_string();
+        let config = Config {
+            path: path.clone(),
+            ..Config::default()
+        };
+
+        let plan = build_plan(&config).unwrap();
+        let plan_path = test_dir.path().join("plan.yaml");
+        plan.save(plan_path.to_str().unwrap()).unwrap();
+
+        // Planning alone changes nothing
+        let content = fs::read_to_string(test_dir.path().join("Anchor.toml")).unwrap();
+        assert!(content.contains("cluster = \"Localnet\""));
+
+        let loaded = Plan::load(plan_path.to_str().unwrap()).unwrap();
+
+        // A file edited after planning makes the plan stale
+        let original = fs::read_to_string(test_dir.path().join("Anchor.toml")).unwrap();
+        fs::write(
+            test_dir.path().join("Anchor.toml"),
+            format!("{}\n# reviewed\n", original),
+        )
+        .unwrap();
+        assert!(matches!(
+            execute_plan(&config, loaded.clone()),
+            Err(MigrationError::PlanError(_))
+        ));
+        fs::write(test_dir.path().join("Anchor.toml"), original).unwrap();
+
+        execute_plan(&config, loaded).unwrap();
+
+        let content = fs::read_to_string(test_dir.path().join("Anchor.toml")).unwrap();
+        assert!(content.contains("https://rpc.devnet.soo.network/rpc"));
+        assert!(content.contains("[programs.devnet]"));
+        assert!(content.contains("[scripts]"));
+        assert!(test_dir.path().join("Anchor.toml.bak").exists());
+    }
+
+    #[test]
+    fn test_declare_id_follows_remapped_program_id() {
+        let test_dir = AnchorProject::new();
+        let lib_rs = test_dir.path().join("programs/migration/src/lib.rs");
+
+        // Without a remap the IDs already agree
+        let config = Config {
+            path: test_dir.path().to_str().unwrap().to_string(),
+            dry_run: true,
+            ..Config::default()
+        };
+        let plan = build_plan(&config).unwrap();
+        assert!(plan.files().iter().all(|file| *file == "Anchor.toml"));
+
+        let config = Config {
+            path: test_dir.path().to_str().unwrap().to_string(),
+            program_ids: vec!["migration=Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS".to_string()],
+            ..Config::default()
+        };
+        let result = run_migration(&config).unwrap();
+
+        let source = fs::read_to_string(&lib_rs).unwrap();
+        assert!(source.contains("declare_id!(\"Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS\");"));
+        assert!(source.starts_with("use anchor_lang::prelude::*;\n\n"));
+        assert!(result
+            .changes
+            .iter()
+            .any(|change| change.kind == ChangeKind::DeclareIdSynced
+                && change.file == "programs/migration/src/lib.rs"));
+
+        // The source edit is journaled and restored with everything else
+        restore_backup(test_dir.path().to_str().unwrap(), false, &[]).unwrap();
+        let source = fs::read_to_string(&lib_rs).unwrap();
+        assert!(source.contains(DEFAULT_PROGRAM_ID));
+    }
+
+    #[test]
+    fn test_toolchain_version_advice() {
+        let test_dir = AnchorProject::builder()
+            .anchor_version("0.26.0")
+            .file(
+                "programs/migration/Cargo.toml",
+                "[package]\nname = \"migration\"\n\n[dependencies]\nanchor-lang = { version = \"0.26.0\", features = [\"init-if-needed\"] }\n",
+            )
+            .build();
+        let config = Config {
+            path: test_dir.path().to_str().unwrap().to_string(),
+            dry_run: true,
+            ..Config::default()
+        };
+
+        let result = run_migration(&config).unwrap();
+
+        let advice: Vec<&Warning> = result
+            .warnings
+            .iter()
+            .filter(|warning| warning.kind == WarningKind::AnchorVersion)
+            .collect();
+        // anchor_version in Anchor.toml gets pinned, so only the crate is left
+        assert_eq!(advice.len(), 1);
+        assert_eq!(
+            advice[0].file.as_deref(),
+            Some("programs/migration/Cargo.toml")
+        );
+        assert!(advice[0].message.contains("0.26.0"));
+    }
+
+    #[test]
+    fn test_toolchain_pinned_to_soon_versions() {
+        let test_dir = AnchorProject::builder()
+            .solana_version("1.16.0")
+            .file(
+                "soon-migrate.toml",
+                "[toolchain]\nsolana_version = \"1.18.20\"\n",
+            )
+            .build();
+        let config = Config {
+            path: test_dir.path().to_str().unwrap().to_string(),
+            ..Config::default()
+        };
+
+        let result = run_migration(&config).unwrap();
+
+        let updated = fs::read_to_string(test_dir.path().join("Anchor.toml")).unwrap();
+        let updated: toml::Value = updated.parse().unwrap();
+        assert_eq!(
+            updated["toolchain"]["anchor_version"].as_str(),
+            Some(crate::toolchain::RECOMMENDED_ANCHOR_VERSION)
+        );
+        assert_eq!(
+            updated["toolchain"]["solana_version"].as_str(),
+            Some("1.18.20")
+        );
+        assert_eq!(
+            result
+                .changes
+                .iter()
+                .filter(|change| change.kind == ChangeKind::ToolchainUpdated)
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_workflow_endpoints_rewritten() {
+        let workflow = "on: push\njobs:\n  deploy:\n    steps:\n      - run: solana config set --url devnet\n      - run: anchor deploy --provider.cluster https://api.devnet.solana.com\n";
+        let test_dir = AnchorProject::builder()
+            .file(".github/workflows/deploy.yml", workflow)
+            .build();
+        let config = Config {
+            path: test_dir.path().to_str().unwrap().to_string(),
+            ..Config::default()
+        };
+
+        let result = run_migration(&config).unwrap();
+
+        let updated = test_dir.read(".github/workflows/deploy.yml");
+        assert_eq!(
+            updated,
+            workflow
+                .replace("devnet\n", "https://rpc.devnet.soo.network/rpc\n")
+                .replace(
+                    "https://api.devnet.solana.com",
+                    "https://rpc.devnet.soo.network/rpc"
+                )
+        );
+        assert_eq!(
+            result
+                .changes
+                .iter()
+                .filter(|change| change.kind == ChangeKind::EndpointRewritten)
+                .count(),
+            2
+        );
+        let journal = Journal::load(test_dir.path()).unwrap();
+        assert!(journal.entry(".github/workflows/deploy.yml").is_some());
+    }
+
+    #[test]
+    fn test_docker_files_migrated() {
+        let test_dir = AnchorProject::builder()
+            .file(
+                "docker-compose.yml",
+                "services:\n  validator:\n    command: solana-test-validator\n  app:\n    environment:\n      RPC_URL: https://api.testnet.solana.com\n",
+            )
+            .file("compose-notes.txt", "https://api.testnet.solana.com\n")
+            .build();
+        let config = Config {
+            path: test_dir.path().to_str().unwrap().to_string(),
+            dry_run: true,
+            ..Config::default()
+        };
+
+        let plan = build_plan(&config).unwrap();
+
+        assert!(plan
+            .edits
+            .iter()
+            .any(|edit| edit.file == "docker-compose.yml"
+                && edit.op
+                    == EditOp::Replace {
+                        line: 6,
+                        old: "https://api.testnet.solana.com".to_string(),
+                        new: "https://rpc.testnet.soo.network/rpc".to_string(),
+                    }));
+        assert!(plan.files().iter().all(|file| *file != "compose-notes.txt"));
+        assert!(plan
+            .warnings
+            .iter()
+            .any(|warning| warning.kind == WarningKind::LocalValidator
+                && warning.file.as_deref() == Some("docker-compose.yml")));
+    }
+
+    #[test]
+    fn test_why_explains_migrated_lines() {
+        let test_dir = AnchorProject::new();
+        let config = Config {
+            path: test_dir.path().to_str().unwrap().to_string(),
+            ..Config::default()
+        };
+        run_migration(&config).unwrap();
+
+        let anchor = fs::read_to_string(test_dir.path().join("Anchor.toml")).unwrap();
+        let line = anchor
+            .lines()
+            .position(|line| line.starts_with("cluster ="))
+            .unwrap()
+            + 1;
+        let explanation = crate::why::explain(test_dir.path(), "Anchor.toml", line).unwrap();
+
+        assert_eq!(
+            explanation.original,
+            Some(vec!["cluster = \"Localnet\"".to_string()])
+        );
+        assert_eq!(explanation.changes.len(), 1);
+        assert_eq!(explanation.changes[0].kind, ChangeKind::ClusterUpdated);
+
+        let line = anchor
+            .lines()
+            .position(|line| line.starts_with("wallet ="))
+            .unwrap()
+            + 1;
+        let explanation = crate::why::explain(test_dir.path(), "Anchor.toml", line).unwrap();
+        assert_eq!(explanation.original, None);
+    }
+
+    #[test]
+    fn test_restore_targets_preview_hashes() {
+        let test_dir = AnchorProject::new();
+        let path = test_dir.path().to_str().unwrap().to_string();
+        let original = fs::read(test_dir.path().join("Anchor.toml")).unwrap();
+        let config = Config {
+            path: path.clone(),
+            ..Config::default()
+        };
+        run_migration(&config).unwrap();
+        let migrated = fs::read(test_dir.path().join("Anchor.toml")).unwrap();
+
+        let targets = restore_targets(&path, &[]).unwrap();
+
+        assert_eq!(
+            targets,
+            vec![RestoreTarget {
+                file: "Anchor.toml".to_string(),
+                backup: "Anchor.toml.bak".to_string(),
+                current_sha256: Some(sha256_hex(&migrated)),
+                restored_sha256: Some(sha256_hex(&original)),
+            }]
+        );
+        // Nothing was touched
+        assert_eq!(
+            fs::read(test_dir.path().join("Anchor.toml")).unwrap(),
+            migrated
+        );
+    }
+
+    #[test]
+    fn test_makefile_cluster_arguments_rewritten() {
+        let makefile = "deploy:\n\tsolana config set -u m\n\tanchor deploy --provider.cluster mainnet\n\nlocal:\n\tsolana config set -u localhost\n";
+        let test_dir = AnchorProject::builder().file("Makefile", makefile).build();
+        let config = Config {
+            path: test_dir.path().to_str().unwrap().to_string(),
+            ..Config::default()
+        };
+
+        run_migration(&config).unwrap();
+
+        let updated = fs::read_to_string(test_dir.path().join("Makefile")).unwrap();
+        assert_eq!(
+            updated,
+            "deploy:\n\tsolana config set -u https://rpc.mainnet.soo.network/rpc\n\tanchor deploy --provider.cluster https://rpc.mainnet.soo.network/rpc\n\nlocal:\n\tsolana config set -u localhost\n"
+        );
+    }
+
+    #[test]
+    fn test_shell_scripts_rewritten_or_flagged() {
+        let script = "#!/bin/sh\nsolana config set --url https://api.devnet.solana.com\nsolana config set --url \"$RPC_URL\"\n";
+        let test_dir = AnchorProject::builder()
+            .file("scripts/deploy.sh", script)
+            .build();
+        let config = Config {
+            path: test_dir.path().to_str().unwrap().to_string(),
+            dry_run: true,
+            ..Config::default()
+        };
+
+        let plan = build_plan(&config).unwrap();
+
+        assert!(plan.files().contains(&"scripts/deploy.sh"));
+        let flagged: Vec<&Warning> = plan
+            .warnings
+            .iter()
+            .filter(|warning| warning.kind == WarningKind::UnresolvedEndpoint)
+            .collect();
+        assert_eq!(flagged.len(), 1);
+        assert_eq!(flagged[0].file.as_deref(), Some("scripts/deploy.sh"));
+        assert!(flagged[0].message.starts_with("line 3:"));
+    }
+
+    #[test]
+    fn test_planned_changes_cover_every_file() {
+        let test_dir = AnchorProject::builder()
+            .file("deploy.sh", "solana config set --url devnet\n")
+            .build();
+        let path = test_dir.path().to_str().unwrap().to_string();
+        let config = Config {
+            path: path.clone(),
+            dry_run: true,
+            ..Config::default()
+        };
+        let plan = build_plan(&config).unwrap();
+
+        let planned = planned_changes(&path, &plan).unwrap();
+
+        let files: Vec<&str> = planned.iter().map(|change| change.file.as_str()).collect();
+        assert_eq!(files, vec!["Anchor.toml", "deploy.sh"]);
+        assert!(planned[0].kinds.contains(&ChangeKind::ClusterUpdated));
+        assert_eq!(planned[1].kinds, vec![ChangeKind::EndpointRewritten]);
+        assert!(planned[1]
+            .diff
+            .contains("+solana config set --url https://rpc.devnet.soo.network/rpc"));
+    }
+
+    #[test]
+    fn test_env_endpoints_injected_and_restored() {
+        let test_dir = AnchorProject::builder()
+            .file(
+                "app/src/client.ts",
+                "export const connection = new Connection(clusterApiUrl(\"devnet\"));\n",
+            )
+            .build();
+        let project = test_dir.path();
+        let path = project.to_str().unwrap().to_string();
+        let config = Config {
+            path: path.clone(),
+            env_endpoints: true,
+            ..Config::default()
+        };
+
+        run_migration(&config).unwrap();
+
+        let client = fs::read_to_string(project.join("app/src/client.ts")).unwrap();
+        assert!(client.contains(
+            "new Connection(process.env.SOON_DEVNET_RPC_URL ?? \"https://rpc.devnet.soo.network/rpc\")"
+        ));
+        assert_eq!(
+            fs::read_to_string(project.join(".env.example")).unwrap(),
+            "SOON_DEVNET_RPC_URL=https://rpc.devnet.soo.network/rpc\n"
+        );
+
+        // Running again neither wraps the lookup twice nor repeats the entry
+        let plan = build_plan(&config).unwrap();
+        assert!(plan.changes.iter().all(|change| !matches!(
+            change.kind,
+            ChangeKind::EndpointInjected | ChangeKind::EnvExampleUpdated
+        )));
+
+        restore_backup(&path, true, &[]).unwrap();
+        assert!(!project.join(".env.example").exists());
+        let client = fs::read_to_string(project.join("app/src/client.ts")).unwrap();
+        assert!(client.contains("clusterApiUrl(\"devnet\")"));
+    }
+
+    #[test]
+    fn test_native_program_migration() {
+        let test_dir = TempDir::new().unwrap();
+        let project = test_dir.path();
+        fs::write(
+            project.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"program\"]\n",
+        )
+        .unwrap();
+        fs::create_dir_all(project.join("program/src")).unwrap();
+        fs::write(
+            project.join("program/Cargo.toml"),
+            "[package]\nname = \"counter-program\"\nversion = \"0.1.0\"\n\n[dependencies]\nsolana-program = \"1.16.0\"\n",
+        )
+        .unwrap();
+        fs::write(
+            project.join("deploy.sh"),
+            "solana program deploy target/deploy/counter_program.so -u devnet\n",
+        )
+        .unwrap();
+        let config = Config {
+            path: project.to_str().unwrap().to_string(),
+            ..Config::default()
+        };
+
+        let result = run_migration(&config).unwrap();
+
+        assert_eq!(
+            result.deployments,
+            vec![Deployment {
+                program: "counter_program".to_string(),
+                manifest: "program/Cargo.toml".to_string(),
+                command: "soon-migrate deploy".to_string(),
+            }]
+        );
+        assert!(result
+            .warnings
+            .iter()
+            .any(|w| w.kind == WarningKind::SolanaVersion
+                && w.file.as_deref() == Some("program/Cargo.toml")));
+        let script = fs::read_to_string(project.join("deploy.sh")).unwrap();
+        assert!(script.contains("-u https://rpc.devnet.soo.network/rpc"));
+        assert!(!project.join("Anchor.toml").exists());
+
+        // A Cargo workspace without any Solana program is still rejected
+        fs::remove_dir_all(project.join("program")).unwrap();
+        assert!(matches!(
+            build_plan(&config),
+            Err(MigrationError::NotAnAnchorProject(_))
+        ));
+    }
+
+    #[test]
+    fn test_seahorse_declare_id_synced() {
+        let test_dir = AnchorProject::builder()
+            .file(
+                "programs_py/migration.py",
+                "from seahorse.prelude import *\n\ndeclare_id('EtQdsPNDckBhME3gRjcj9Z4Z9tGEYAoHjWKv7aHJgBua')\n",
+            )
+            .build();
+        let config = Config {
+            path: test_dir.path().to_str().unwrap().to_string(),
+            program_ids: vec!["migration=Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS".to_string()],
+            ..Config::default()
+        };
+
+        let result = run_migration(&config).unwrap();
+
+        let python = fs::read_to_string(test_dir.path().join("programs_py/migration.py")).unwrap();
+        assert!(python.contains("declare_id('Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS')"));
+        let synced: Vec<&str> = result
+            .changes
+            .iter()
+            .filter(|change| change.kind == ChangeKind::DeclareIdSynced)
+            .map(|change| change.file.as_str())
+            .collect();
+        assert_eq!(
+            synced,
+            vec!["programs/migration/src/lib.rs", "programs_py/migration.py"]
+        );
+    }
+
+    #[test]
+    fn test_solang_program_id_synced() {
+        let test_dir = AnchorProject::builder()
+            .file(
+                "solidity/migration.sol",
+                "@program_id(\"EtQdsPNDckBhME3gRjcj9Z4Z9tGEYAoHjWKv7aHJgBua\")\ncontract migration {\n}\n",
+            )
+            .build();
+        let config = Config {
+            path: test_dir.path().to_str().unwrap().to_string(),
+            program_ids: vec!["migration=Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS".to_string()],
+            ..Config::default()
+        };
+
+        let result = run_migration(&config).unwrap();
+
+        let contract = fs::read_to_string(test_dir.path().join("solidity/migration.sol")).unwrap();
+        assert!(
+            contract.starts_with("@program_id(\"Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS\")")
+        );
+        assert!(result
+            .changes
+            .iter()
+            .any(|change| change.kind == ChangeKind::DeclareIdSynced
+                && change.file == "solidity/migration.sol"));
+    }
+}
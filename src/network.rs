@@ -0,0 +1,206 @@
+use serde_json::Value;
+use std::time::Duration;
+
+/// How long a request may take, unless the caller needs otherwise.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Async HTTP client on tokio and reqwest, behind everything that goes
+/// online: RPC calls, webhooks and the bridge API. Errors are the bare
+/// reason; callers add the host (never the full URL, which often carries an
+/// API key) and their own context.
+#[cfg(feature = "network")]
+#[derive(Clone)]
+pub struct AsyncClient {
+    /// The error instead when the TLS backend could not be set up.
+    http: Result<reqwest::Client, String>,
+    /// Sent with every request; header values are secrets and never shown.
+    headers: Vec<(String, String)>,
+}
+
+#[cfg(feature = "network")]
+impl AsyncClient {
+    pub fn new(timeout: Duration) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .map_err(reason);
+        AsyncClient {
+            http,
+            headers: Vec::new(),
+        }
+    }
+
+    /// The same client, sending `headers` with every request.
+    pub fn with_headers(mut self, headers: Vec<(String, String)>) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    /// POSTs `body` as JSON and returns the JSON response.
+    pub async fn post_json(&self, url: &str, body: &Value) -> Result<Value, String> {
+        let response = self.send(self.http()?.post(parse(url)?).json(body)).await?;
+        response.json().await.map_err(reason)
+    }
+
+    /// POSTs `body` as JSON, ignoring whatever comes back.
+    pub async fn post(&self, url: &str, body: &Value) -> Result<(), String> {
+        self.send(self.http()?.post(parse(url)?).json(body))
+            .await
+            .map(|_| ())
+    }
+
+    /// GETs `url` and returns the response as text.
+    pub async fn get_text(&self, url: &str) -> Result<String, String> {
+        let response = self.send(self.http()?.get(parse(url)?)).await?;
+        response.text().await.map_err(reason)
+    }
+
+    fn http(&self) -> Result<&reqwest::Client, String> {
+        self.http.as_ref().map_err(Clone::clone)
+    }
+
+    /// Sends `request` with the client's headers; an error status is an error.
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response, String> {
+        let request = self.headers.iter().fold(request, |request, (name, value)| {
+            request.header(name.as_str(), value.as_str())
+        });
+        request
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(reason)
+    }
+}
+
+/// Blocking facade over [`AsyncClient`] for the CLI, whose commands run one
+/// request after another.
+#[cfg(feature = "network")]
+pub struct Client {
+    inner: AsyncClient,
+}
+
+#[cfg(feature = "network")]
+impl Client {
+    pub fn new(timeout: Duration) -> Self {
+        Client {
+            inner: AsyncClient::new(timeout),
+        }
+    }
+
+    /// The same client, sending `headers` with every request.
+    pub fn with_headers(self, headers: Vec<(String, String)>) -> Self {
+        Client {
+            inner: self.inner.with_headers(headers),
+        }
+    }
+
+    /// POSTs `body` as JSON and returns the JSON response.
+    pub fn post_json(&self, url: &str, body: &Value) -> Result<Value, String> {
+        runtime()?.block_on(self.inner.post_json(url, body))
+    }
+
+    /// POSTs `body` as JSON, ignoring whatever comes back.
+    pub fn post(&self, url: &str, body: &Value) -> Result<(), String> {
+        runtime()?.block_on(self.inner.post(url, body))
+    }
+
+    /// GETs `url` and returns the response as text.
+    pub fn get_text(&self, url: &str) -> Result<String, String> {
+        runtime()?.block_on(self.inner.get_text(url))
+    }
+}
+
+/// The runtime every blocking call runs on. A current-thread runtime is
+/// enough for one request at a time, and `block_on` may be called from
+/// several threads at once.
+#[cfg(feature = "network")]
+fn runtime() -> Result<&'static tokio::runtime::Runtime, String> {
+    static RUNTIME: std::sync::OnceLock<Result<tokio::runtime::Runtime, String>> =
+        std::sync::OnceLock::new();
+    RUNTIME
+        .get_or_init(|| {
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .map_err(|e| format!("could not start the async runtime: {}", e))
+        })
+        .as_ref()
+        .map_err(Clone::clone)
+}
+
+#[cfg(feature = "network")]
+fn parse(url: &str) -> Result<reqwest::Url, String> {
+    reqwest::Url::parse(url).map_err(|_| "invalid URL".to_string())
+}
+
+/// What went wrong, with the error's causes but without the URL reqwest
+/// would otherwise include.
+#[cfg(feature = "network")]
+fn reason(e: reqwest::Error) -> String {
+    let e = e.without_url();
+    let mut reason = e.to_string();
+    let mut source = std::error::Error::source(&e);
+    while let Some(cause) = source {
+        reason.push_str(": ");
+        reason.push_str(&cause.to_string());
+        source = cause.source();
+    }
+    reason
+}
+
+/// Stand-in when built without the `network` feature: every request fails,
+/// so commands that go online report why instead of failing to build.
+#[cfg(not(feature = "network"))]
+pub struct Client(());
+
+#[cfg(not(feature = "network"))]
+const OFFLINE: &str = "soon-migrate was built without the `network` feature";
+
+#[cfg(not(feature = "network"))]
+impl Client {
+    pub fn new(_timeout: Duration) -> Self {
+        Client(())
+    }
+
+    pub fn with_headers(self, _headers: Vec<(String, String)>) -> Self {
+        self
+    }
+
+    pub fn post_json(&self, _url: &str, _body: &Value) -> Result<Value, String> {
+        Err(OFFLINE.to_string())
+    }
+
+    pub fn post(&self, _url: &str, _body: &Value) -> Result<(), String> {
+        Err(OFFLINE.to_string())
+    }
+
+    pub fn get_text(&self, _url: &str) -> Result<String, String> {
+        Err(OFFLINE.to_string())
+    }
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Client::new(DEFAULT_TIMEOUT)
+    }
+}
+
+#[cfg(all(test, feature = "network"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_errors_leave_out_the_url() {
+        let client = Client::new(Duration::from_secs(2));
+        assert_eq!(
+            client.get_text("not a url?api-key=secret"),
+            Err("invalid URL".to_string())
+        );
+
+        // Nothing listens on port 9 of localhost (discard), so this fails fast
+        let e = client
+            .post_json("http://127.0.0.1:9/?api-key=secret", &Value::Null)
+            .unwrap_err();
+        assert!(!e.contains("secret"), "{}", e);
+    }
+}
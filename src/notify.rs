@@ -0,0 +1,248 @@
+use crate::cli::Config;
+use crate::endpoints::url_host;
+use crate::errors::MigrationError;
+use crate::network::Client;
+use crate::result::MigrationResult;
+use crate::settings::Settings;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::path::Path;
+use std::time::Duration;
+
+/// Shape of the message POSTed to the webhook.
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum NotifyFormat {
+    /// The run summary as plain JSON, for internal endpoints.
+    #[default]
+    Json,
+    Slack,
+    Discord,
+}
+
+impl NotifyFormat {
+    pub fn parse(format: &str) -> Option<Self> {
+        match format {
+            "json" => Some(NotifyFormat::Json),
+            "slack" => Some(NotifyFormat::Slack),
+            "discord" => Some(NotifyFormat::Discord),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Success,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn of(outcome: &Result<MigrationResult, MigrationError>) -> Self {
+        match outcome {
+            Ok(result) if result.warnings.is_empty() => Severity::Success,
+            Ok(_) => Severity::Warning,
+            Err(_) => Severity::Error,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Success => "success",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+/// Webhook body summarising a run of `project`.
+pub fn payload(
+    format: NotifyFormat,
+    project: &str,
+    outcome: &Result<MigrationResult, MigrationError>,
+) -> Value {
+    let severity = Severity::of(outcome);
+
+    match format {
+        NotifyFormat::Json => match outcome {
+            Ok(result) => json!({
+                "severity": severity.as_str(),
+                "project": project,
+                "network": result.network,
+                "dry_run": result.dry_run,
+                "changes": result.changes,
+                "warnings": result.warnings,
+            }),
+            Err(e) => json!({
+                "severity": severity.as_str(),
+                "project": project,
+                "error": e.to_string(),
+            }),
+        },
+        NotifyFormat::Slack => {
+            let (title, body) = headline(project, outcome);
+            let color = match severity {
+                Severity::Success => "good",
+                Severity::Warning => "warning",
+                Severity::Error => "danger",
+            };
+            json!({
+                "text": title,
+                "attachments": [{ "color": color, "text": body }],
+            })
+        }
+        NotifyFormat::Discord => {
+            let (title, body) = headline(project, outcome);
+            let color = match severity {
+                Severity::Success => 0x2eb67d,
+                Severity::Warning => 0xecb22e,
+                Severity::Error => 0xe01e5a,
+            };
+            json!({
+                "embeds": [{ "title": title, "description": body, "color": color }],
+            })
+        }
+    }
+}
+
+/// Title and body of a chat message about the run.
+fn headline(project: &str, outcome: &Result<MigrationResult, MigrationError>) -> (String, String) {
+    match outcome {
+        Ok(result) => {
+            let mut body = format!(
+                "{} change(s), {} warning(s){}",
+                result.changes.len(),
+                result.warnings.len(),
+                if result.dry_run { " (dry run)" } else { "" }
+            );
+            for warning in &result.warnings {
+                body.push_str(&format!("\n- {}", warning));
+            }
+            (
+                format!(
+                    "soon-migrate: {} migrated to SOON {}",
+                    project, result.network
+                ),
+                body,
+            )
+        }
+        Err(e) => (
+            format!("soon-migrate: {} failed to migrate", project),
+            e.to_string(),
+        ),
+    }
+}
+
+/// Sends the outcome of a run to the webhook from `--notify` or the `[notify]`
+/// config section. Returns whether a notification was sent.
+pub fn notify_run(
+    config: &Config,
+    outcome: &Result<MigrationResult, MigrationError>,
+) -> Result<bool, MigrationError> {
+    // A broken config file already failed the run; still report that
+    let settings = Settings::load(&config.path, config.config_file.as_deref()).unwrap_or_default();
+    let Some(webhook) = config
+        .notify
+        .as_deref()
+        .or(settings.notify.webhook.as_deref())
+    else {
+        return Ok(false);
+    };
+    if config.offline {
+        return Err(MigrationError::NotifyError(
+            "--offline is set, so the webhook was not contacted".to_string(),
+        ));
+    }
+    let format = config
+        .notify_format
+        .as_deref()
+        .and_then(NotifyFormat::parse)
+        .unwrap_or(settings.notify.format);
+
+    let path = Path::new(&config.path);
+    let project = path
+        .canonicalize()
+        .ok()
+        .and_then(|path| {
+            path.file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+        })
+        .unwrap_or_else(|| config.path.clone());
+
+    send(webhook, &payload(format, &project, outcome))?;
+    Ok(true)
+}
+
+/// POSTs `payload` to the webhook. Errors only name the webhook's host, since
+/// Slack and Discord webhook URLs are secrets.
+pub fn send(webhook: &str, payload: &Value) -> Result<(), MigrationError> {
+    if !webhook.starts_with("http://") && !webhook.starts_with("https://") {
+        return Err(MigrationError::NotifyError(
+            "webhook must be an http(s) URL".to_string(),
+        ));
+    }
+
+    Client::new(Duration::from_secs(10))
+        .post(webhook, payload)
+        .map_err(|e| MigrationError::NotifyError(format!("{}: {}", url_host(webhook), e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endpoints::SoonNetwork;
+    use crate::result::{Warning, WarningKind};
+
+    #[test]
+    fn test_payload_severity_formatting() {
+        let mut result = MigrationResult::new(SoonNetwork::Testnet, false);
+        result.warnings.push(Warning::new(
+            WarningKind::CustomCluster,
+            "unrecognised cluster",
+        ));
+        let outcome = Ok(result);
+
+        let slack = payload(NotifyFormat::Slack, "vault", &outcome);
+        assert_eq!(
+            slack["text"],
+            "soon-migrate: vault migrated to SOON testnet"
+        );
+        assert_eq!(slack["attachments"][0]["color"], "warning");
+        assert!(slack["attachments"][0]["text"]
+            .as_str()
+            .unwrap()
+            .contains("- unrecognised cluster"));
+
+        let json = payload(NotifyFormat::Json, "vault", &outcome);
+        assert_eq!(json["severity"], "warning");
+        assert_eq!(json["network"], "testnet");
+
+        let failed = Err(MigrationError::NotAnAnchorProject("vault".to_string()));
+        let discord = payload(NotifyFormat::Discord, "vault", &failed);
+        assert_eq!(discord["embeds"][0]["color"], 0xe01e5a);
+    }
+
+    #[test]
+    fn test_offline_never_contacts_webhook() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("soon-migrate.toml"),
+            "[notify]\nwebhook = \"http://127.0.0.1:9/hook\"\n",
+        )
+        .unwrap();
+        let config = Config {
+            path: dir.path().to_string_lossy().into_owned(),
+            offline: true,
+            ..Config::default()
+        };
+
+        let sent = notify_run(
+            &config,
+            &Ok(MigrationResult::new(SoonNetwork::Devnet, false)),
+        );
+        assert!(
+            matches!(sent, Err(MigrationError::NotifyError(message)) if message.contains("--offline"))
+        );
+    }
+}
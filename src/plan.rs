@@ -0,0 +1,629 @@
+use crate::compat::CompatFinding;
+use crate::endpoints::{
+    redact_url, redact_urls, url_host, url_spans, Endpoints, SoonNetwork, REDACTED,
+};
+use crate::errors::MigrationError;
+use crate::report::sha256_hex;
+use crate::result::{Change, Deployment, MigrationResult, Warning};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path};
+
+/// Bumped whenever the plan format changes incompatibly.
+pub const PLAN_VERSION: u32 = 1;
+
+/// Declarative description of a migration: the exact key-level edits to make,
+/// plus the changes and warnings they add up to. Produced by `plan`, executed
+/// verbatim by `apply`. Saved as JSON when the file name ends in `.json`,
+/// YAML otherwise.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Plan {
+    pub version: u32,
+    pub network: SoonNetwork,
+    pub edits: Vec<Edit>,
+    #[serde(default)]
+    pub changes: Vec<Change>,
+    #[serde(default)]
+    pub warnings: Vec<Warning>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub compatibility: Vec<CompatFinding>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deployments: Vec<Deployment>,
+    /// SHA-256 of every existing file the plan edits, as it was when planned.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub inputs: BTreeMap<String, String>,
+}
+
+/// One edit to a file relative to the project root: either a key-level edit
+/// of a TOML file, where `key` is the path of table names down to the edited
+/// key, or a line-level edit of any text file.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Edit {
+    pub file: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub key: Vec<String>,
+    #[serde(flatten)]
+    pub op: EditOp,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum EditOp {
+    Set {
+        value: toml::Value,
+    },
+    Remove,
+    /// Replaces the first `old` on 1-based `line` with `new`.
+    Replace {
+        line: usize,
+        old: String,
+        new: String,
+    },
+    /// Adds `text` as a new last line, creating the file if it is missing.
+    Append {
+        text: String,
+    },
+}
+
+impl Edit {
+    pub fn replace(file: &str, line: usize, old: &str, new: &str) -> Self {
+        Edit {
+            file: file.to_string(),
+            key: Vec::new(),
+            op: EditOp::Replace {
+                line,
+                old: old.to_string(),
+                new: new.to_string(),
+            },
+        }
+    }
+
+    pub fn append(file: &str, text: &str) -> Self {
+        Edit {
+            file: file.to_string(),
+            key: Vec::new(),
+            op: EditOp::Append {
+                text: text.to_string(),
+            },
+        }
+    }
+
+    /// Rewrites every piece of text the edit writes or looks for.
+    fn map_text(
+        &mut self,
+        map: &mut impl FnMut(&str) -> Result<String, MigrationError>,
+    ) -> Result<(), MigrationError> {
+        match &mut self.op {
+            EditOp::Set { value } => map_strings(value, map),
+            EditOp::Remove => Ok(()),
+            EditOp::Replace { old, new, .. } => {
+                *old = map(old)?;
+                *new = map(new)?;
+                Ok(())
+            }
+            EditOp::Append { text } => {
+                *text = map(text)?;
+                Ok(())
+            }
+        }
+    }
+
+    fn is_text(&self) -> bool {
+        matches!(self.op, EditOp::Replace { .. } | EditOp::Append { .. })
+    }
+}
+
+impl Plan {
+    pub fn load(path: &str) -> Result<Self, MigrationError> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| MigrationError::PlanError(format!("{}: {}", path, e)))?;
+        let plan: Plan = if is_json(path) {
+            serde_json::from_str(&content).map_err(|e| e.to_string())
+        } else {
+            serde_yaml::from_str(&content).map_err(|e| e.to_string())
+        }
+        .map_err(|e| MigrationError::PlanError(format!("{}: {}", path, e)))?;
+
+        if plan.version != PLAN_VERSION {
+            return Err(MigrationError::PlanError(format!(
+                "{}: unsupported plan version {} (expected {})",
+                path, plan.version, PLAN_VERSION
+            )));
+        }
+        for file in plan
+            .edits
+            .iter()
+            .map(|edit| &edit.file)
+            .chain(plan.inputs.keys())
+        {
+            check_relative(file)?;
+        }
+
+        Ok(plan)
+    }
+
+    /// Writes the plan with every URL redacted, so API keys in endpoints
+    /// stay out of it; `restore_urls` puts them back before it is applied.
+    pub fn save(&self, path: &str) -> Result<(), MigrationError> {
+        let redacted = self.redacted();
+        let content = if is_json(path) {
+            serde_json::to_string_pretty(&redacted)
+                .map(|json| json + "\n")
+                .map_err(|e| MigrationError::PlanError(e.to_string()))?
+        } else {
+            serde_yaml::to_string(&redacted)
+                .map_err(|e| MigrationError::PlanError(e.to_string()))?
+        };
+        fs::write(path, content).map_err(|e| MigrationError::PlanError(format!("{}: {}", path, e)))
+    }
+
+    fn redacted(&self) -> Plan {
+        let mut plan = self.clone();
+        for edit in &mut plan.edits {
+            edit.map_text(&mut |text| Ok(redact_urls(text)))
+                .expect("redacting cannot fail");
+        }
+        for change in &mut plan.changes {
+            change.detail = redact_urls(&change.detail);
+        }
+        for warning in &mut plan.warnings {
+            warning.message = redact_urls(&warning.message);
+        }
+        plan
+    }
+
+    /// Puts back the URLs `save` redacted: each must be a SOON endpoint in
+    /// `endpoints` or a URL in the file being edited, which `check_inputs`
+    /// guarantees is unchanged since the plan was made.
+    pub fn restore_urls(
+        &mut self,
+        project: &Path,
+        endpoints: &Endpoints,
+    ) -> Result<(), MigrationError> {
+        for edit in &mut self.edits {
+            let content = fs::read_to_string(project.join(&edit.file)).unwrap_or_default();
+            let mut candidates: Vec<&str> = [
+                SoonNetwork::Devnet,
+                SoonNetwork::Testnet,
+                SoonNetwork::Mainnet,
+            ]
+            .into_iter()
+            .map(|network| endpoints.url(network))
+            .collect();
+            candidates.extend(url_spans(&content).into_iter().map(|span| &content[span]));
+
+            let file = edit.file.clone();
+            edit.map_text(&mut |text| restore(text, &candidates, &file))?;
+        }
+        Ok(())
+    }
+
+    /// Records the current hash of every existing file the plan edits.
+    pub fn hash_inputs(&mut self, project: &Path) {
+        let inputs = self
+            .files()
+            .into_iter()
+            .filter_map(|file| {
+                let content = fs::read(project.join(file)).ok()?;
+                Some((file.to_string(), sha256_hex(&content)))
+            })
+            .collect();
+        self.inputs = inputs;
+    }
+
+    /// Fails if any file changed since the plan was made, so an approved plan
+    /// is never applied to content nobody reviewed.
+    pub fn check_inputs(&self, project: &Path) -> Result<(), MigrationError> {
+        for (file, expected) in &self.inputs {
+            let content = fs::read(project.join(file))
+                .map_err(|e| MigrationError::PlanError(format!("{}: {}", file, e)))?;
+            if sha256_hex(&content) != *expected {
+                return Err(MigrationError::PlanError(format!(
+                    "{} changed since the plan was made; plan again",
+                    file
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Files touched by the plan, in the order they are first edited.
+    pub fn files(&self) -> Vec<&str> {
+        let mut files: Vec<&str> = Vec::new();
+        for edit in &self.edits {
+            if !files.contains(&edit.file.as_str()) {
+                files.push(&edit.file);
+            }
+        }
+        files
+    }
+
+    pub fn edits_for<'a>(&'a self, file: &'a str) -> impl Iterator<Item = &'a Edit> {
+        self.edits.iter().filter(move |edit| edit.file == file)
+    }
+
+    pub fn into_result(self, dry_run: bool) -> MigrationResult {
+        MigrationResult {
+            network: self.network,
+            dry_run,
+            changes: self.changes,
+            warnings: self.warnings,
+            compatibility: self.compatibility,
+            deployments: self.deployments,
+        }
+    }
+}
+
+fn map_strings(
+    value: &mut toml::Value,
+    map: &mut impl FnMut(&str) -> Result<String, MigrationError>,
+) -> Result<(), MigrationError> {
+    match value {
+        toml::Value::String(text) => *text = map(text)?,
+        toml::Value::Array(values) => {
+            for value in values {
+                map_strings(value, map)?;
+            }
+        }
+        toml::Value::Table(table) => {
+            for (_, value) in table.iter_mut() {
+                map_strings(value, map)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// `text` with each redacted URL replaced by the one candidate it was
+/// redacted from.
+fn restore(text: &str, candidates: &[&str], file: &str) -> Result<String, MigrationError> {
+    let mut restored = String::new();
+    let mut last = 0;
+    for span in url_spans(text) {
+        if !text[span.end..].starts_with(REDACTED) {
+            continue;
+        }
+        let redacted = &text[span.start..span.end + REDACTED.len()];
+        let mut matches: Vec<&str> = candidates
+            .iter()
+            .copied()
+            .filter(|candidate| redact_url(candidate) == redacted)
+            .collect();
+        matches.dedup();
+        let [url] = matches[..] else {
+            return Err(MigrationError::PlanError(format!(
+                "{}: cannot tell which {} URL the plan means; pass the same --rpc-* flags or config file as when planning",
+                file,
+                url_host(redacted)
+            )));
+        };
+        restored.push_str(&text[last..span.start]);
+        restored.push_str(url);
+        last = span.end + REDACTED.len();
+    }
+    restored.push_str(&text[last..]);
+    Ok(restored)
+}
+
+fn is_json(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("json"))
+}
+
+/// Edits turning `old` into `new`, descending into tables present on both
+/// sides so that unrelated keys are left alone.
+pub fn diff_toml(file: &str, old: &toml::Value, new: &toml::Value) -> Vec<Edit> {
+    let mut edits = Vec::new();
+    diff_value(file, &mut Vec::new(), old, new, &mut edits);
+    edits
+}
+
+fn diff_value(
+    file: &str,
+    key: &mut Vec<String>,
+    old: &toml::Value,
+    new: &toml::Value,
+    edits: &mut Vec<Edit>,
+) {
+    match (old.as_table(), new.as_table()) {
+        (Some(old_table), Some(new_table)) => {
+            for (name, old_value) in old_table {
+                key.push(name.clone());
+                match new_table.get(name) {
+                    Some(new_value) => diff_value(file, key, old_value, new_value, edits),
+                    None => edits.push(Edit {
+                        file: file.to_string(),
+                        key: key.clone(),
+                        op: EditOp::Remove,
+                    }),
+                }
+                key.pop();
+            }
+            for (name, new_value) in new_table {
+                if !old_table.contains_key(name) {
+                    key.push(name.clone());
+                    edits.push(Edit {
+                        file: file.to_string(),
+                        key: key.clone(),
+                        op: EditOp::Set {
+                            value: new_value.clone(),
+                        },
+                    });
+                    key.pop();
+                }
+            }
+        }
+        _ if old != new => edits.push(Edit {
+            file: file.to_string(),
+            key: key.clone(),
+            op: EditOp::Set { value: new.clone() },
+        }),
+        _ => {}
+    }
+}
+
+/// New content of `file` after applying its edits. TOML edits re-serialize the
+/// document; line replacements leave every other byte untouched.
+pub fn render_file<'a>(
+    file: &str,
+    content: &str,
+    edits: impl Iterator<Item = &'a Edit>,
+) -> Result<String, MigrationError> {
+    let edits: Vec<&Edit> = edits.collect();
+
+    if edits.iter().all(|edit| edit.is_text()) {
+        return apply_line_edits(content, edits.into_iter());
+    }
+    if edits.iter().any(|edit| edit.is_text()) {
+        return Err(MigrationError::PlanError(format!(
+            "{}: mixes key edits and line replacements",
+            file
+        )));
+    }
+
+    let mut document: toml::Value = content
+        .parse()
+        .map_err(|e: toml::de::Error| MigrationError::TomlParseError(e.to_string()))?;
+    apply_edits(&mut document, edits.into_iter())?;
+    toml::to_string_pretty(&document).map_err(|e| MigrationError::TomlParseError(e.to_string()))
+}
+
+fn apply_line_edits<'a>(
+    content: &str,
+    edits: impl Iterator<Item = &'a Edit>,
+) -> Result<String, MigrationError> {
+    let mut lines: Vec<String> = content.split_inclusive('\n').map(String::from).collect();
+
+    for edit in edits {
+        let (line, old, new) = match &edit.op {
+            EditOp::Replace { line, old, new } => (line, old, new),
+            EditOp::Append { text } => {
+                if let Some(last) = lines.last_mut().filter(|last| !last.ends_with('\n')) {
+                    last.push('\n');
+                }
+                lines.push(format!("{}\n", text));
+                continue;
+            }
+            _ => continue,
+        };
+        let target = line
+            .checked_sub(1)
+            .and_then(|index| lines.get_mut(index))
+            .filter(|text| text.contains(old.as_str()))
+            .ok_or_else(|| {
+                MigrationError::PlanError(format!(
+                    "{}:{}: expected to find {:?}; the file changed since the plan was made",
+                    edit.file, line, old
+                ))
+            })?;
+        *target = target.replacen(old.as_str(), new, 1);
+    }
+
+    Ok(lines.concat())
+}
+
+/// Applies key-level edits to a parsed TOML document, creating intermediate
+/// tables as needed.
+pub fn apply_edits<'a>(
+    document: &mut toml::Value,
+    edits: impl Iterator<Item = &'a Edit>,
+) -> Result<(), MigrationError> {
+    for edit in edits {
+        let Some((last, parents)) = edit.key.split_last().filter(|_| !edit.is_text()) else {
+            return Err(MigrationError::PlanError(format!(
+                "{}: edit with an empty key",
+                edit.file
+            )));
+        };
+
+        let mut table = document.as_table_mut().ok_or_else(|| not_a_table(edit))?;
+        for name in parents {
+            table = table
+                .entry(name.as_str())
+                .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+                .as_table_mut()
+                .ok_or_else(|| not_a_table(edit))?;
+        }
+
+        match &edit.op {
+            EditOp::Set { value } => {
+                table.insert(last.clone(), value.clone());
+            }
+            EditOp::Remove => {
+                table.remove(last);
+            }
+            EditOp::Replace { .. } | EditOp::Append { .. } => {
+                unreachable!("line edits have no key")
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn not_a_table(edit: &Edit) -> MigrationError {
+    MigrationError::PlanError(format!(
+        "{}: {} is not inside a table",
+        edit.file,
+        edit.key.join(".")
+    ))
+}
+
+/// Plans come from outside the tool, so their paths must stay inside the project.
+fn check_relative(file: &str) -> Result<(), MigrationError> {
+    let inside = Path::new(file)
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+    if inside && !file.is_empty() {
+        Ok(())
+    } else {
+        Err(MigrationError::PlanError(format!(
+            "refusing to edit {} outside the project",
+            file
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_and_apply_round_trip() {
+        let old: toml::Value = r#"
+[provider]
+cluster = "Localnet"
+wallet = "id.json"
+
+[programs.localnet]
+a = "A"
+"#
+        .parse()
+        .unwrap();
+        let new: toml::Value = r#"
+[provider]
+cluster = "https://rpc.devnet.soo.network/rpc"
+wallet = "id.json"
+
+[programs.devnet]
+a = "A"
+"#
+        .parse()
+        .unwrap();
+
+        let edits = diff_toml("Anchor.toml", &old, &new);
+        assert_eq!(edits.len(), 3);
+        assert!(edits.iter().all(|edit| edit.key != ["provider", "wallet"]));
+
+        let mut document = old.clone();
+        apply_edits(&mut document, edits.iter()).unwrap();
+        assert_eq!(document, new);
+    }
+
+    #[test]
+    fn test_line_replacement() {
+        let content = "a\nkeep \"old\" old\r\nb";
+        let edits = [Edit::replace("lib.rs", 2, "old", "new")];
+
+        let rendered = render_file("lib.rs", content, edits.iter()).unwrap();
+        assert_eq!(rendered, "a\nkeep \"new\" old\r\nb");
+
+        let stale = [Edit::replace("lib.rs", 3, "old", "new")];
+        assert!(render_file("lib.rs", content, stale.iter()).is_err());
+    }
+
+    #[test]
+    fn test_plan_yaml_round_trip() {
+        let plan = Plan {
+            version: PLAN_VERSION,
+            network: SoonNetwork::Devnet,
+            edits: vec![Edit {
+                file: "Anchor.toml".to_string(),
+                key: vec!["provider".to_string(), "cluster".to_string()],
+                op: EditOp::Set {
+                    value: toml::Value::String("https://rpc.devnet.soo.network/rpc".to_string()),
+                },
+            }],
+            changes: Vec::new(),
+            warnings: Vec::new(),
+            compatibility: Vec::new(),
+            deployments: Vec::new(),
+            inputs: BTreeMap::from([("Anchor.toml".to_string(), "ab".repeat(32))]),
+        };
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("plan.yaml");
+        let path = path.to_str().unwrap();
+
+        plan.save(path).unwrap();
+
+        let yaml = fs::read_to_string(path).unwrap();
+        assert!(yaml.contains("op: set"));
+        assert_eq!(Plan::load(path).unwrap(), plan);
+
+        let json_path = dir.path().join("plan.json");
+        let json_path = json_path.to_str().unwrap();
+        plan.save(json_path).unwrap();
+        let json = fs::read_to_string(json_path).unwrap();
+        assert!(json.contains("\"op\": \"set\""));
+        assert_eq!(Plan::load(json_path).unwrap(), plan);
+    }
+
+    #[test]
+    fn test_saved_plan_leaves_out_api_keys() {
+        let soon = "https://rpc.example/?api-key=SECRET";
+        let solana = "https://mainnet.helius-rpc.com/?api-key=SOLANA_SECRET";
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::write(
+            dir.path().join("deploy.sh"),
+            format!("solana deploy --url {}\n", solana),
+        )
+        .unwrap();
+        let plan = Plan {
+            version: PLAN_VERSION,
+            network: SoonNetwork::Devnet,
+            edits: vec![
+                Edit {
+                    file: "Anchor.toml".to_string(),
+                    key: vec!["provider".to_string(), "cluster".to_string()],
+                    op: EditOp::Set {
+                        value: toml::Value::String(soon.to_string()),
+                    },
+                },
+                Edit::replace("deploy.sh", 1, solana, soon),
+            ],
+            changes: Vec::new(),
+            warnings: Vec::new(),
+            compatibility: Vec::new(),
+            deployments: Vec::new(),
+            inputs: BTreeMap::new(),
+        };
+        let path = dir.path().join("plan.yaml");
+        let path = path.to_str().unwrap();
+
+        plan.save(path).unwrap();
+        assert!(!fs::read_to_string(path).unwrap().contains("SECRET"));
+
+        let mut loaded = Plan::load(path).unwrap();
+        let endpoints = Endpoints {
+            devnet: soon.to_string(),
+            ..Endpoints::default()
+        };
+        assert!(loaded
+            .clone()
+            .restore_urls(dir.path(), &Endpoints::default())
+            .is_err());
+        loaded.restore_urls(dir.path(), &endpoints).unwrap();
+        assert_eq!(loaded, plan);
+    }
+
+    #[test]
+    fn test_plan_rejects_paths_outside_project() {
+        assert!(check_relative("Anchor.toml").is_ok());
+        assert!(check_relative("programs/a/Cargo.toml").is_ok());
+        assert!(check_relative("../Anchor.toml").is_err());
+        assert!(check_relative("/etc/passwd").is_err());
+    }
+}
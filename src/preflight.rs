@@ -0,0 +1,383 @@
+use crate::endpoints::SoonNetwork;
+use crate::errors::MigrationError;
+use crate::result::{Warning, WarningKind};
+use crate::rpc::Rpc;
+use crate::wallet::{self, LAMPORTS_PER_SOL};
+use crate::workspace::SEAHORSE_DIR;
+use serde_json::json;
+use std::collections::BTreeSet;
+use std::fs::{self, OpenOptions};
+use std::io::ErrorKind;
+use std::path::Path;
+use std::process::Command;
+
+/// Build commands to try, in order; the first one that is installed is run.
+const BUILD_COMMANDS: &[(&str, &[&str])] = &[("anchor", &["build"]), ("cargo", &["build-sbf"])];
+
+/// How much of a failed build's output ends up in the error.
+const OUTPUT_TAIL_LINES: usize = 20;
+
+/// Builds the project as it is, before the migration touches anything, so
+/// breakage that was already there is not blamed on the migration. Returns
+/// the command that was run. With `offline`, Cargo is kept from fetching
+/// crates, so only what is already downloaded can be used.
+pub fn build(project: &Path, offline: bool) -> Result<String, MigrationError> {
+    // Seahorse has to regenerate the Rust sources from Python first
+    if project.join(SEAHORSE_DIR).is_dir() {
+        return run_build(project, &[("seahorse", &["build"])], offline);
+    }
+    run_build(project, BUILD_COMMANDS, offline)
+}
+
+fn run_build(
+    project: &Path,
+    commands: &[(&str, &[&str])],
+    offline: bool,
+) -> Result<String, MigrationError> {
+    for (program, args) in commands {
+        let command = format!("{} {}", program, args.join(" ")).trim().to_string();
+        let mut build = Command::new(program);
+        build.args(*args).current_dir(project);
+        if offline {
+            build.env("CARGO_NET_OFFLINE", "true");
+        }
+        let output = match build.output() {
+            Ok(output) => output,
+            Err(e) if e.kind() == ErrorKind::NotFound => continue,
+            Err(e) => {
+                return Err(MigrationError::PreflightFailed(format!(
+                    "{}: {}",
+                    command, e
+                )));
+            }
+        };
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let lines: Vec<&str> = stderr.lines().collect();
+            let tail = lines[lines.len().saturating_sub(OUTPUT_TAIL_LINES)..].join("\n");
+            return Err(MigrationError::PreflightFailed(format!(
+                "{} failed; fix the build before migrating\n{}",
+                command, tail
+            )));
+        }
+        return Ok(command);
+    }
+
+    let tried: Vec<&str> = commands.iter().map(|(program, _)| *program).collect();
+    Err(MigrationError::PreflightFailed(format!(
+        "none of {} is installed",
+        tried.join(", ")
+    )))
+}
+
+/// Checks that every file about to be rewritten, as `(file, current
+/// content, new content)`, can be written and that the disk has room for the
+/// backups. All problems are reported together, before anything is touched.
+pub fn check_writable(
+    project: &Path,
+    files: &[(String, String, String)],
+) -> Result<(), MigrationError> {
+    let mut problems = Vec::new();
+    let mut dirs = BTreeSet::new();
+    let mut needed: u64 = 0;
+
+    for (file, current, new) in files {
+        let path = project.join(file);
+        if let Some(dir) = path.parent() {
+            dirs.insert(dir.to_path_buf());
+        }
+        needed += new.len() as u64;
+
+        let Ok(metadata) = fs::metadata(&path) else {
+            continue;
+        };
+        needed += current.len() as u64;
+        if metadata.permissions().readonly() {
+            problems.push(format!("{} is read-only", file));
+        } else if let Err(e) = OpenOptions::new().append(true).open(&path) {
+            // Also where Windows reports a file held open by another process
+            problems.push(format!("{} cannot be opened for writing: {}", file, e));
+        }
+    }
+
+    // Backups and new files are created next to the files they belong to
+    for dir in &dirs {
+        if let Err(e) = tempfile::NamedTempFile::new_in(dir) {
+            let relative = dir.strip_prefix(project).unwrap_or(dir);
+            let name = match relative.to_string_lossy() {
+                name if name.is_empty() => ".".to_string(),
+                name => name.into_owned(),
+            };
+            problems.push(format!("cannot create files in {}: {}", name, e));
+        }
+    }
+
+    if let Some(available) = available_space(project) {
+        if available < needed {
+            problems.push(format!(
+                "{} bytes needed for backups and rewritten files, but only {} free",
+                needed, available
+            ));
+        }
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(MigrationError::PreflightFailed(format!(
+            "nothing was changed\n  {}",
+            problems.join("\n  ")
+        )))
+    }
+}
+
+/// Largest account the SVM allows, and so the largest program it can hold.
+const MAX_PROGRAM_SIZE: u64 = 10 * 1024 * 1024;
+
+/// Share of [`MAX_PROGRAM_SIZE`], in percent, above which a program is
+/// reported as close to the limit.
+const PROGRAM_SIZE_WARN_PERCENT: u64 = 80;
+
+/// Bytes the program data account holds besides the program itself: its own
+/// header, and the overhead rent is charged for on every account.
+const PROGRAM_ACCOUNT_OVERHEAD: u64 = 45 + 128;
+
+/// Lamports per byte for rent exemption at Solana's default rent.
+const RENT_EXEMPT_LAMPORTS_PER_BYTE: u64 = 3480 * 2;
+
+/// Built programs in `target/deploy/` and their sizes, by file name.
+fn built_programs(project: &Path) -> Vec<(String, u64)> {
+    let Ok(entries) = fs::read_dir(project.join("target").join("deploy")) else {
+        return Vec::new();
+    };
+
+    let mut programs: Vec<(String, u64)> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            name.ends_with(".so")
+                .then_some((name, entry.metadata().ok()?.len()))
+        })
+        .collect();
+    programs.sort();
+    programs
+}
+
+/// Warnings for built programs in `target/deploy/` that are close to the
+/// largest account SOON can hold. Nothing is reported before a build.
+pub fn program_size_warnings(project: &Path) -> Vec<Warning> {
+    built_programs(project)
+        .into_iter()
+        .filter_map(|(name, size)| program_size_warning(&format!("target/deploy/{}", name), size))
+        .collect()
+}
+
+fn program_size_warning(file: &str, size: u64) -> Option<Warning> {
+    if size * 100 < MAX_PROGRAM_SIZE * PROGRAM_SIZE_WARN_PERCENT {
+        return None;
+    }
+    let lamports = (size + PROGRAM_ACCOUNT_OVERHEAD) * RENT_EXEMPT_LAMPORTS_PER_BYTE;
+    Some(
+        Warning::new(
+            WarningKind::ProgramSize,
+            format!(
+                "program is {} KiB, {}% of the {} KiB an account can hold; deploying it takes about {:.2} SOL at Solana's rent",
+                size / 1024,
+                size * 100 / MAX_PROGRAM_SIZE,
+                MAX_PROGRAM_SIZE / 1024,
+                lamports as f64 / 1e9
+            ),
+        )
+        .in_file(file)
+        .recommend(
+            "Shrink the program before it outgrows the limit: set opt-level = \"z\", lto = \"fat\" and codegen-units = 1 under [profile.release], and drop unused features of large dependencies.",
+        ),
+    )
+}
+
+/// Header of the buffer a deploy writes the program into first.
+const BUFFER_HEADER: u64 = 37;
+
+/// Header of the program data account the buffer is copied into.
+const PROGRAM_DATA_HEADER: u64 = 45;
+
+/// Size of the program account, which points at its program data.
+const PROGRAM_ACCOUNT_SIZE: u64 = 36;
+
+/// Program bytes a deploy writes per transaction, and the fee each one pays.
+const DEPLOY_CHUNK_SIZE: u64 = 1000;
+const SIGNATURE_FEE: u64 = 5000;
+
+/// Lamports the wallet needs to deploy a program of `size` bytes: rent for
+/// the program and its program data, rent for the buffer until the deploy
+/// closes it, and the fee of every write. `rent` gives the rent-exempt
+/// balance of an account of the given size on the target network.
+fn deploy_cost(
+    size: u64,
+    rent: impl Fn(u64) -> Result<u64, MigrationError>,
+) -> Result<u64, MigrationError> {
+    let writes = size.div_ceil(DEPLOY_CHUNK_SIZE) + 2;
+    Ok(rent(PROGRAM_ACCOUNT_SIZE)?
+        + rent(PROGRAM_DATA_HEADER + size)?
+        + rent(BUFFER_HEADER + size)?
+        + writes * SIGNATURE_FEE)
+}
+
+/// Warns when the project's wallet cannot pay for deploying every program
+/// in `target/deploy/` to SOON, or holds nothing at all before a build.
+/// Says nothing when the wallet's keypair is not on this machine.
+pub fn wallet_balance_warnings(project: &Path, network: SoonNetwork, rpc: &Rpc) -> Vec<Warning> {
+    let Ok(address) = wallet::wallet_address(project) else {
+        return Vec::new();
+    };
+    let rent = |size: u64| {
+        rpc.call("getMinimumBalanceForRentExemption", json!([size]))?
+            .as_u64()
+            .ok_or_else(|| {
+                MigrationError::RpcError(format!(
+                    "{} getMinimumBalanceForRentExemption: not a balance",
+                    rpc.host()
+                ))
+            })
+    };
+    let checked = wallet::balance(rpc, &address).and_then(|balance| {
+        let cost = built_programs(project)
+            .into_iter()
+            .map(|(_, size)| deploy_cost(size, rent))
+            .sum::<Result<u64, MigrationError>>()?;
+        Ok((balance, cost))
+    });
+
+    match checked {
+        Ok((balance, cost)) => wallet_balance_warning(&address, network, balance, cost)
+            .into_iter()
+            .collect(),
+        Err(e) => vec![Warning::new(
+            WarningKind::WalletBalance,
+            format!("could not check the balance of wallet {}: {}", address, e),
+        )],
+    }
+}
+
+fn wallet_balance_warning(
+    address: &str,
+    network: SoonNetwork,
+    balance: u64,
+    cost: u64,
+) -> Option<Warning> {
+    let sol = |lamports: u64| lamports as f64 / LAMPORTS_PER_SOL as f64;
+    let message = if cost == 0 && balance == 0 {
+        format!("wallet {} holds nothing on SOON {}", address, network)
+    } else if balance < cost {
+        format!(
+            "wallet {} holds {:.4} SOL on SOON {}, but deploying the built programs takes about {:.4} SOL",
+            address,
+            sol(balance),
+            network,
+            sol(cost)
+        )
+    } else {
+        return None;
+    };
+
+    let recommendation = match network {
+        SoonNetwork::Devnet => "Fund it with soon-migrate airdrop before deploying.".to_string(),
+        _ => format!("Fund it on SOON {} before deploying.", network),
+    };
+    Some(Warning::new(WarningKind::WalletBalance, message).recommend(recommendation))
+}
+
+/// Bytes free for unprivileged users on the filesystem holding `path`.
+#[cfg(unix)]
+fn available_space(path: &Path) -> Option<u64> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stats: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stats) } != 0 {
+        return None;
+    }
+    Some(stats.f_bavail as u64 * stats.f_frsize as u64)
+}
+
+#[cfg(not(unix))]
+fn available_space(_path: &Path) -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_build_falls_back_and_fails() {
+        let dir = tempfile::TempDir::new().unwrap();
+
+        let ran = run_build(
+            dir.path(),
+            &[("soon-migrate-no-such-tool", &["build"]), ("true", &[])],
+            false,
+        );
+        assert_eq!(ran.unwrap(), "true");
+
+        assert!(matches!(
+            run_build(dir.path(), &[("false", &[])], false),
+            Err(MigrationError::PreflightFailed(_))
+        ));
+        assert!(run_build(dir.path(), &[("soon-migrate-no-such-tool", &[])], true).is_err());
+    }
+
+    #[test]
+    fn test_program_size_warning() {
+        assert!(program_size_warning("target/deploy/small.so", 400 * 1024).is_none());
+
+        let warning = program_size_warning("target/deploy/big.so", 9 * 1024 * 1024).unwrap();
+        assert_eq!(warning.kind, WarningKind::ProgramSize);
+        assert!(warning
+            .message
+            .starts_with("program is 9216 KiB, 90% of the 10240 KiB"));
+    }
+
+    #[test]
+    fn test_wallet_balance_warning() {
+        let cost = deploy_cost(200_000, |size| Ok((size + 128) * 6960)).unwrap();
+        assert_eq!(cost, 2_788_503_920);
+
+        let address = "3ARMH9zfVCnU2TKiphU4xcEyWdA45fc1sjKEtYMdf3gr";
+        let short =
+            wallet_balance_warning(address, SoonNetwork::Devnet, LAMPORTS_PER_SOL, cost).unwrap();
+        assert_eq!(
+            short.message,
+            "wallet 3ARMH9zfVCnU2TKiphU4xcEyWdA45fc1sjKEtYMdf3gr holds 1.0000 SOL on SOON devnet, but deploying the built programs takes about 2.7885 SOL"
+        );
+        assert!(short.recommendation.unwrap().contains("airdrop"));
+        assert!(wallet_balance_warning(address, SoonNetwork::Mainnet, 0, 0).is_some());
+        assert!(wallet_balance_warning(address, SoonNetwork::Mainnet, cost, cost).is_none());
+    }
+
+    #[test]
+    fn test_check_writable_reports_every_problem() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let project = dir.path();
+        for file in ["Anchor.toml", "Cargo.toml"] {
+            fs::write(project.join(file), "[provider]\n").unwrap();
+            let mut permissions = fs::metadata(project.join(file)).unwrap().permissions();
+            permissions.set_readonly(true);
+            fs::set_permissions(project.join(file), permissions).unwrap();
+        }
+        let files: Vec<_> = ["Anchor.toml", "Cargo.toml", ".env.example"]
+            .iter()
+            .map(|file| (file.to_string(), String::new(), "x\n".to_string()))
+            .collect();
+
+        let Err(MigrationError::PreflightFailed(message)) = check_writable(project, &files) else {
+            panic!("read-only files were not reported");
+        };
+        assert!(message.contains("Anchor.toml is read-only"));
+        assert!(message.contains("Cargo.toml is read-only"));
+
+        assert!(check_writable(project, &files[2..]).is_ok());
+    }
+}
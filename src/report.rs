@@ -0,0 +1,528 @@
+use crate::compat::{
+    complexity, oracle_redundancy, Availability, CompatFinding, Complexity, OracleRedundancy,
+};
+use crate::endpoints::SoonNetwork;
+use crate::errors::MigrationError;
+use crate::result::{Change, MigrationResult, Warning};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+const SIGNATURE_ALGORITHM: &str = "ed25519";
+
+/// Bumped whenever the report's shape changes, additions included: reports
+/// are read with unknown fields rejected and the schema allows no other
+/// properties, so an older reader cannot take a newer report.
+pub const REPORT_SCHEMA_VERSION: u32 = 3;
+
+/// JSON Schema of `Report`, derived from the types themselves. Printed by
+/// `soon-migrate schema` and published as `schema/report.schema.json`.
+pub fn report_schema() -> String {
+    let schema = schemars::schema_for!(Report);
+    serde_json::to_string_pretty(&schema).expect("the report schema serializes") + "\n"
+}
+
+/// JSON Schema of `RollUp`, printed by `soon-migrate schema --roll-up` and
+/// published as `schema/rollup.schema.json`.
+pub fn roll_up_schema() -> String {
+    let schema = schemars::schema_for!(RollUp);
+    serde_json::to_string_pretty(&schema).expect("the roll-up schema serializes") + "\n"
+}
+
+/// JSON record of a migration run, meant to be attached to audits. The same
+/// inputs always produce the same report: there are no timestamps, and maps
+/// are ordered.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+#[schemars(
+    title = "soon-migrate report",
+    extend("$id" = "https://github.com/akshatcoder-hash/soon-migrate/blob/main/schema/report.schema.json")
+)]
+pub struct Report {
+    /// `REPORT_SCHEMA_VERSION` of the tool that wrote the report; absent in
+    /// reports from before it was versioned.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema_version: Option<u32>,
+    pub tool: String,
+    pub version: String,
+    pub network: SoonNetwork,
+    pub dry_run: bool,
+    pub changes: Vec<Change>,
+    pub warnings: Vec<Warning>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub compatibility: Vec<CompatFinding>,
+    /// Oracle networks the project uses together, with advice for moving
+    /// them to APRO as one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oracle_redundancy: Option<OracleRedundancy>,
+    /// How much work the compatibility findings add up to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub complexity: Option<Complexity>,
+    pub provenance: Provenance,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<ReportSignature>,
+}
+
+/// What the run started from.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Provenance {
+    /// SHA-256 of the config file, if one was used.
+    pub config_sha256: Option<String>,
+    /// SHA-256 of every file the run read or changed, before the run.
+    pub inputs: BTreeMap<String, String>,
+    /// SHA-256 of the report with `digest` empty and no signature.
+    pub digest: String,
+}
+
+/// Ed25519 signature over `provenance.digest`, keys and signatures in base58.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ReportSignature {
+    pub algorithm: String,
+    pub public_key: String,
+    pub value: String,
+}
+
+impl Provenance {
+    /// Hashes the config file and the given project files as they are now.
+    /// Must be called before the migration writes anything.
+    pub fn collect<'a>(
+        project: &Path,
+        config_file: Option<&Path>,
+        files: impl Iterator<Item = &'a str>,
+    ) -> Result<Self, MigrationError> {
+        let config_sha256 = config_file.map(hash_file).transpose()?;
+
+        let mut inputs = BTreeMap::new();
+        for file in files {
+            inputs.insert(file.to_string(), hash_file(&project.join(file))?);
+        }
+
+        Ok(Provenance {
+            config_sha256,
+            inputs,
+            digest: String::new(),
+        })
+    }
+}
+
+impl Report {
+    pub fn new(result: &MigrationResult, provenance: Provenance) -> Self {
+        let mut report = Report {
+            schema_version: Some(REPORT_SCHEMA_VERSION),
+            tool: env!("CARGO_PKG_NAME").to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            network: result.network,
+            dry_run: result.dry_run,
+            changes: result.changes.clone(),
+            warnings: result.warnings.clone(),
+            compatibility: result.compatibility.clone(),
+            oracle_redundancy: oracle_redundancy(&result.compatibility),
+            complexity: complexity(&result.compatibility),
+            provenance,
+            signature: None,
+        };
+        report.provenance.digest = report.compute_digest();
+        report
+    }
+
+    pub fn load(path: &str) -> Result<Self, MigrationError> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| MigrationError::ReportError(format!("{}: {}", path, e)))?;
+        let report: Report = serde_json::from_str(&content)
+            .map_err(|e| MigrationError::ReportError(format!("{}: {}", path, e)))?;
+
+        match report.schema_version {
+            Some(version) if version > REPORT_SCHEMA_VERSION => {
+                Err(MigrationError::ReportError(format!(
+                    "{}: schema version {} is newer than this soon-migrate supports ({})",
+                    path, version, REPORT_SCHEMA_VERSION
+                )))
+            }
+            _ => Ok(report),
+        }
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), MigrationError> {
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| MigrationError::ReportError(e.to_string()))?;
+        fs::write(path, content + "\n")
+            .map_err(|e| MigrationError::ReportError(format!("{}: {}", path, e)))
+    }
+
+    /// Signs the digest; see `load_keypair`.
+    pub fn sign(&mut self, key: &SigningKey) {
+        let signature = key.sign(self.provenance.digest.as_bytes());
+
+        self.signature = Some(ReportSignature {
+            algorithm: SIGNATURE_ALGORITHM.to_string(),
+            public_key: bs58::encode(key.verifying_key().to_bytes()).into_string(),
+            value: bs58::encode(signature.to_bytes()).into_string(),
+        });
+    }
+
+    /// Checks that the report has not been edited since it was written and,
+    /// if signed, that the signature matches. Returns the signer's public key.
+    pub fn verify(&self) -> Result<Option<&str>, MigrationError> {
+        if self.compute_digest() != self.provenance.digest {
+            return Err(MigrationError::ReportError(
+                "digest mismatch; the report was modified after it was written".to_string(),
+            ));
+        }
+
+        let Some(signature) = &self.signature else {
+            return Ok(None);
+        };
+        if signature.algorithm != SIGNATURE_ALGORITHM {
+            return Err(MigrationError::ReportError(format!(
+                "unsupported signature algorithm {}",
+                signature.algorithm
+            )));
+        }
+
+        let public_key: [u8; 32] = decode_base58(&signature.public_key)?;
+        let value: [u8; 64] = decode_base58(&signature.value)?;
+        VerifyingKey::from_bytes(&public_key)
+            .and_then(|key| {
+                key.verify(
+                    self.provenance.digest.as_bytes(),
+                    &Signature::from_bytes(&value),
+                )
+            })
+            .map_err(|_| MigrationError::ReportError("invalid signature".to_string()))?;
+
+        Ok(Some(&signature.public_key))
+    }
+
+    fn compute_digest(&self) -> String {
+        let mut unsigned = self.clone();
+        unsigned.provenance.digest.clear();
+        unsigned.signature = None;
+        let bytes = serde_json::to_vec(&unsigned).expect("reports always serialize");
+        sha256_hex(&bytes)
+    }
+}
+
+/// Report of a `--recursive` run: each project's own report, or why it
+/// failed, with what the projects have in common.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+#[schemars(
+    title = "soon-migrate roll-up",
+    extend("$id" = "https://github.com/akshatcoder-hash/soon-migrate/blob/main/schema/rollup.schema.json")
+)]
+pub struct RollUp {
+    /// `REPORT_SCHEMA_VERSION`, which covers the roll-up and the reports in
+    /// it alike.
+    pub schema_version: u32,
+    pub tool: String,
+    pub version: String,
+    pub projects: Vec<ProjectReport>,
+    pub totals: Totals,
+    /// What keeps projects from deploying, most widespread first.
+    pub blockers: Vec<Blocker>,
+}
+
+/// One project of a roll-up, by its path under the root.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectReport {
+    pub path: String,
+    /// 0 to 100; see `readiness`.
+    pub readiness: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub report: Option<Report>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Totals {
+    pub projects: usize,
+    pub failed: usize,
+    pub changes: usize,
+    pub warnings: usize,
+}
+
+/// A program missing on SOON, or a kind of warning, and the projects it
+/// affects.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct Blocker {
+    pub blocker: String,
+    pub projects: Vec<String>,
+}
+
+/// How close a migrated project is to deploying on SOON, from 0 to 100:
+/// each program absent from SOON costs 20, each warning and each program
+/// not verified on SOON 5.
+pub fn readiness(report: &Report) -> u32 {
+    let absent = report
+        .compatibility
+        .iter()
+        .filter(|finding| finding.availability == Availability::Absent)
+        .count();
+    let unverified = report
+        .compatibility
+        .iter()
+        .filter(|finding| finding.availability == Availability::Unverified)
+        .count();
+    let penalty = 20 * absent + 5 * (unverified + report.warnings.len());
+    100u32.saturating_sub(penalty.min(100) as u32)
+}
+
+impl RollUp {
+    /// Rolls up each project's report, or the error it failed with.
+    pub fn new(projects: Vec<(String, Result<Report, String>)>) -> Self {
+        let mut totals = Totals {
+            projects: projects.len(),
+            ..Totals::default()
+        };
+        let mut blockers: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        let projects = projects
+            .into_iter()
+            .map(|(path, outcome)| match outcome {
+                Ok(report) => {
+                    totals.changes += report.changes.len();
+                    totals.warnings += report.warnings.len();
+                    let absent = report
+                        .compatibility
+                        .iter()
+                        .filter(|finding| finding.availability == Availability::Absent)
+                        .map(|finding| format!("{} is not on SOON", finding.program));
+                    let warned = report
+                        .warnings
+                        .iter()
+                        .map(|warning| format!("warning: {}", warning_kind(warning)));
+                    for blocker in absent.chain(warned) {
+                        let affected = blockers.entry(blocker).or_default();
+                        if !affected.contains(&path) {
+                            affected.push(path.clone());
+                        }
+                    }
+                    ProjectReport {
+                        readiness: readiness(&report),
+                        path,
+                        report: Some(report),
+                        error: None,
+                    }
+                }
+                Err(error) => {
+                    totals.failed += 1;
+                    ProjectReport {
+                        path,
+                        readiness: 0,
+                        report: None,
+                        error: Some(error),
+                    }
+                }
+            })
+            .collect();
+
+        let mut blockers: Vec<Blocker> = blockers
+            .into_iter()
+            .map(|(blocker, projects)| Blocker { blocker, projects })
+            .collect();
+        // Stable, so ties stay in name order
+        blockers.sort_by_key(|blocker| std::cmp::Reverse(blocker.projects.len()));
+
+        RollUp {
+            schema_version: REPORT_SCHEMA_VERSION,
+            tool: env!("CARGO_PKG_NAME").to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            projects,
+            totals,
+            blockers,
+        }
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), MigrationError> {
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| MigrationError::ReportError(e.to_string()))?;
+        fs::write(path, content + "\n")
+            .map_err(|e| MigrationError::ReportError(format!("{}: {}", path, e)))
+    }
+}
+
+/// The snake_case name a warning's kind has in reports.
+fn warning_kind(warning: &Warning) -> String {
+    serde_json::to_value(warning.kind)
+        .ok()
+        .and_then(|kind| kind.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+fn hash_file(path: &Path) -> Result<String, MigrationError> {
+    let content = fs::read(path)
+        .map_err(|e| MigrationError::ReportError(format!("{}: {}", path.display(), e)))?;
+    Ok(sha256_hex(&content))
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Loads a Solana keypair file (a JSON array of 64 bytes) for signing reports.
+pub fn load_keypair(path: &str) -> Result<SigningKey, MigrationError> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| MigrationError::ReportError(format!("{}: {}", path, e)))?;
+    let bytes: Vec<u8> = serde_json::from_str(&content)
+        .map_err(|e| MigrationError::ReportError(format!("{}: {}", path, e)))?;
+    let bytes: [u8; 64] = bytes.try_into().map_err(|_| {
+        MigrationError::ReportError(format!("{}: expected a 64-byte keypair", path))
+    })?;
+
+    SigningKey::from_keypair_bytes(&bytes)
+        .map_err(|_| MigrationError::ReportError(format!("{}: not a valid keypair", path)))
+}
+
+fn decode_base58<const N: usize>(value: &str) -> Result<[u8; N], MigrationError> {
+    bs58::decode(value)
+        .into_vec()
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| MigrationError::ReportError(format!("malformed signature field {}", value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::result::ChangeKind;
+
+    fn sample_report() -> Report {
+        let mut result = MigrationResult::new(SoonNetwork::Devnet, false);
+        result.changes.push(Change {
+            file: "Anchor.toml".to_string(),
+            kind: ChangeKind::ClusterUpdated,
+            detail: "provider.cluster: Localnet -> https://rpc.devnet.soo.network/rpc".to_string(),
+        });
+        let provenance = Provenance {
+            inputs: BTreeMap::from([("Anchor.toml".to_string(), "ab".repeat(32))]),
+            ..Provenance::default()
+        };
+        Report::new(&result, provenance)
+    }
+
+    #[test]
+    fn test_report_is_reproducible_and_tamper_evident() {
+        let report = sample_report();
+        assert_eq!(report, sample_report());
+        assert_eq!(report.verify().unwrap(), None);
+
+        let mut tampered = report.clone();
+        tampered.changes[0].detail = "provider.cluster: unchanged".to_string();
+        assert!(tampered.verify().is_err());
+    }
+
+    #[test]
+    fn test_signed_report_round_trip() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let keypair_path = dir.path().join("id.json");
+        let key = SigningKey::from_bytes(&[7; 32]);
+        fs::write(
+            &keypair_path,
+            serde_json::to_string(&key.to_keypair_bytes().to_vec()).unwrap(),
+        )
+        .unwrap();
+        let report_path = dir.path().join("report.json");
+        let report_path = report_path.to_str().unwrap();
+
+        let mut report = sample_report();
+        report.sign(&load_keypair(keypair_path.to_str().unwrap()).unwrap());
+        report.save(report_path).unwrap();
+
+        let loaded = Report::load(report_path).unwrap();
+        let signer = bs58::encode(key.verifying_key().to_bytes()).into_string();
+        assert_eq!(loaded.verify().unwrap(), Some(signer.as_str()));
+
+        let mut forged = loaded.clone();
+        forged.signature.as_mut().unwrap().public_key =
+            bs58::encode(SigningKey::from_bytes(&[8; 32]).verifying_key().to_bytes()).into_string();
+        assert!(forged.verify().is_err());
+    }
+
+    #[test]
+    fn test_roll_up() {
+        let mut warned = sample_report();
+        warned.warnings.push(Warning::new(
+            crate::result::WarningKind::AnchorVersion,
+            "Anchor 0.28 is older than validated",
+        ));
+        let projects = vec![
+            ("a".to_string(), Ok(warned.clone())),
+            ("b".to_string(), Ok(warned)),
+            ("c".to_string(), Ok(sample_report())),
+            ("d".to_string(), Err("no Anchor.toml".to_string())),
+        ];
+
+        let roll_up = RollUp::new(projects);
+
+        assert_eq!(
+            roll_up.totals,
+            Totals {
+                projects: 4,
+                failed: 1,
+                changes: 3,
+                warnings: 2,
+            }
+        );
+        let scores: Vec<u32> = roll_up.projects.iter().map(|p| p.readiness).collect();
+        assert_eq!(scores, vec![95, 95, 100, 0]);
+        assert_eq!(
+            roll_up.blockers,
+            vec![Blocker {
+                blocker: "warning: anchor_version".to_string(),
+                projects: vec!["a".to_string(), "b".to_string()],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_roll_up_schema_published() {
+        assert_eq!(
+            roll_up_schema(),
+            include_str!("../schema/rollup.schema.json"),
+            "schema/rollup.schema.json is out of date; regenerate it with `cargo run -- schema --roll-up`"
+        );
+
+        let schema: serde_json::Value = serde_json::from_str(&roll_up_schema()).unwrap();
+        let roll_up = RollUp::new(vec![
+            ("a".to_string(), Ok(sample_report())),
+            ("b".to_string(), Err("no Anchor.toml".to_string())),
+        ]);
+        let serialized = serde_json::to_value(&roll_up).unwrap();
+        for field in serialized.as_object().unwrap().keys() {
+            assert!(schema["properties"].get(field).is_some(), "{}", field);
+        }
+        let project = &schema["$defs"]["ProjectReport"]["properties"];
+        for field in ["path", "readiness", "report", "error"] {
+            assert!(project.get(field).is_some(), "{}", field);
+        }
+    }
+
+    #[test]
+    fn test_schema_published() {
+        assert_eq!(
+            report_schema(),
+            include_str!("../schema/report.schema.json"),
+            "schema/report.schema.json is out of date; regenerate it with `cargo run -- schema`"
+        );
+
+        let schema: serde_json::Value = serde_json::from_str(&report_schema()).unwrap();
+        let mut report = sample_report();
+        report.sign(&SigningKey::from_bytes(&[7; 32]));
+        let serialized = serde_json::to_value(&report).unwrap();
+        for field in serialized.as_object().unwrap().keys() {
+            assert!(schema["properties"].get(field).is_some(), "{}", field);
+        }
+    }
+}
@@ -0,0 +1,326 @@
+use crate::compat::{oracle_redundancy, Availability, CompatFinding};
+use crate::endpoints::SoonNetwork;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Outcome of a migration run: what was changed (or would be, in a dry run)
+/// and anything the user should look at.
+#[derive(Debug)]
+pub struct MigrationResult {
+    pub network: SoonNetwork,
+    pub dry_run: bool,
+    pub changes: Vec<Change>,
+    pub warnings: Vec<Warning>,
+    /// SPL and Metaplex programs the project uses, and their status on SOON.
+    pub compatibility: Vec<CompatFinding>,
+    /// How to deploy each native program, which has no Anchor.toml to migrate.
+    pub deployments: Vec<Deployment>,
+}
+
+/// A single edit made to a project file.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    pub file: String,
+    pub kind: ChangeKind,
+    pub detail: String,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    ClusterUpdated,
+    ProgramsMoved,
+    ProgramsCopied,
+    ProgramIdRemapped,
+    DeclareIdSynced,
+    ToolchainUpdated,
+    EndpointRewritten,
+    EndpointInjected,
+    EnvExampleUpdated,
+}
+
+/// A native program and the command that deploys it to SOON once built.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Deployment {
+    pub program: String,
+    /// The program's Cargo.toml, relative to the project root.
+    pub manifest: String,
+    pub command: String,
+}
+
+/// Something the user should look at before deploying. `recommendation`,
+/// when present, says what to do about it.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    pub kind: WarningKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recommendation: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WarningKind {
+    CustomCluster,
+    ProgramIdConflict,
+    UnknownProgram,
+    AnchorVersion,
+    SolanaVersion,
+    LocalValidator,
+    UnresolvedEndpoint,
+    DependencyVersion,
+    LookupTable,
+    SlotTiming,
+    RentAssumption,
+    ProgramSize,
+    ClientLibrary,
+    WalletBalance,
+}
+
+impl ChangeKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChangeKind::ClusterUpdated => "cluster_updated",
+            ChangeKind::ProgramsMoved => "programs_moved",
+            ChangeKind::ProgramsCopied => "programs_copied",
+            ChangeKind::ProgramIdRemapped => "program_id_remapped",
+            ChangeKind::DeclareIdSynced => "declare_id_synced",
+            ChangeKind::ToolchainUpdated => "toolchain_updated",
+            ChangeKind::EndpointRewritten => "endpoint_rewritten",
+            ChangeKind::EndpointInjected => "endpoint_injected",
+            ChangeKind::EnvExampleUpdated => "env_example_updated",
+        }
+    }
+}
+
+impl Warning {
+    pub fn new(kind: WarningKind, message: impl Into<String>) -> Self {
+        Warning {
+            kind,
+            file: None,
+            message: message.into(),
+            recommendation: None,
+        }
+    }
+
+    pub fn in_file(mut self, file: impl Into<String>) -> Self {
+        self.file = Some(file.into());
+        self
+    }
+
+    pub fn recommend(mut self, recommendation: impl Into<String>) -> Self {
+        self.recommendation = Some(recommendation.into());
+        self
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.file {
+            Some(file) => write!(f, "{}: {}", file, self.message),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+/// Follow-up action for the user, with the change or warning that prompted it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NextStep {
+    pub action: String,
+    pub evidence: String,
+}
+
+impl MigrationResult {
+    pub fn new(network: SoonNetwork, dry_run: bool) -> Self {
+        MigrationResult {
+            network,
+            dry_run,
+            changes: Vec::new(),
+            warnings: Vec::new(),
+            compatibility: Vec::new(),
+            deployments: Vec::new(),
+        }
+    }
+
+    /// Builds the next steps from what this run actually touched, instead of
+    /// a fixed checklist.
+    pub fn next_steps(&self) -> Vec<NextStep> {
+        let mut steps = Vec::new();
+
+        if self.dry_run && !self.changes.is_empty() {
+            steps.push(NextStep {
+                action: "Review the changes above and re-run without --dry-run to apply them."
+                    .to_string(),
+                evidence: format!("{} pending change(s)", self.changes.len()),
+            });
+        }
+
+        for change in &self.changes {
+            match change.kind {
+                ChangeKind::ClusterUpdated => {
+                    steps.push(NextStep {
+                        action: format!(
+                            "Fund the provider wallet on SOON {} before deploying.",
+                            self.network
+                        ),
+                        evidence: evidence(change),
+                    });
+                    steps.push(NextStep {
+                        action: format!(
+                            "Run your tests against SOON {} (anchor test --skip-local-validator).",
+                            self.network
+                        ),
+                        evidence: evidence(change),
+                    });
+                }
+                ChangeKind::ProgramsMoved | ChangeKind::ProgramsCopied => {
+                    steps.push(NextStep {
+                        action: format!(
+                            "Build and deploy your programs to SOON {} (anchor build && anchor deploy).",
+                            self.network
+                        ),
+                        evidence: evidence(change),
+                    });
+                }
+                ChangeKind::ProgramIdRemapped => {
+                    steps.push(NextStep {
+                        action: "Deploy with the keypair for the new program ID (target/deploy/<program>-keypair.json)."
+                            .to_string(),
+                        evidence: evidence(change),
+                    });
+                }
+                ChangeKind::ToolchainUpdated => {
+                    steps.push(NextStep {
+                        action: "Install the pinned toolchain (avm use, solana-install init) before building."
+                            .to_string(),
+                        evidence: evidence(change),
+                    });
+                }
+                ChangeKind::EndpointRewritten => {
+                    let action = format!(
+                        "Check that {} still targets the right SOON network.",
+                        change.file
+                    );
+                    // One step per file, however many lines changed in it
+                    if !steps.iter().any(|step| step.action == action) {
+                        steps.push(NextStep {
+                            action,
+                            evidence: evidence(change),
+                        });
+                    }
+                }
+                ChangeKind::EndpointInjected => {
+                    let action = format!(
+                        "Set the SOON RPC variables wherever {} runs, or rely on the fallback URL.",
+                        change.file
+                    );
+                    if !steps.iter().any(|step| step.action == action) {
+                        steps.push(NextStep {
+                            action,
+                            evidence: evidence(change),
+                        });
+                    }
+                }
+                ChangeKind::EnvExampleUpdated => {
+                    let action = format!("Copy the new entries in {} into your .env.", change.file);
+                    if !steps.iter().any(|step| step.action == action) {
+                        steps.push(NextStep {
+                            action,
+                            evidence: evidence(change),
+                        });
+                    }
+                }
+                ChangeKind::DeclareIdSynced => {
+                    let build = if change.file.ends_with(".py") {
+                        "seahorse build"
+                    } else {
+                        "anchor build"
+                    };
+                    steps.push(NextStep {
+                        action: format!("Rebuild with {} so the program embeds its new ID.", build),
+                        evidence: evidence(change),
+                    });
+                }
+            }
+        }
+
+        for deployment in &self.deployments {
+            steps.push(NextStep {
+                action: format!(
+                    "Build {} with cargo build-sbf, then deploy it to SOON {}: {}",
+                    deployment.program, self.network, deployment.command
+                ),
+                evidence: format!("native program in {}", deployment.manifest),
+            });
+        }
+
+        for warning in &self.warnings {
+            steps.push(NextStep {
+                action: warning
+                    .recommendation
+                    .clone()
+                    .unwrap_or_else(|| "Resolve the warning before deploying.".to_string()),
+                evidence: warning.to_string(),
+            });
+        }
+
+        // Oracles used together get one step for the pattern, where the first is
+        let redundancy = oracle_redundancy(&self.compatibility);
+        let mut consolidated = false;
+        for finding in &self.compatibility {
+            if let Some(redundancy) = &redundancy {
+                if finding.apro.is_some() && redundancy.programs.contains(&finding.program) {
+                    if !consolidated {
+                        steps.push(NextStep {
+                            action: redundancy.advice.clone(),
+                            evidence: format!("oracles {}", redundancy.programs.join(", ")),
+                        });
+                        consolidated = true;
+                    }
+                    continue;
+                }
+            }
+            let action = match (finding.availability, &finding.soon_program_id) {
+                (Availability::Available, _) => continue,
+                (Availability::Moved, Some(id)) => format!(
+                    "Replace {} with {}, the address of {} on SOON.",
+                    finding.program_id.as_deref().unwrap_or(&finding.program),
+                    id,
+                    finding.program
+                ),
+                (Availability::Absent, _) => format!(
+                    "Find a replacement for {}, which is not deployed on SOON.",
+                    finding.program
+                ),
+                _ => match &finding.apro {
+                    Some(apro) => format!("Move {} to APRO: {}", finding.program, apro),
+                    None => format!(
+                        "Confirm {} is deployed on SOON {} before relying on it.",
+                        finding.describe(),
+                        self.network
+                    ),
+                },
+            };
+            steps.push(NextStep {
+                action,
+                evidence: format!("used at {}", finding.evidence.join(", ")),
+            });
+        }
+
+        if self.changes.is_empty() && self.warnings.is_empty() && self.deployments.is_empty() {
+            steps.push(NextStep {
+                action: "Nothing to migrate; deploy to SOON when ready.".to_string(),
+                evidence: "no changes were needed".to_string(),
+            });
+        }
+
+        steps
+    }
+}
+
+fn evidence(change: &Change) -> String {
+    format!("{}: {}", change.file, change.detail)
+}
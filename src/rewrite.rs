@@ -0,0 +1,670 @@
+use crate::endpoints::{Endpoints, SoonNetwork};
+use crate::ignore::IgnoreRules;
+use crate::plan::{Edit, EditOp};
+use crate::result::{Change, ChangeKind, MigrationResult, Warning, WarningKind};
+use crate::workspace::find_files;
+use std::fs;
+use std::path::Path;
+
+/// Public Solana RPC hosts and the SOON network each one maps to.
+const SOLANA_RPC_HOSTS: &[(&str, SoonNetwork)] = &[
+    ("api.devnet.solana.com", SoonNetwork::Devnet),
+    ("api.testnet.solana.com", SoonNetwork::Testnet),
+    ("api.mainnet-beta.solana.com", SoonNetwork::Mainnet),
+];
+
+/// Flags taking a cluster, and the command that must appear on the line for
+/// the flag to count (`-u` alone is far too common to rewrite blindly).
+const CLUSTER_FLAGS: &[(&str, &str)] = &[
+    ("--provider.cluster", "anchor"),
+    ("--url", "solana"),
+    ("-u", "solana"),
+];
+
+/// Public Solana RPC URL of the cluster that maps to `network`.
+pub fn solana_url(network: SoonNetwork) -> String {
+    let (host, _) = SOLANA_RPC_HOSTS
+        .iter()
+        .find(|(_, soon)| *soon == network)
+        .expect("every SOON network has a Solana counterpart");
+    format!("https://{}", host)
+}
+
+/// SOON network for a Solana cluster moniker. Localnet is left alone, since
+/// it points at a local validator rather than a public cluster.
+fn moniker_network(moniker: &str) -> Option<SoonNetwork> {
+    match moniker {
+        "devnet" | "d" => Some(SoonNetwork::Devnet),
+        "testnet" | "t" => Some(SoonNetwork::Testnet),
+        "mainnet-beta" | "mainnet" | "m" => Some(SoonNetwork::Mainnet),
+        _ => None,
+    }
+}
+
+/// Replacements that point one line of a script or config file at SOON:
+/// public Solana RPC URLs, and cluster monikers passed to `solana --url`/`-u`
+/// or `anchor --provider.cluster`. Each pair replaces the first occurrence of
+/// `old` on the line with `new`.
+pub fn rewrite_line(line: &str, endpoints: &Endpoints) -> Vec<(String, String)> {
+    let mut replacements = Vec::new();
+
+    for scheme in ["https://", "http://"] {
+        for (host, network) in SOLANA_RPC_HOSTS {
+            let url = format!("{}{}", scheme, host);
+            for _ in line.matches(url.as_str()) {
+                replacements.push((url.clone(), endpoints.url(*network).to_string()));
+            }
+        }
+    }
+
+    for (old, value) in cluster_arguments(line) {
+        if let Some(network) = moniker_network(value) {
+            let new = old.replacen(value, endpoints.url(network), 1);
+            replacements.push((old, new));
+        }
+    }
+
+    replacements
+}
+
+/// Cluster flags on a line, as the text to replace and the unquoted value.
+fn cluster_arguments(line: &str) -> Vec<(String, &str)> {
+    let mut arguments = Vec::new();
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+
+    for (index, token) in tokens.iter().enumerate() {
+        for (flag, command) in CLUSTER_FLAGS {
+            if !tokens.contains(command) {
+                continue;
+            }
+
+            let (old, value) = if token == flag {
+                match tokens.get(index + 1) {
+                    Some(value) => (format!("{} {}", flag, value), *value),
+                    None => continue,
+                }
+            } else if let Some(value) = token
+                .strip_prefix(flag)
+                .and_then(|rest| rest.strip_prefix('='))
+            {
+                (token.to_string(), value)
+            } else {
+                continue;
+            };
+
+            // Only keep it when the text is exactly as assumed above
+            if line.contains(&old) {
+                arguments.push((old, value.trim_matches(|c| c == '"' || c == '\'')));
+            }
+        }
+    }
+
+    arguments
+}
+
+/// Edits, changes and warnings that point every line of `file` at SOON.
+/// Clusters taken from variables cannot be rewritten, so those lines get a
+/// warning instead. Files that cannot be read are skipped.
+pub fn rewrite_file(
+    project: &Path,
+    file: &str,
+    endpoints: &Endpoints,
+    edits: &mut Vec<Edit>,
+    result: &mut MigrationResult,
+) {
+    let Ok(content) = fs::read_to_string(project.join(file)) else {
+        return;
+    };
+
+    for (index, line) in content.lines().enumerate() {
+        for (old, new) in rewrite_line(line, endpoints) {
+            result.changes.push(Change {
+                file: file.to_string(),
+                kind: ChangeKind::EndpointRewritten,
+                detail: format!("line {}: {} -> {}", index + 1, old, new),
+            });
+            edits.push(Edit::replace(file, index + 1, &old, &new));
+        }
+
+        for (old, value) in cluster_arguments(line) {
+            if value.contains('$') || value.contains("{{") {
+                result.warnings.push(
+                    Warning::new(
+                        WarningKind::UnresolvedEndpoint,
+                        format!("line {}: cluster comes from {} ({})", index + 1, value, old),
+                    )
+                    .in_file(file)
+                    .recommend(format!(
+                        "Make sure {} resolves to a SOON RPC endpoint wherever {} runs.",
+                        value, file
+                    )),
+                );
+            }
+        }
+    }
+}
+
+/// GitHub Actions workflow files, relative to the project root and sorted.
+pub fn workflow_files(project: &Path) -> Vec<String> {
+    let rules = IgnoreRules::load(project);
+    let Ok(entries) = fs::read_dir(project.join(".github").join("workflows")) else {
+        return Vec::new();
+    };
+
+    let mut files: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_file())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| name.ends_with(".yml") || name.ends_with(".yaml"))
+        .map(|name| format!(".github/workflows/{}", name))
+        .filter(|file| !rules.is_ignored(file))
+        .collect();
+    files.sort();
+    files
+}
+
+/// Warnings for lines of `file` that start or configure a local
+/// `solana-test-validator`, which has no SOON counterpart to rewrite to.
+pub fn local_validator_warnings(project: &Path, file: &str) -> Vec<Warning> {
+    let Ok(content) = fs::read_to_string(project.join(file)) else {
+        return Vec::new();
+    };
+
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| {
+            // Also catches variables such as SOLANA_TEST_VALIDATOR_PORT
+            line.to_ascii_lowercase()
+                .replace('_', "-")
+                .contains("solana-test-validator")
+        })
+        .map(|(index, _)| {
+            Warning::new(
+                WarningKind::LocalValidator,
+                format!("line {} runs or configures solana-test-validator", index + 1),
+            )
+            .in_file(file)
+            .recommend(format!(
+                "Point the services in {} at a SOON RPC endpoint instead of a local Solana validator, or keep it only for local tests.",
+                file
+            ))
+        })
+        .collect()
+}
+
+/// Files, besides `Anchor.toml`, that may hold an RPC URL: source, config
+/// and shell scripts.
+fn endpoint_files(project: &Path) -> Vec<String> {
+    find_files(project, &|name| {
+        name != "Anchor.toml"
+            && (name.starts_with(".env")
+                || [
+                    ".rs", ".ts", ".tsx", ".js", ".mjs", ".cjs", ".json", ".yaml", ".yml", ".toml",
+                    ".sh",
+                ]
+                .iter()
+                .any(|extension| name.ends_with(extension)))
+    })
+}
+
+/// The public Solana endpoint a line refers to, by host (over any scheme,
+/// websockets included) or through `clusterApiUrl(...)`.
+fn solana_endpoint(line: &str) -> Option<&'static str> {
+    SOLANA_RPC_HOSTS
+        .iter()
+        .map(|(host, _)| *host)
+        .find(|host| line.contains(host))
+        .or_else(|| line.contains("clusterApiUrl(").then_some("clusterApiUrl"))
+}
+
+/// Warnings for every line in the project that still refers to a public
+/// Solana endpoint once `edits` are applied, so the ones that cannot be
+/// rewritten safely are at least listed.
+pub fn leftover_endpoint_warnings(project: &Path, edits: &[Edit]) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+
+    for file in endpoint_files(project) {
+        let Ok(content) = fs::read_to_string(project.join(&file)) else {
+            continue;
+        };
+
+        for (index, line) in content.lines().enumerate() {
+            let Some(endpoint) = solana_endpoint(line) else {
+                continue;
+            };
+            let rewritten = edits.iter().any(|edit| {
+                edit.file == file
+                    && matches!(edit.op, EditOp::Replace { line, .. } if line == index + 1)
+            });
+            if rewritten {
+                continue;
+            }
+            warnings.push(
+                Warning::new(
+                    WarningKind::UnresolvedEndpoint,
+                    format!("line {} still points at Solana ({})", index + 1, endpoint),
+                )
+                .in_file(&file)
+                .recommend(format!(
+                    "Point {} at a SOON RPC endpoint by hand, or use --env-endpoints for client code.",
+                    file
+                )),
+            );
+        }
+    }
+
+    warnings
+}
+
+/// Address of the native program that owns every address lookup table.
+const LOOKUP_TABLE_PROGRAM: &str = "AddressLookupTab1e1111111111111111111111111";
+
+/// Warnings for address lookup tables hardcoded in `file`: addresses on lines
+/// that mention a lookup table. Tables are Solana accounts, so each has to be
+/// created again on SOON under a new address.
+pub fn lookup_table_warnings(project: &Path, file: &str) -> Vec<Warning> {
+    let Ok(content) = fs::read_to_string(project.join(file)) else {
+        return Vec::new();
+    };
+
+    let mut warnings = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        if !line.to_ascii_lowercase().contains("lookup") {
+            continue;
+        }
+        let addresses = line
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|word| (32..=44).contains(&word.len()) && *word != LOOKUP_TABLE_PROGRAM)
+            .filter(|word| {
+                bs58::decode(word)
+                    .into_vec()
+                    .is_ok_and(|bytes| bytes.len() == 32)
+            });
+        for address in addresses {
+            warnings.push(
+                Warning::new(
+                    WarningKind::LookupTable,
+                    format!(
+                        "line {} uses lookup table {}, which only exists on Solana",
+                        index + 1,
+                        address
+                    ),
+                )
+                .in_file(file)
+                .recommend(format!(
+                    "Create the lookup table again on SOON with the same addresses, then replace {} in {}.",
+                    address, file
+                )),
+            );
+        }
+    }
+    warnings
+}
+
+/// Dockerfiles and Compose files in the project root and `docker/`, sorted.
+pub fn docker_files(project: &Path) -> Vec<String> {
+    let rules = IgnoreRules::load(project);
+    let mut files = Vec::new();
+
+    for dir in ["", "docker"] {
+        let Ok(entries) = fs::read_dir(project.join(dir)) else {
+            continue;
+        };
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let Ok(name) = entry.file_name().into_string() else {
+                continue;
+            };
+            let is_docker = name == "Dockerfile"
+                || name.starts_with("Dockerfile.")
+                || name.ends_with(".Dockerfile")
+                || ["docker-compose", "compose"].iter().any(|stem| {
+                    name.strip_prefix(stem)
+                        .is_some_and(|rest| rest.ends_with(".yml") || rest.ends_with(".yaml"))
+                });
+            let file = if dir.is_empty() {
+                name
+            } else {
+                format!("{}/{}", dir, name)
+            };
+            if is_docker && entry.path().is_file() && !rules.is_ignored(&file) {
+                files.push(file);
+            }
+        }
+    }
+
+    files.sort();
+    files
+}
+
+/// Makefiles, justfiles and shell scripts anywhere in the project, sorted.
+pub fn build_files(project: &Path) -> Vec<String> {
+    find_files(project, &|name| {
+        matches!(
+            name,
+            "Makefile" | "makefile" | "GNUmakefile" | "justfile" | "Justfile" | ".justfile"
+        ) || name.ends_with(".mk")
+            || name.ends_with(".just")
+            || name.ends_with(".sh")
+    })
+}
+
+/// Where the environment variables introduced by `inject_env_endpoints` are listed.
+pub const ENV_EXAMPLE_FILE: &str = ".env.example";
+
+/// Name of the environment variable holding the RPC URL for `network`.
+pub fn endpoint_env_var(network: SoonNetwork) -> String {
+    format!("SOON_{}_RPC_URL", network.as_str().to_ascii_uppercase())
+}
+
+/// TypeScript, JavaScript and Rust sources anywhere in the project, sorted.
+pub fn client_files(project: &Path) -> Vec<String> {
+    find_files(project, &|name| {
+        [".ts", ".tsx", ".js", ".mjs", ".cjs", ".rs"]
+            .iter()
+            .any(|extension| name.ends_with(extension))
+    })
+}
+
+/// Replacements that swap the RPC URL literals on one line of client code,
+/// Solana's or SOON's, for a lookup of the network's environment variable
+/// that falls back to the SOON URL. Each comes with the network it reads.
+pub fn inject_line(
+    line: &str,
+    rust: bool,
+    endpoints: &Endpoints,
+) -> Vec<(String, String, SoonNetwork)> {
+    let mut urls: Vec<(String, SoonNetwork)> = Vec::new();
+    for scheme in ["https://", "http://"] {
+        for (host, network) in SOLANA_RPC_HOSTS {
+            urls.push((format!("{}{}", scheme, host), *network));
+            urls.push((format!("{}{}/", scheme, host), *network));
+        }
+    }
+    for network in [
+        SoonNetwork::Devnet,
+        SoonNetwork::Testnet,
+        SoonNetwork::Mainnet,
+    ] {
+        urls.push((endpoints.url(network).to_string(), network));
+    }
+
+    // `const` and `static` initialisers cannot call functions in Rust
+    let trimmed = line.trim_start();
+    if rust
+        && ["const ", "static ", "pub const ", "pub static "]
+            .iter()
+            .any(|prefix| trimmed.starts_with(prefix))
+    {
+        return Vec::new();
+    }
+
+    let quotes: &[char] = if rust { &['"'] } else { &['"', '\'', '`'] };
+    let mut literals: Vec<(String, SoonNetwork)> = Vec::new();
+    for (url, network) in &urls {
+        for quote in quotes {
+            let literal = format!("{}{}{}", quote, url, quote);
+            for _ in line.matches(literal.as_str()) {
+                literals.push((literal.clone(), *network));
+            }
+        }
+    }
+    if !rust {
+        for quote in ['"', '\''] {
+            for moniker in ["devnet", "testnet", "mainnet-beta"] {
+                let call = format!("clusterApiUrl({}{}{})", quote, moniker, quote);
+                if let Some(network) = moniker_network(moniker).filter(|_| line.contains(&call)) {
+                    literals.push((call, network));
+                }
+            }
+        }
+    }
+
+    literals
+        .into_iter()
+        // Lines already reading the variable were migrated before
+        .filter(|(_, network)| !line.contains(&endpoint_env_var(*network)))
+        .map(|(old, network)| {
+            let var = endpoint_env_var(network);
+            let url = endpoints.url(network);
+            let new = if rust {
+                format!("option_env!(\"{}\").unwrap_or(\"{}\")", var, url)
+            } else {
+                format!("process.env.{} ?? \"{}\"", var, url)
+            };
+            (old, new, network)
+        })
+        .collect()
+}
+
+/// Points client code at SOON through environment variables rather than
+/// hardcoded URLs, and lists every variable it reads in `.env.example`.
+pub fn inject_env_endpoints(
+    project: &Path,
+    endpoints: &Endpoints,
+    edits: &mut Vec<Edit>,
+    result: &mut MigrationResult,
+) {
+    let mut networks: Vec<SoonNetwork> = Vec::new();
+
+    for file in client_files(project) {
+        let Ok(content) = fs::read_to_string(project.join(&file)) else {
+            continue;
+        };
+        let rust = file.ends_with(".rs");
+
+        for (index, line) in content.lines().enumerate() {
+            for (old, new, network) in inject_line(line, rust, endpoints) {
+                result.changes.push(Change {
+                    file: file.clone(),
+                    kind: ChangeKind::EndpointInjected,
+                    detail: format!("line {}: {} -> {}", index + 1, old, new),
+                });
+                edits.push(Edit::replace(&file, index + 1, &old, &new));
+                if !networks.contains(&network) {
+                    networks.push(network);
+                }
+            }
+        }
+    }
+
+    let existing = fs::read_to_string(project.join(ENV_EXAMPLE_FILE)).unwrap_or_default();
+    for network in networks {
+        let var = endpoint_env_var(network);
+        let listed = existing
+            .lines()
+            .any(|line| line.trim_start().starts_with(&format!("{}=", var)));
+        if listed {
+            continue;
+        }
+
+        let entry = format!("{}={}", var, endpoints.url(network));
+        result.changes.push(Change {
+            file: ENV_EXAMPLE_FILE.to_string(),
+            kind: ChangeKind::EnvExampleUpdated,
+            detail: format!("added {}", entry),
+        });
+        edits.push(Edit::append(ENV_EXAMPLE_FILE, &entry));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endpoints::{DEFAULT_DEVNET_RPC, DEFAULT_MAINNET_RPC};
+
+    #[test]
+    fn test_rewrite_line() {
+        let endpoints = Endpoints::default();
+
+        assert_eq!(
+            rewrite_line(
+                "      SOLANA_RPC_URL: https://api.mainnet-beta.solana.com",
+                &endpoints
+            ),
+            vec![(
+                "https://api.mainnet-beta.solana.com".to_string(),
+                DEFAULT_MAINNET_RPC.to_string()
+            )]
+        );
+        assert_eq!(
+            rewrite_line("run: solana config set --url devnet", &endpoints),
+            vec![(
+                "--url devnet".to_string(),
+                format!("--url {}", DEFAULT_DEVNET_RPC)
+            )]
+        );
+        assert_eq!(
+            rewrite_line("anchor deploy --provider.cluster=\"devnet\"", &endpoints),
+            vec![(
+                "--provider.cluster=\"devnet\"".to_string(),
+                format!("--provider.cluster=\"{}\"", DEFAULT_DEVNET_RPC)
+            )]
+        );
+
+        // Localnet, unrelated commands and SOON URLs stay as they are
+        assert!(rewrite_line("solana config set --url localhost", &endpoints).is_empty());
+        assert!(rewrite_line("set -u d", &endpoints).is_empty());
+        assert!(rewrite_line(DEFAULT_DEVNET_RPC, &endpoints).is_empty());
+    }
+
+    #[test]
+    fn test_build_files_found_recursively() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("scripts")).unwrap();
+        fs::create_dir_all(dir.path().join("node_modules/pkg")).unwrap();
+        for file in [
+            "Makefile",
+            "scripts/deploy.mk",
+            "scripts/justfile",
+            "scripts/deploy.sh",
+            "node_modules/pkg/Makefile",
+            "README.md",
+        ] {
+            fs::write(dir.path().join(file), "").unwrap();
+        }
+
+        assert_eq!(
+            build_files(dir.path()),
+            vec![
+                "Makefile",
+                "scripts/deploy.mk",
+                "scripts/deploy.sh",
+                "scripts/justfile"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_leftover_endpoint_warnings() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::write(
+            dir.path().join("config.yaml"),
+            "rpc: https://api.devnet.solana.com\nws: wss://api.mainnet-beta.solana.com\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("client.ts"),
+            "const c = new Connection(clusterApiUrl(\"devnet\"));\n",
+        )
+        .unwrap();
+        let edits = vec![Edit::replace(
+            "config.yaml",
+            1,
+            "https://api.devnet.solana.com",
+            "https://rpc.devnet.soo.network/rpc",
+        )];
+
+        let warnings = leftover_endpoint_warnings(dir.path(), &edits);
+
+        let found: Vec<String> = warnings.iter().map(|w| w.to_string()).collect();
+        assert_eq!(
+            found,
+            vec![
+                "client.ts: line 1 still points at Solana (clusterApiUrl)",
+                "config.yaml: line 2 still points at Solana (api.mainnet-beta.solana.com)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lookup_table_warnings() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::write(
+            dir.path().join("client.ts"),
+            "const owner = new PublicKey(\"Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS\");\n\
+             const lookupTable = new PublicKey(\"4QJZbqvjRhWn1Mz8vRpn5T6ANbqhmW5HMmqhUH3p8GGW\");\n\
+             const table = await connection.getAddressLookupTable(lookupTable);\n",
+        )
+        .unwrap();
+
+        let warnings = lookup_table_warnings(dir.path(), "client.ts");
+
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].kind, WarningKind::LookupTable);
+        assert!(warnings[0]
+            .message
+            .starts_with("line 2 uses lookup table 4QJZbqvjRhWn1Mz8vRpn5T6ANbqhmW5HMmqhUH3p8GGW"));
+    }
+
+    #[test]
+    fn test_inject_line() {
+        let endpoints = Endpoints::default();
+
+        assert_eq!(
+            inject_line(
+                "const connection = new Connection(\"https://api.devnet.solana.com\");",
+                false,
+                &endpoints
+            ),
+            vec![(
+                "\"https://api.devnet.solana.com\"".to_string(),
+                format!(
+                    "process.env.SOON_DEVNET_RPC_URL ?? \"{}\"",
+                    DEFAULT_DEVNET_RPC
+                ),
+                SoonNetwork::Devnet
+            )]
+        );
+        assert_eq!(
+            inject_line(
+                "let url = clusterApiUrl('mainnet-beta');",
+                false,
+                &endpoints
+            )[0]
+            .1,
+            format!(
+                "process.env.SOON_MAINNET_RPC_URL ?? \"{}\"",
+                DEFAULT_MAINNET_RPC
+            )
+        );
+        assert_eq!(
+            inject_line(
+                &format!(
+                    "    let client = RpcClient::new(\"{}\".to_string());",
+                    DEFAULT_DEVNET_RPC
+                ),
+                true,
+                &endpoints
+            )[0]
+            .1,
+            format!(
+                "option_env!(\"SOON_DEVNET_RPC_URL\").unwrap_or(\"{}\")",
+                DEFAULT_DEVNET_RPC
+            )
+        );
+
+        // Constants and lines that already read the variable stay as they are
+        assert!(inject_line(
+            "const RPC: &str = \"https://api.devnet.solana.com\";",
+            true,
+            &endpoints
+        )
+        .is_empty());
+        let injected = format!(
+            "process.env.SOON_DEVNET_RPC_URL ?? \"{}\"",
+            DEFAULT_DEVNET_RPC
+        );
+        assert!(inject_line(&injected, false, &endpoints).is_empty());
+    }
+}
@@ -0,0 +1,529 @@
+use crate::endpoints::{url_host, Endpoints, SoonNetwork};
+use crate::errors::MigrationError;
+use crate::network::Client;
+use serde_json::{json, Value};
+use std::time::{Duration, Instant};
+
+/// Most accounts `getMultipleAccounts` accepts in one request.
+const MAX_ACCOUNTS_PER_REQUEST: usize = 100;
+
+/// Owner of every feature gate account.
+const FEATURE_PROGRAM: &str = "Feature111111111111111111111111111111111111";
+
+/// Genesis hashes of the Solana clusters, which no SOON endpoint may have.
+const SOLANA_GENESIS_HASHES: &[(&str, &str)] = &[
+    (
+        "mainnet-beta",
+        "5eykt4UsFv8P8NJdTREpY1vzqKqZKvdpKuc147dw2N9d",
+    ),
+    ("devnet", "EtWTRABZaYq6iMfeYKouRu166VU2xqa1wcaWoxPkrZBG"),
+    ("testnet", "4uhcVJyU9pJkvQyS88uRDiswHXSCkY3zQawwpjk2NsNY"),
+];
+
+/// Minimal JSON-RPC client for a Solana-compatible endpoint. Errors only
+/// name the endpoint's host, since RPC URLs often carry API keys.
+pub struct Rpc {
+    url: String,
+    host: String,
+    client: Client,
+}
+
+impl Rpc {
+    pub fn new(url: &str) -> Self {
+        Rpc {
+            url: url.to_string(),
+            host: url_host(url).to_string(),
+            client: Client::default(),
+        }
+    }
+
+    /// Client for SOON `network`'s endpoint, authenticated with its
+    /// `${VAR}` references expanded and its headers attached. Messages show
+    /// the host as configured.
+    pub fn connect(endpoints: &Endpoints, network: SoonNetwork) -> Result<Self, MigrationError> {
+        Ok(Rpc {
+            url: endpoints.resolved_url(network)?,
+            host: url_host(endpoints.url(network)).to_string(),
+            client: Client::default().with_headers(endpoints.resolved_headers(network)?),
+        })
+    }
+
+    /// Host of the endpoint, for messages.
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// Calls `method` and returns its `result`.
+    pub fn call(&self, method: &str, params: Value) -> Result<Value, MigrationError> {
+        self.request(method, params)?.map_err(|message| {
+            MigrationError::RpcError(format!("{} {}: {}", self.host(), method, message))
+        })
+    }
+
+    /// Calls `method`. The outer error is for an endpoint that cannot be
+    /// reached or does not speak JSON-RPC; the inner one is the error the
+    /// node answered with.
+    fn request(
+        &self,
+        method: &str,
+        params: Value,
+    ) -> Result<Result<Value, String>, MigrationError> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        });
+        let fail =
+            |e: String| MigrationError::RpcError(format!("{} {}: {}", self.host(), method, e));
+
+        let mut body = self.client.post_json(&self.url, &request).map_err(fail)?;
+
+        if let Some(error) = body.get("error") {
+            let message = error["message"].as_str().unwrap_or("unknown error");
+            return Ok(Err(message.to_string()));
+        }
+        match body.get_mut("result") {
+            Some(result) => Ok(Ok(result.take())),
+            None => Err(fail("response has no result".to_string())),
+        }
+    }
+
+    /// The node's `solana-core` version and feature set identifier.
+    pub fn version(&self) -> Result<(String, Option<u64>), MigrationError> {
+        let version = self.call("getVersion", json!([]))?;
+        Ok((
+            version["solana-core"]
+                .as_str()
+                .unwrap_or("unknown")
+                .to_string(),
+            version["feature-set"].as_u64(),
+        ))
+    }
+
+    /// Hash of the chain's genesis block, which tells chains apart.
+    pub fn genesis_hash(&self) -> Result<String, MigrationError> {
+        self.call("getGenesisHash", json!([]))?
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| {
+                MigrationError::RpcError(format!("{} getGenesisHash: not a hash", self.host()))
+            })
+    }
+
+    /// Owner and data of the account at `address`, or `None` when there is
+    /// no account.
+    pub fn account_data(&self, address: &str) -> Result<Option<(String, Vec<u8>)>, MigrationError> {
+        let result = self.call("getAccountInfo", json!([address, { "encoding": "base64" }]))?;
+        let account = &result["value"];
+        if account.is_null() {
+            return Ok(None);
+        }
+        let data = account["data"][0]
+            .as_str()
+            .and_then(decode_base64)
+            .ok_or_else(|| {
+                MigrationError::RpcError(format!(
+                    "{} getAccountInfo: unreadable data for {}",
+                    self.host(),
+                    address
+                ))
+            })?;
+        let owner = account["owner"].as_str().unwrap_or_default().to_string();
+        Ok(Some((owner, data)))
+    }
+
+    /// Whether an account exists at each of `addresses`, in order.
+    pub fn accounts_exist(&self, addresses: &[&str]) -> Result<Vec<bool>, MigrationError> {
+        let mut exist = Vec::with_capacity(addresses.len());
+        for chunk in addresses.chunks(MAX_ACCOUNTS_PER_REQUEST) {
+            let result = self.call(
+                "getMultipleAccounts",
+                json!([chunk, { "encoding": "base64", "dataSlice": { "offset": 0, "length": 0 } }]),
+            )?;
+            let accounts = result["value"].as_array().cloned().unwrap_or_default();
+            exist.extend((0..chunk.len()).map(|i| accounts.get(i).is_some_and(|a| !a.is_null())));
+        }
+        Ok(exist)
+    }
+
+    /// Unix time of the latest transaction that touched the account at
+    /// `address`, or `None` when there is none or the node does not know
+    /// when it was.
+    pub fn last_write(&self, address: &str) -> Result<Option<u64>, MigrationError> {
+        let signatures = self.call("getSignaturesForAddress", json!([address, { "limit": 1 }]))?;
+        Ok(signatures[0]["blockTime"].as_u64())
+    }
+
+    /// Whether each of the feature gates at `ids` is active, in order. A
+    /// feature with no account, or one still pending, is not.
+    pub fn features_active(&self, ids: &[&str]) -> Result<Vec<bool>, MigrationError> {
+        let mut active = Vec::with_capacity(ids.len());
+        for chunk in ids.chunks(MAX_ACCOUNTS_PER_REQUEST) {
+            let result = self.call(
+                "getMultipleAccounts",
+                json!([chunk, { "encoding": "base64" }]),
+            )?;
+            let accounts = result["value"].as_array().cloned().unwrap_or_default();
+            active.extend((0..chunk.len()).map(|i| accounts.get(i).is_some_and(is_active_feature)));
+        }
+        Ok(active)
+    }
+}
+
+/// Whether an account returned by `getMultipleAccounts` is an activated
+/// feature gate. Its data is a bincode `Option<u64>` activation slot, so
+/// the first byte is 1 once the feature is active.
+fn is_active_feature(account: &Value) -> bool {
+    account["owner"] == FEATURE_PROGRAM
+        && account["data"][0]
+            .as_str()
+            .and_then(decode_base64)
+            .is_some_and(|data| data.first() == Some(&1))
+}
+
+/// Decodes standard, padded base64 as RPC nodes send account data.
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let value = |c: u8| match c {
+        b'A'..=b'Z' => Some(c - b'A'),
+        b'a'..=b'z' => Some(c - b'a' + 26),
+        b'0'..=b'9' => Some(c - b'0' + 52),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    };
+
+    let text = text.trim_end_matches('=').as_bytes();
+    let mut bytes = Vec::with_capacity(text.len() * 3 / 4);
+    for chunk in text.chunks(4) {
+        let mut group = 0u32;
+        for (i, c) in chunk.iter().enumerate() {
+            group |= (value(*c)? as u32) << (18 - 6 * i);
+        }
+        let len = chunk.len().checked_sub(1).filter(|len| *len > 0)?;
+        bytes.extend(&group.to_be_bytes()[1..1 + len]);
+    }
+    Some(bytes)
+}
+
+/// What `check` found out about an endpoint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Health {
+    /// `ok`, or why the node says it is unhealthy (usually that it is behind).
+    pub status: String,
+    /// Average round trip of the requests made.
+    pub latency: Duration,
+    pub version: String,
+    pub slot: u64,
+}
+
+/// Asks an endpoint for its health, version and slot. Fails only when the
+/// endpoint cannot be reached or answers nonsense; a node that reports
+/// itself unhealthy still counts as reachable.
+pub fn check(rpc: &Rpc) -> Result<Health, MigrationError> {
+    let started = Instant::now();
+    let status = match rpc.request("getHealth", json!([]))? {
+        Ok(status) => status.as_str().unwrap_or("ok").to_string(),
+        Err(reason) => reason,
+    };
+    let (version, _) = rpc.version()?;
+    let slot = rpc
+        .call("getSlot", json!([]))?
+        .as_u64()
+        .ok_or_else(|| MigrationError::RpcError(format!("{} getSlot: not a slot", rpc.host())))?;
+
+    Ok(Health {
+        status,
+        latency: started.elapsed() / 3,
+        version,
+        slot,
+    })
+}
+
+/// Checks that `custom` serves SOON `network`: that its genesis hash is
+/// `expected`, or else the one the public endpoint reports.
+pub fn verify_genesis(
+    network: SoonNetwork,
+    custom: &Rpc,
+    expected: Option<&str>,
+) -> Result<(), MigrationError> {
+    let actual = custom.genesis_hash()?;
+    let expected = match expected {
+        Some(hash) => hash.to_string(),
+        None => Rpc::new(Endpoints::default().url(network))
+            .genesis_hash()
+            .map_err(|e| match e {
+                MigrationError::RpcError(reason) => MigrationError::RpcError(format!(
+                    "{}; pin the genesis hash of SOON {} under [rpc.genesis], or pass --offline to skip the check",
+                    reason, network
+                )),
+                e => e,
+            })?,
+    };
+    check_genesis(network, custom.host(), &actual, &expected)
+}
+
+/// The Solana cluster whose genesis hash is `hash`, with the SOON network
+/// that stands in for it.
+pub fn solana_cluster(hash: &str) -> Option<(&'static str, SoonNetwork)> {
+    SOLANA_GENESIS_HASHES
+        .iter()
+        .find(|(_, known)| *known == hash)
+        .map(|(cluster, _)| (*cluster, SoonNetwork::from_cluster(cluster)))
+}
+
+fn check_genesis(
+    network: SoonNetwork,
+    host: &str,
+    actual: &str,
+    expected: &str,
+) -> Result<(), MigrationError> {
+    if actual == expected {
+        return Ok(());
+    }
+    if let Some((cluster, _)) = solana_cluster(actual) {
+        return Err(MigrationError::WrongChain(format!(
+            "{} serves Solana {}, not SOON {}",
+            host, cluster, network
+        )));
+    }
+    Err(MigrationError::WrongChain(format!(
+        "{} has genesis hash {}, but SOON {} has {}",
+        host, actual, network, expected
+    )))
+}
+
+/// Methods `bench` times: the cheapest call, the one every transaction
+/// needs, and an account read.
+pub const BENCH_METHODS: &[&str] = &["getSlot", "getLatestBlockhash", "getAccountInfo"];
+
+/// Round trips of one method against an endpoint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bench {
+    pub method: &'static str,
+    pub min: Duration,
+    pub median: Duration,
+    pub max: Duration,
+    /// Requests answered per second, one after the other.
+    pub per_second: u64,
+}
+
+/// Calls `method` `requests` times in a row and times each round trip.
+pub fn bench(rpc: &Rpc, method: &'static str, requests: usize) -> Result<Bench, MigrationError> {
+    let params = match method {
+        "getAccountInfo" => json!(["11111111111111111111111111111111", { "encoding": "base64" }]),
+        _ => json!([]),
+    };
+
+    let started = Instant::now();
+    let mut times = Vec::with_capacity(requests);
+    for _ in 0..requests.max(1) {
+        let call = Instant::now();
+        rpc.call(method, params.clone())?;
+        times.push(call.elapsed());
+    }
+    let total = started.elapsed();
+    times.sort();
+
+    Ok(Bench {
+        method,
+        min: times[0],
+        median: times[times.len() / 2],
+        max: times[times.len() - 1],
+        per_second: (times.len() as f64 / total.as_secs_f64()) as u64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "network")]
+    use std::io::{BufRead, BufReader, Read, Write};
+    #[cfg(feature = "network")]
+    use std::net::TcpListener;
+    #[cfg(feature = "network")]
+    use std::sync::mpsc::{self, Receiver};
+    #[cfg(feature = "network")]
+    use std::thread;
+
+    /// Serves one canned JSON-RPC body per request, in order, and passes on
+    /// the head of each request it gets.
+    #[cfg(feature = "network")]
+    fn serve(bodies: Vec<&'static str>) -> (String, Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let (heads, received) = mpsc::channel();
+        thread::spawn(move || {
+            for body in bodies {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut length = 0;
+                let mut head = String::new();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    head.push_str(&line);
+                    if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                        length = value.trim().parse().unwrap();
+                    }
+                    if line == "\r\n" {
+                        break;
+                    }
+                }
+                reader.read_exact(&mut vec![0; length]).unwrap();
+                let _ = heads.send(head);
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                reader.get_mut().write_all(response.as_bytes()).unwrap();
+            }
+        });
+        (url, received)
+    }
+
+    #[test]
+    fn test_check_genesis() {
+        let soon = "4S8bcTx9fUtSzDhyAkfWzrABc4y9B5HU3mDomXVEVbRj";
+        assert!(check_genesis(SoonNetwork::Devnet, "rpc.example", soon, soon).is_ok());
+
+        let solana = check_genesis(
+            SoonNetwork::Devnet,
+            "rpc.example",
+            "EtWTRABZaYq6iMfeYKouRu166VU2xqa1wcaWoxPkrZBG",
+            soon,
+        )
+        .unwrap_err();
+        assert_eq!(
+            solana.to_string(),
+            "RPC endpoint is on the wrong chain: rpc.example serves Solana devnet, not SOON devnet"
+        );
+
+        let other = check_genesis(
+            SoonNetwork::Mainnet,
+            "rpc.example",
+            "8wWLP4YmLwFJT9kVo3Mhjwu8tkc4JyYUaSBV3JBXbDqk",
+            soon,
+        );
+        assert!(matches!(other, Err(MigrationError::WrongChain(_))));
+    }
+
+    #[test]
+    fn test_solana_cluster() {
+        assert_eq!(
+            solana_cluster("5eykt4UsFv8P8NJdTREpY1vzqKqZKvdpKuc147dw2N9d"),
+            Some(("mainnet-beta", SoonNetwork::Mainnet))
+        );
+        assert_eq!(
+            solana_cluster("4uhcVJyU9pJkvQyS88uRDiswHXSCkY3zQawwpjk2NsNY"),
+            Some(("testnet", SoonNetwork::Testnet))
+        );
+        assert_eq!(
+            solana_cluster("4S8bcTx9fUtSzDhyAkfWzrABc4y9B5HU3mDomXVEVbRj"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_is_active_feature() {
+        let account = |owner: &str, data: &str| json!({ "owner": owner, "data": [data, "base64"] });
+
+        assert!(is_active_feature(&account(FEATURE_PROGRAM, "AQAAAAAAAAAA")));
+        assert!(!is_active_feature(&account(
+            FEATURE_PROGRAM,
+            "AAAAAAAAAAAA"
+        )));
+        assert!(!is_active_feature(&account(
+            "11111111111111111111111111111111",
+            "AQAAAAAAAAAA"
+        )));
+        assert!(!is_active_feature(&Value::Null));
+    }
+
+    #[test]
+    fn test_decode_base64() {
+        assert_eq!(decode_base64("").unwrap(), b"");
+        assert_eq!(decode_base64("Zg==").unwrap(), b"f");
+        assert_eq!(decode_base64("Zm8=").unwrap(), b"fo");
+        assert_eq!(decode_base64("Zm9vYmFy").unwrap(), b"foobar");
+        assert_eq!(decode_base64("f0VMRgIB").unwrap(), b"\x7fELF\x02\x01");
+        assert!(decode_base64("Zm9v!").is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "network")]
+    fn test_bench() {
+        let (url, _) = serve(vec![r#"{"jsonrpc":"2.0","id":1,"result":1234}"#; 3]);
+
+        let bench = bench(&Rpc::new(&url), "getSlot", 3).unwrap();
+
+        assert_eq!(bench.method, "getSlot");
+        assert!(bench.min <= bench.median && bench.median <= bench.max);
+        assert!(bench.per_second > 0);
+    }
+
+    #[test]
+    #[cfg(feature = "network")]
+    fn test_check_reports_unhealthy_node() {
+        let (url, _) = serve(vec![
+            r#"{"jsonrpc":"2.0","id":1,"error":{"code":-32005,"message":"Node is behind by 42 slots"}}"#,
+            r#"{"jsonrpc":"2.0","id":1,"result":{"solana-core":"2.1.0","feature-set":1}}"#,
+            r#"{"jsonrpc":"2.0","id":1,"result":1234}"#,
+        ]);
+
+        let health = check(&Rpc::new(&url)).unwrap();
+
+        assert_eq!(health.status, "Node is behind by 42 slots");
+        assert_eq!(health.version, "2.1.0");
+        assert_eq!(health.slot, 1234);
+    }
+
+    #[test]
+    #[cfg(feature = "network")]
+    fn test_last_write() {
+        let (url, _) = serve(vec![
+            r#"{"jsonrpc":"2.0","id":1,"result":[{"signature":"5h6x","slot":1234,"blockTime":1760000000,"err":null}]}"#,
+            r#"{"jsonrpc":"2.0","id":1,"result":[]}"#,
+        ]);
+        let rpc = Rpc::new(&url);
+
+        assert_eq!(
+            rpc.last_write("8wWLP4YmLwFJT9kVo3Mhjwu8tkc4JyYUaSBV3JBXbDqk")
+                .unwrap(),
+            Some(1760000000)
+        );
+        assert_eq!(
+            rpc.last_write("8wWLP4YmLwFJT9kVo3Mhjwu8tkc4JyYUaSBV3JBXbDqk")
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "network")]
+    fn test_connect_authenticates() {
+        let (url, heads) = serve(vec![r#"{"jsonrpc":"2.0","id":1,"result":1234}"#]);
+        std::env::set_var("SOON_MIGRATE_TEST_RPC_URL", &url);
+        std::env::set_var("SOON_MIGRATE_TEST_RPC_TOKEN", "secret");
+        let mut endpoints = Endpoints {
+            devnet: "${SOON_MIGRATE_TEST_RPC_URL}".to_string(),
+            ..Endpoints::default()
+        };
+        endpoints.headers.devnet.insert(
+            "Authorization".to_string(),
+            "Bearer ${SOON_MIGRATE_TEST_RPC_TOKEN}".to_string(),
+        );
+
+        let rpc = Rpc::connect(&endpoints, SoonNetwork::Devnet).unwrap();
+
+        assert_eq!(rpc.host(), "${SOON_MIGRATE_TEST_RPC_URL}");
+        assert_eq!(rpc.call("getSlot", json!([])).unwrap(), 1234);
+        let head = heads.recv().unwrap().to_ascii_lowercase();
+        assert!(
+            head.contains("authorization: bearer secret\r\n"),
+            "{}",
+            head
+        );
+    }
+}
@@ -0,0 +1,324 @@
+use crate::compat::{is_comment, mentions, Availability, CompatFinding};
+use crate::endpoints::{Endpoints, SoonNetwork};
+use crate::errors::MigrationError;
+use crate::rpc::Rpc;
+use crate::workspace::find_files;
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Bundled list of runtime feature gates to compare.
+const FEATURE_CATALOG: &str = include_str!("../data/features.toml");
+
+#[derive(Deserialize)]
+struct FeatureCatalog {
+    feature: Vec<Feature>,
+}
+
+/// A runtime feature gate.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Feature {
+    pub key: String,
+    pub id: String,
+    pub name: String,
+    /// Catalog programs and sysvars the feature changes, by name.
+    #[serde(default)]
+    pub programs: Vec<String>,
+    /// Syscalls and other names in program source the feature changes.
+    #[serde(default)]
+    pub syscalls: Vec<String>,
+}
+
+impl Feature {
+    /// Whether the feature changes one of `programs` or `syscalls`.
+    fn concerns(&self, programs: &[&str], syscalls: &[String]) -> bool {
+        self.programs
+            .iter()
+            .any(|program| programs.contains(&program.as_str()))
+            || self
+                .syscalls
+                .iter()
+                .any(|syscall| syscalls.contains(syscall))
+    }
+}
+
+/// The features in the bundled catalog, in catalog order.
+fn features() -> &'static [Feature] {
+    static FEATURES: OnceLock<Vec<Feature>> = OnceLock::new();
+    FEATURES.get_or_init(|| {
+        toml::from_str::<FeatureCatalog>(FEATURE_CATALOG)
+            .expect("the bundled feature catalog is valid")
+            .feature
+    })
+}
+
+/// A node's runtime, as reported by `getVersion`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClusterRuntime {
+    pub host: String,
+    pub core: String,
+    pub feature_set: Option<u64>,
+}
+
+/// How SOON's runtime compares with the Solana cluster the project came
+/// from, for the programs and sysvars the project uses.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuntimeComparison {
+    pub solana: ClusterRuntime,
+    pub soon: ClusterRuntime,
+    /// Programs and sysvars that exist on Solana but not on SOON.
+    pub missing: Vec<String>,
+    /// Features active on Solana but not on SOON that change something the
+    /// project uses.
+    pub features_missing: Vec<Feature>,
+    /// Features active on SOON but not on Solana that change something the
+    /// project uses.
+    pub features_extra: Vec<Feature>,
+    /// How many other features are active on only one of the two.
+    pub features_unrelated: usize,
+}
+
+impl RuntimeComparison {
+    /// Whether the two nodes run a different set of runtime features.
+    pub fn feature_sets_differ(&self) -> bool {
+        self.solana.feature_set != self.soon.feature_set
+    }
+}
+
+/// SOON network the project targets, from `provider.cluster` in Anchor.toml
+/// whether or not it has been migrated yet; devnet when there is none.
+pub fn project_network(project: &Path, endpoints: &Endpoints) -> SoonNetwork {
+    let cluster = fs::read_to_string(project.join("Anchor.toml"))
+        .ok()
+        .and_then(|content| content.parse::<toml::Value>().ok())
+        .and_then(|anchor| {
+            Some(
+                anchor
+                    .get("provider")?
+                    .get("cluster")?
+                    .as_str()?
+                    .to_string(),
+            )
+        });
+    let Some(cluster) = cluster else {
+        return SoonNetwork::Devnet;
+    };
+
+    // A migrated project points at one of the SOON endpoints, custom or not
+    [
+        SoonNetwork::Devnet,
+        SoonNetwork::Testnet,
+        SoonNetwork::Mainnet,
+    ]
+    .into_iter()
+    .find(|network| endpoints.url(*network) == cluster)
+    .unwrap_or_else(|| SoonNetwork::from_cluster(&cluster))
+}
+
+fn runtime(rpc: &Rpc) -> Result<ClusterRuntime, MigrationError> {
+    let (core, feature_set) = rpc.version()?;
+    Ok(ClusterRuntime {
+        host: rpc.host().to_string(),
+        core,
+        feature_set,
+    })
+}
+
+/// Address each finding's program lives at on SOON, when it is known.
+fn soon_address(finding: &CompatFinding) -> Option<&str> {
+    match (&finding.availability, &finding.soon_program_id) {
+        (Availability::Moved, Some(id)) => Some(id),
+        _ => finding.program_id.as_deref(),
+    }
+}
+
+/// The syscalls and other feature-gated names in `features.toml` that the
+/// project's Rust source uses, sorted.
+pub fn syscalls_used(project: &Path) -> Vec<String> {
+    let mut used: Vec<String> = Vec::new();
+    for file in find_files(project, &|name| name.ends_with(".rs")) {
+        let Ok(content) = fs::read_to_string(project.join(&file)) else {
+            continue;
+        };
+        for line in content.lines().filter(|line| !is_comment(&file, line)) {
+            for syscall in features().iter().flat_map(|feature| &feature.syscalls) {
+                if !used.contains(syscall) && mentions(line, syscall) {
+                    used.push(syscall.clone());
+                }
+            }
+        }
+    }
+    used.sort();
+    used
+}
+
+/// Asks both nodes for their version, whether every program and sysvar in
+/// `findings` exists on each, and which of the bundled feature gates each
+/// has activated. Only features that change one of `findings` or `syscalls`
+/// are listed; the others are counted.
+pub fn compare(
+    findings: &[CompatFinding],
+    syscalls: &[String],
+    solana: &Rpc,
+    soon: &Rpc,
+) -> Result<RuntimeComparison, MigrationError> {
+    // Programs found only by their crates have no address to look up
+    let located: Vec<&CompatFinding> = findings.iter().filter(|f| f.program_id.is_some()).collect();
+    let on_solana: Vec<&str> = located
+        .iter()
+        .filter_map(|f| f.program_id.as_deref())
+        .collect();
+    let on_soon: Vec<&str> = located.iter().filter_map(|f| soon_address(f)).collect();
+    let feature_ids: Vec<&str> = features().iter().map(|f| f.id.as_str()).collect();
+    let solana_features = solana.features_active(&feature_ids)?;
+    let soon_features = soon.features_active(&feature_ids)?;
+
+    let programs: Vec<&str> = findings.iter().map(|f| f.program.as_str()).collect();
+    let (features_missing, unrelated_missing) = split_related(
+        active_on_first(&solana_features, &soon_features),
+        &programs,
+        syscalls,
+    );
+    let (features_extra, unrelated_extra) = split_related(
+        active_on_first(&soon_features, &solana_features),
+        &programs,
+        syscalls,
+    );
+
+    Ok(RuntimeComparison {
+        solana: runtime(solana)?,
+        soon: runtime(soon)?,
+        missing: missing_on_soon(
+            &located,
+            &solana.accounts_exist(&on_solana)?,
+            &soon.accounts_exist(&on_soon)?,
+        ),
+        features_missing,
+        features_extra,
+        features_unrelated: unrelated_missing + unrelated_extra,
+    })
+}
+
+/// The features that change one of `programs` or `syscalls`, and how many
+/// others there are.
+fn split_related(
+    features: Vec<Feature>,
+    programs: &[&str],
+    syscalls: &[String],
+) -> (Vec<Feature>, usize) {
+    let (related, unrelated): (Vec<Feature>, Vec<Feature>) = features
+        .into_iter()
+        .partition(|feature| feature.concerns(programs, syscalls));
+    (related, unrelated.len())
+}
+
+/// Catalog features active according to `first` but not `second`.
+fn active_on_first(first: &[bool], second: &[bool]) -> Vec<Feature> {
+    features()
+        .iter()
+        .zip(first.iter().zip(second))
+        .filter(|(_, (first, second))| **first && !**second)
+        .map(|(feature, _)| feature.clone())
+        .collect()
+}
+
+/// Findings whose program exists on Solana but not on SOON.
+fn missing_on_soon(
+    findings: &[&CompatFinding],
+    on_solana: &[bool],
+    on_soon: &[bool],
+) -> Vec<String> {
+    findings
+        .iter()
+        .zip(on_solana.iter().zip(on_soon))
+        .filter(|(_, (solana, soon))| **solana && !**soon)
+        .map(|(finding, _)| finding.program.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_on_soon() {
+        let finding = |program: &str| CompatFinding {
+            program: program.to_string(),
+            program_id: Some("11111111111111111111111111111111".to_string()),
+            soon_program_id: None,
+            availability: Availability::Unverified,
+            note: String::new(),
+            provider: None,
+            model: None,
+            apro: None,
+            evidence: Vec::new(),
+            locations: Vec::new(),
+            uses: 0,
+            confidence: 85,
+            signals: Vec::new(),
+            dev_only: false,
+            client_only: false,
+            cpi_calls: Vec::new(),
+            crates: Vec::new(),
+            owners: Vec::new(),
+            effort: None,
+        };
+        let findings = [finding("Pyth"), finding("SPL Token"), finding("Local")];
+
+        assert_eq!(
+            missing_on_soon(
+                &findings.iter().collect::<Vec<_>>(),
+                &[true, true, false],
+                &[false, true, false]
+            ),
+            vec!["Pyth"]
+        );
+    }
+
+    #[test]
+    fn test_only_related_features_listed() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::write(
+            dir.path().join("lib.rs"),
+            "use solana_program::alt_bn128::prelude::*;\n// sol_log_data(&[b\"x\"]);\n",
+        )
+        .unwrap();
+        let syscalls = syscalls_used(dir.path());
+        assert_eq!(syscalls, vec!["alt_bn128"]);
+
+        let differing: Vec<Feature> = features().to_vec();
+        let (related, unrelated) = split_related(differing, &["Ed25519 precompile"], &syscalls);
+        let keys: Vec<&str> = related.iter().map(|f| f.key.as_str()).collect();
+        assert!(keys.contains(&"enable_alt_bn128_syscall"));
+        assert!(keys.contains(&"ed25519_program_enabled"));
+        assert!(!keys.contains(&"sol_log_data_syscall_enabled"));
+        assert_eq!(related.len() + unrelated, features().len());
+    }
+
+    #[test]
+    fn test_feature_catalog() {
+        const PROGRAM_CATALOG: &str = include_str!("../data/programs.toml");
+
+        let mut active = vec![false; features().len()];
+        active[0] = true;
+        let differing = active_on_first(&active, &vec![false; features().len()]);
+        assert_eq!(differing, vec![features()[0].clone()]);
+        assert!(active_on_first(&active, &active).is_empty());
+
+        let mut ids = std::collections::HashSet::new();
+        for feature in features() {
+            assert!(ids.insert(&feature.id), "{} is listed twice", feature.key);
+            let bytes = bs58::decode(&feature.id).into_vec().unwrap();
+            assert_eq!(bytes.len(), 32, "{} has a malformed address", feature.key);
+            for program in &feature.programs {
+                assert!(
+                    PROGRAM_CATALOG.contains(&format!("name = \"{}\"", program)),
+                    "{} names {}, which is not in programs.toml",
+                    feature.key,
+                    program
+                );
+            }
+        }
+    }
+}
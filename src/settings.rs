@@ -0,0 +1,143 @@
+use crate::compat::Rule;
+use crate::endpoints::SoonNetwork;
+use crate::errors::MigrationError;
+use crate::notify::NotifyFormat;
+use crate::theme::Theme;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Name of the optional per-project config file.
+pub const CONFIG_FILE_NAME: &str = "soon-migrate.toml";
+
+/// Contents of `soon-migrate.toml`. Every section is optional.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub rpc: RpcSettings,
+    /// Program name to the ID it is deployed under on SOON.
+    pub program_ids: BTreeMap<String, String>,
+    pub toolchain: ToolchainSettings,
+    pub notify: NotifySettings,
+    pub output: OutputSettings,
+    pub bridge: BridgeSettings,
+    pub oracle: OracleSettings,
+    /// Detection rules run alongside the bundled catalog.
+    pub rules: Vec<Rule>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct RpcSettings {
+    pub devnet: Option<String>,
+    pub testnet: Option<String>,
+    pub mainnet: Option<String>,
+    pub genesis: GenesisSettings,
+    pub headers: HeaderSettings,
+}
+
+/// Genesis hash each SOON network is expected to have, checked against
+/// custom endpoints instead of asking the public ones.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct GenesisSettings {
+    pub devnet: Option<String>,
+    pub testnet: Option<String>,
+    pub mainnet: Option<String>,
+}
+
+/// Headers sent with every request to each SOON network's endpoint, such as
+/// the API key a private RPC wants. Values may reference `${VAR}`.
+#[derive(Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct HeaderSettings {
+    pub devnet: BTreeMap<String, String>,
+    pub testnet: BTreeMap<String, String>,
+    pub mainnet: BTreeMap<String, String>,
+}
+
+/// Versions to pin in Anchor.toml's `[toolchain]`, instead of the recommended ones.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct ToolchainSettings {
+    pub anchor_version: Option<String>,
+    pub solana_version: Option<String>,
+}
+
+/// Where to send a summary after each run.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotifySettings {
+    pub webhook: Option<String>,
+    pub format: NotifyFormat,
+}
+
+/// How terminal output looks.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutputSettings {
+    pub theme: Theme,
+}
+
+/// Where tokens the project uses are bridged to SOON.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct BridgeSettings {
+    /// Bridge API listing the assets it supports, asked by `bridge --online`.
+    pub api: Option<String>,
+    /// Solana mint, or symbol of a well-known one, to its mint on SOON.
+    pub mints: BTreeMap<String, String>,
+}
+
+/// Where the oracle feeds the project reads are served on SOON.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct OracleSettings {
+    /// Solana feed account to the APRO feed serving the same pair on SOON.
+    pub feeds: BTreeMap<String, String>,
+}
+
+impl GenesisSettings {
+    pub fn hash(&self, network: SoonNetwork) -> Option<&str> {
+        match network {
+            SoonNetwork::Devnet => self.devnet.as_deref(),
+            SoonNetwork::Testnet => self.testnet.as_deref(),
+            SoonNetwork::Mainnet => self.mainnet.as_deref(),
+        }
+    }
+}
+
+impl HeaderSettings {
+    pub fn get(&self, network: SoonNetwork) -> &BTreeMap<String, String> {
+        match network {
+            SoonNetwork::Devnet => &self.devnet,
+            SoonNetwork::Testnet => &self.testnet,
+            SoonNetwork::Mainnet => &self.mainnet,
+        }
+    }
+}
+
+impl Settings {
+    /// Loads the config file given explicitly, or `soon-migrate.toml` from the
+    /// project root when present. A missing default file is not an error.
+    pub fn load(project_path: &str, explicit: Option<&str>) -> Result<Self, MigrationError> {
+        let Some(path) = Settings::path(project_path, explicit) else {
+            return Ok(Settings::default());
+        };
+
+        let content = fs::read_to_string(&path)
+            .map_err(|e| MigrationError::ConfigError(format!("{}: {}", path.display(), e)))?;
+
+        toml::from_str(&content)
+            .map_err(|e| MigrationError::ConfigError(format!("{}: {}", path.display(), e)))
+    }
+
+    /// The config file `load` reads, if any.
+    pub fn path(project_path: &str, explicit: Option<&str>) -> Option<PathBuf> {
+        match explicit {
+            Some(file) => Some(PathBuf::from(file)),
+            None => Some(Path::new(project_path).join(CONFIG_FILE_NAME)).filter(|p| p.exists()),
+        }
+    }
+}
@@ -0,0 +1,211 @@
+//! Synthetic Anchor projects for integration tests, laid out the way
+//! `anchor init` would: `Anchor.toml`, a workspace `Cargo.toml` and one crate
+//! per program under `programs/`.
+
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+/// Program ID `anchor init` gives the first program.
+pub const DEFAULT_PROGRAM_ID: &str = "EtQdsPNDckBhME3gRjcj9Z4Z9tGEYAoHjWKv7aHJgBua";
+
+/// An Anchor project in a temporary directory, deleted when dropped.
+pub struct AnchorProject {
+    dir: TempDir,
+}
+
+/// Describes an `AnchorProject` before it is written to disk.
+#[derive(Debug, Clone)]
+pub struct AnchorProjectBuilder {
+    cluster: String,
+    programs: Vec<(String, String)>,
+    anchor_version: Option<String>,
+    solana_version: Option<String>,
+    files: Vec<(String, String)>,
+}
+
+impl Default for AnchorProjectBuilder {
+    fn default() -> Self {
+        AnchorProjectBuilder {
+            cluster: "Localnet".to_string(),
+            programs: Vec::new(),
+            anchor_version: None,
+            solana_version: None,
+            files: Vec::new(),
+        }
+    }
+}
+
+impl AnchorProjectBuilder {
+    /// Value of `provider.cluster`; `Localnet` by default.
+    pub fn cluster(mut self, cluster: &str) -> Self {
+        self.cluster = cluster.to_string();
+        self
+    }
+
+    /// Adds a program under `[programs.localnet]`, with a crate in
+    /// `programs/<name>` that declares `id`.
+    pub fn program(mut self, name: &str, id: &str) -> Self {
+        self.programs.push((name.to_string(), id.to_string()));
+        self
+    }
+
+    /// Pins `anchor_version` in Anchor.toml's `[toolchain]`.
+    pub fn anchor_version(mut self, version: &str) -> Self {
+        self.anchor_version = Some(version.to_string());
+        self
+    }
+
+    /// Pins `solana_version` in Anchor.toml's `[toolchain]`.
+    pub fn solana_version(mut self, version: &str) -> Self {
+        self.solana_version = Some(version.to_string());
+        self
+    }
+
+    /// Adds any other file, such as a workflow or a client script, at a path
+    /// relative to the project root.
+    pub fn file(mut self, path: &str, content: &str) -> Self {
+        self.files.push((path.to_string(), content.to_string()));
+        self
+    }
+
+    /// Writes the project. A project without programs gets a single
+    /// `migration` program, like a fresh `anchor init`.
+    pub fn build(self) -> AnchorProject {
+        let dir = TempDir::new().expect("failed to create a temporary directory");
+        let project = AnchorProject { dir };
+
+        let mut programs = self.programs;
+        if programs.is_empty() {
+            programs.push(("migration".to_string(), DEFAULT_PROGRAM_ID.to_string()));
+        }
+
+        let mut toolchain = String::new();
+        if let Some(version) = &self.anchor_version {
+            toolchain.push_str(&format!("anchor_version = \"{}\"\n", version));
+        }
+        if let Some(version) = &self.solana_version {
+            toolchain.push_str(&format!("solana_version = \"{}\"\n", version));
+        }
+        let program_ids: String = programs
+            .iter()
+            .map(|(name, id)| format!("{} = \"{}\"\n", name, id))
+            .collect();
+
+        project.write(
+            "Anchor.toml",
+            &format!(
+                r#"[toolchain]
+{}
+[features]
+resolution = true
+skip-lint = false
+
+[programs.localnet]
+{}
+[registry]
+url = "https://api.apr.dev"
+
+[provider]
+cluster = "{}"
+wallet = "~/.config/solana/id.json"
+
+[scripts]
+test = "yarn run ts-mocha -p ./tsconfig.json -t 1000000 tests/**/*.ts"
+"#,
+                toolchain, program_ids, self.cluster
+            ),
+        );
+        project.write(
+            "Cargo.toml",
+            "[workspace]\nmembers = [\"programs/*\"]\nresolver = \"2\"\n",
+        );
+
+        for (name, id) in &programs {
+            project.write(
+                &format!("programs/{}/Cargo.toml", name),
+                &format!(
+                    "[package]\nname = \"{}\"\nversion = \"0.1.0\"\n\n[dependencies]\nanchor-lang = \"{}\"\n",
+                    name,
+                    self.anchor_version.as_deref().unwrap_or("0.30.1")
+                ),
+            );
+            project.write(
+                &format!("programs/{}/src/lib.rs", name),
+                &format!("use anchor_lang::prelude::*;\n\ndeclare_id!(\"{}\");\n", id),
+            );
+        }
+        for (path, content) in &self.files {
+            project.write(path, content);
+        }
+
+        project
+    }
+}
+
+impl AnchorProject {
+    pub fn builder() -> AnchorProjectBuilder {
+        AnchorProjectBuilder::default()
+    }
+
+    /// A fresh `anchor init` project with a single program.
+    pub fn new() -> Self {
+        AnchorProject::builder().build()
+    }
+
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// Reads a file relative to the project root.
+    pub fn read(&self, file: &str) -> String {
+        fs::read_to_string(self.path().join(file))
+            .unwrap_or_else(|e| panic!("failed to read {}: {}", file, e))
+    }
+
+    /// Writes a file relative to the project root, creating its directory.
+    pub fn write(&self, file: &str, content: &str) {
+        let path = self.path().join(file);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .unwrap_or_else(|e| panic!("failed to create {}: {}", parent.display(), e));
+        }
+        fs::write(&path, content).unwrap_or_else(|e| panic!("failed to write {}: {}", file, e));
+    }
+}
+
+impl Default for AnchorProject {
+    fn default() -> Self {
+        AnchorProject::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder_lays_out_an_anchor_workspace() {
+        let project = AnchorProject::builder()
+            .cluster("Devnet")
+            .program("vault", DEFAULT_PROGRAM_ID)
+            .anchor_version("0.29.0")
+            .file(".github/workflows/deploy.yml", "run: anchor deploy\n")
+            .build();
+
+        let anchor: toml::Value = project.read("Anchor.toml").parse().unwrap();
+        assert_eq!(anchor["provider"]["cluster"].as_str(), Some("Devnet"));
+        assert_eq!(
+            anchor["programs"]["localnet"]["vault"].as_str(),
+            Some(DEFAULT_PROGRAM_ID)
+        );
+        assert_eq!(
+            anchor["toolchain"]["anchor_version"].as_str(),
+            Some("0.29.0")
+        );
+        assert!(project
+            .read("programs/vault/src/lib.rs")
+            .contains(DEFAULT_PROGRAM_ID));
+        assert!(project.path().join(".github/workflows/deploy.yml").exists());
+    }
+}
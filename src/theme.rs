@@ -0,0 +1,109 @@
+use colored::{Color, ColoredString, Colorize};
+use serde::Deserialize;
+use std::sync::OnceLock;
+
+/// Terminal color scheme, chosen with `[output] theme` in the config file.
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    /// Yellow and cyan accents, for dark backgrounds.
+    #[default]
+    Dark,
+    /// Magenta and blue instead of yellow and cyan, which wash out on light
+    /// backgrounds.
+    Light,
+    /// No colors at all.
+    Plain,
+}
+
+/// What a piece of output means, which decides its color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Success,
+    Warning,
+    Error,
+    Info,
+    Muted,
+}
+
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+/// Sets the theme for the rest of the run. Only the first call counts.
+pub fn set(theme: Theme) {
+    if theme == Theme::Plain {
+        colored::control::set_override(false);
+    }
+    let _ = THEME.set(theme);
+}
+
+/// Colors `text` for `role` under `theme`.
+pub fn paint(text: ColoredString, theme: Theme, role: Role) -> ColoredString {
+    let color = match (theme, role) {
+        (Theme::Plain, _) => return text.clear(),
+        (_, Role::Muted) => return text.dimmed(),
+        (_, Role::Success) => Color::Green,
+        (_, Role::Error) => Color::Red,
+        (Theme::Dark, Role::Warning) => Color::Yellow,
+        (Theme::Dark, Role::Info) => Color::Cyan,
+        (Theme::Light, Role::Warning) => Color::Magenta,
+        (Theme::Light, Role::Info) => Color::Blue,
+    };
+    text.color(color)
+}
+
+/// Colors output by role under the theme picked with `set`.
+pub trait Styled: Sized {
+    fn styled(self, role: Role) -> ColoredString;
+
+    fn success(self) -> ColoredString {
+        self.styled(Role::Success)
+    }
+
+    fn warning(self) -> ColoredString {
+        self.styled(Role::Warning)
+    }
+
+    fn error(self) -> ColoredString {
+        self.styled(Role::Error)
+    }
+
+    fn info(self) -> ColoredString {
+        self.styled(Role::Info)
+    }
+
+    fn muted(self) -> ColoredString {
+        self.styled(Role::Muted)
+    }
+}
+
+impl<T: Colorize> Styled for T {
+    fn styled(self, role: Role) -> ColoredString {
+        paint(
+            self.normal(),
+            THEME.get().copied().unwrap_or_default(),
+            role,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_light_theme_avoids_yellow_and_cyan() {
+        let warning = paint("warning".normal(), Theme::Light, Role::Warning);
+        assert_eq!(warning.fgcolor(), Some(Color::Magenta));
+        let info = paint("info".normal(), Theme::Light, Role::Info);
+        assert_eq!(info.fgcolor(), Some(Color::Blue));
+
+        assert_eq!(
+            paint("warning".normal(), Theme::Dark, Role::Warning).fgcolor(),
+            Some(Color::Yellow)
+        );
+        assert_eq!(
+            paint("error".red(), Theme::Plain, Role::Error).fgcolor(),
+            None
+        );
+    }
+}
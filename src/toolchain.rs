@@ -0,0 +1,505 @@
+use crate::result::{Warning, WarningKind};
+use crate::workspace::{find_files, ProgramCrate};
+use std::cmp::Ordering;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// Anchor version recommended for SOON.
+pub const RECOMMENDED_ANCHOR_VERSION: &str = "0.30.1";
+/// Solana toolchain version recommended for SOON.
+pub const RECOMMENDED_SOLANA_VERSION: &str = "1.18.26";
+
+/// Oldest and newest (major, minor) release lines known to work on SOON.
+type Validated = ((u64, u64), (u64, u64));
+
+const ANCHOR_VALIDATED: Validated = ((0, 29), (0, 30));
+const SOLANA_VALIDATED: Validated = ((1, 17), (1, 18));
+
+/// Crates checked in the resolved dependency graph, with the oldest and
+/// newest release lines validated on SOON and the version to move to.
+/// `anchor-lang` is here as well as in each program's requirement, since
+/// Cargo.lock can resolve a requirement such as `^0.29` outside the range.
+const DEPENDENCY_MATRIX: &[(&str, Validated, &str)] = &[
+    ("anchor-lang", ANCHOR_VALIDATED, RECOMMENDED_ANCHOR_VERSION),
+    ("anchor-spl", ANCHOR_VALIDATED, RECOMMENDED_ANCHOR_VERSION),
+    (
+        "solana-program",
+        SOLANA_VALIDATED,
+        RECOMMENDED_SOLANA_VERSION,
+    ),
+    ("solana-sdk", SOLANA_VALIDATED, RECOMMENDED_SOLANA_VERSION),
+    ("spl-token", ((4, 0), (4, 0)), "4.0.0"),
+    ("spl-token-2022", ((0, 9), (3, 0)), "3.0.4"),
+    ("spl-associated-token-account", ((2, 2), (3, 0)), "3.0.4"),
+];
+
+/// Parses the leading `major.minor.patch` out of a version or requirement
+/// string such as `0.30.1`, `=0.29.0` or `^0.28`. Missing parts count as 0.
+pub fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let start = version.find(|c: char| c.is_ascii_digit())?;
+    let mut parts = version[start..]
+        .split(|c: char| !c.is_ascii_digit())
+        .take(3)
+        .map(|part| part.parse::<u64>().ok());
+
+    let major = parts.next()??;
+    let minor = parts.next().flatten().unwrap_or(0);
+    let patch = parts.next().flatten().unwrap_or(0);
+    Some((major, minor, patch))
+}
+
+/// Version `key` of `[toolchain]` should be pinned to: `configured` when
+/// given, otherwise the recommended version if `current` is missing or not
+/// validated on SOON. `None` means the current value can stay.
+pub fn pinned_version(
+    key: &str,
+    current: Option<&str>,
+    configured: Option<&str>,
+) -> Option<String> {
+    let (validated, recommended) = match key {
+        "anchor_version" => (ANCHOR_VALIDATED, RECOMMENDED_ANCHOR_VERSION),
+        "solana_version" => (SOLANA_VALIDATED, RECOMMENDED_SOLANA_VERSION),
+        _ => return None,
+    };
+
+    let target = match configured {
+        Some(version) => version,
+        None if current.and_then(|v| compare(v, validated)) == Some(Ordering::Equal) => {
+            return None
+        }
+        None => recommended,
+    };
+    (current != Some(target)).then(|| target.to_string())
+}
+
+/// Where a version sits relative to the validated release lines.
+fn compare(version: &str, validated: Validated) -> Option<Ordering> {
+    let (major, minor, _) = parse_version(version)?;
+    let line = (major, minor);
+    Some(if line < validated.0 {
+        Ordering::Less
+    } else if line > validated.1 {
+        Ordering::Greater
+    } else {
+        Ordering::Equal
+    })
+}
+
+fn version_warning(
+    kind: WarningKind,
+    what: &str,
+    found: &str,
+    file: &str,
+    validated: Validated,
+    recommended: &str,
+) -> Option<Warning> {
+    let range = format!(
+        "{}.{}-{}.{}",
+        validated.0 .0, validated.0 .1, validated.1 .0, validated.1 .1
+    );
+
+    let warning = match compare(found, validated)? {
+        Ordering::Equal => return None,
+        Ordering::Less => Warning::new(
+            kind,
+            format!(
+                "{} {} predates the versions validated on SOON ({})",
+                what, found, range
+            ),
+        )
+        .recommend(format!("Upgrade {} to {}.", what, recommended)),
+        Ordering::Greater => Warning::new(
+            kind,
+            format!(
+                "{} {} is newer than the versions validated on SOON ({})",
+                what, found, range
+            ),
+        )
+        .recommend(format!(
+            "Test carefully on SOON devnet or pin {} to {}.",
+            what, recommended
+        )),
+    };
+
+    Some(warning.in_file(file))
+}
+
+/// Checks the `[toolchain]` versions in Anchor.toml and the `anchor-lang`
+/// dependency of every program against the versions validated on SOON.
+pub fn toolchain_advice(
+    project: &Path,
+    anchor_toml: &toml::Value,
+    programs: &[ProgramCrate],
+) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+    let toolchain = anchor_toml.get("toolchain");
+
+    if let Some(version) = toolchain
+        .and_then(|t| t.get("anchor_version"))
+        .and_then(|v| v.as_str())
+    {
+        warnings.extend(version_warning(
+            WarningKind::AnchorVersion,
+            "anchor_version",
+            version,
+            "Anchor.toml",
+            ANCHOR_VALIDATED,
+            RECOMMENDED_ANCHOR_VERSION,
+        ));
+    }
+
+    if let Some(version) = toolchain
+        .and_then(|t| t.get("solana_version"))
+        .and_then(|v| v.as_str())
+    {
+        warnings.extend(version_warning(
+            WarningKind::SolanaVersion,
+            "solana_version",
+            version,
+            "Anchor.toml",
+            SOLANA_VALIDATED,
+            RECOMMENDED_SOLANA_VERSION,
+        ));
+    }
+
+    warnings.extend(dependency_warnings(
+        project,
+        programs,
+        "anchor-lang",
+        WarningKind::AnchorVersion,
+        ANCHOR_VALIDATED,
+        RECOMMENDED_ANCHOR_VERSION,
+    ));
+
+    warnings
+}
+
+/// Checks the `solana-program` dependency of native programs against the
+/// versions validated on SOON.
+pub fn native_toolchain_advice(project: &Path, programs: &[ProgramCrate]) -> Vec<Warning> {
+    dependency_warnings(
+        project,
+        programs,
+        "solana-program",
+        WarningKind::SolanaVersion,
+        SOLANA_VALIDATED,
+        RECOMMENDED_SOLANA_VERSION,
+    )
+}
+
+/// Warnings for every program whose `dependency` is outside `validated`.
+fn dependency_warnings(
+    project: &Path,
+    programs: &[ProgramCrate],
+    dependency: &str,
+    kind: WarningKind,
+    validated: Validated,
+    recommended: &str,
+) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+
+    for program in programs {
+        let manifest_path = program.dir.join("Cargo.toml");
+        let Some(manifest) = fs::read_to_string(project.join(&manifest_path))
+            .ok()
+            .and_then(|content| content.parse::<toml::Value>().ok())
+        else {
+            continue;
+        };
+
+        let Some(version) = manifest
+            .get("dependencies")
+            .and_then(|deps| deps.get(dependency))
+            .and_then(|dep| dep.as_str().or_else(|| dep.get("version")?.as_str()))
+        else {
+            continue;
+        };
+
+        warnings.extend(version_warning(
+            kind,
+            dependency,
+            version,
+            &manifest_path.to_string_lossy().replace('\\', "/"),
+            validated,
+            recommended,
+        ));
+    }
+
+    warnings
+}
+
+/// Checks the versions `cargo metadata` resolves for the crates in
+/// [`DEPENDENCY_MATRIX`], including ones only pulled in through other
+/// crates. Only runs when the project has a Cargo.lock, so nothing is
+/// re-resolved; with `offline`, Cargo may only use what is downloaded
+/// already. Nothing is reported if Cargo is missing or the graph cannot be
+/// resolved.
+pub fn dependency_matrix_advice(project: &Path, offline: bool) -> Vec<Warning> {
+    if !project.join("Cargo.lock").is_file() {
+        return Vec::new();
+    }
+
+    let mut metadata = Command::new("cargo");
+    metadata
+        .args(["metadata", "--format-version", "1", "--locked"])
+        .current_dir(project);
+    if offline {
+        metadata.arg("--offline");
+    }
+    let Some(graph) = metadata
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| serde_json::from_slice::<serde_json::Value>(&output.stdout).ok())
+    else {
+        return Vec::new();
+    };
+
+    let packages: Vec<(String, String)> = graph
+        .get("packages")
+        .and_then(|packages| packages.as_array())
+        .into_iter()
+        .flatten()
+        // Workspace members have no source; their own versions mean nothing here
+        .filter(|package| {
+            package
+                .get("source")
+                .is_some_and(|source| !source.is_null())
+        })
+        .filter_map(|package| {
+            Some((
+                package.get("name")?.as_str()?.to_string(),
+                package.get("version")?.as_str()?.to_string(),
+            ))
+        })
+        .collect();
+    matrix_warnings(&packages)
+}
+
+/// Warnings for every resolved `(name, version)` outside its validated range.
+fn matrix_warnings(packages: &[(String, String)]) -> Vec<Warning> {
+    let mut resolved: Vec<&(String, String)> = packages.iter().collect();
+    resolved.sort();
+    resolved.dedup();
+
+    resolved
+        .into_iter()
+        .filter_map(|(name, version)| {
+            let (_, validated, recommended) = DEPENDENCY_MATRIX
+                .iter()
+                .find(|(crate_name, _, _)| crate_name == name)?;
+            version_warning(
+                WarningKind::DependencyVersion,
+                name,
+                version,
+                "Cargo.lock",
+                *validated,
+                recommended,
+            )
+        })
+        .collect()
+}
+
+/// What a client library needs when it talks to SOON: the package, the
+/// major versions it applies to (`None` for any), and the advice.
+const CLIENT_LIBRARIES: &[(&str, Option<u64>, &str)] = &[
+    (
+        "@solana/web3.js",
+        Some(1),
+        "web3.js 1.x derives the websocket URL from the RPC URL; pass SOON's websocket endpoint as wsEndpoint to new Connection(...) so subscriptions and confirmations work.",
+    ),
+    (
+        "@solana/web3.js",
+        Some(2),
+        "web3.js 2.x takes the RPC and websocket URLs separately; give both createSolanaRpc and createSolanaRpcSubscriptions SOON endpoints.",
+    ),
+    (
+        "@solana/kit",
+        None,
+        "Kit takes the RPC and websocket URLs separately; give both createSolanaRpc and createSolanaRpcSubscriptions SOON endpoints.",
+    ),
+    (
+        "@solana/wallet-adapter-base",
+        None,
+        WALLET_ADAPTER_ADVICE,
+    ),
+    (
+        "@solana/wallet-adapter-react",
+        None,
+        WALLET_ADAPTER_ADVICE,
+    ),
+];
+
+const WALLET_ADAPTER_ADVICE: &str = "WalletAdapterNetwork only names Solana clusters; give ConnectionProvider the SOON endpoint and check that the wallets you offer can sign for SOON.";
+
+/// Warnings for the Solana client libraries each `package.json` depends on,
+/// with what they need to work against SOON's RPC.
+pub fn client_library_warnings(project: &Path) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+
+    for file in find_files(project, &|name| name == "package.json") {
+        let Some(package) = fs::read_to_string(project.join(&file))
+            .ok()
+            .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+        else {
+            continue;
+        };
+
+        for (library, major, advice) in CLIENT_LIBRARIES {
+            let Some(version) = ["dependencies", "devDependencies", "peerDependencies"]
+                .iter()
+                .find_map(|section| package[section][library].as_str())
+            else {
+                continue;
+            };
+            let found_major = parse_version(version).map(|(major, _, _)| major);
+            if major.is_some() && found_major != *major {
+                continue;
+            }
+            // The wallet adapter packages share their advice; give it once
+            let advised = warnings.iter().any(|warning: &Warning| {
+                warning.file.as_deref() == Some(file.as_str())
+                    && warning.recommendation.as_deref() == Some(*advice)
+            });
+            if advised {
+                continue;
+            }
+            warnings.push(
+                Warning::new(
+                    WarningKind::ClientLibrary,
+                    format!("{} {} needs configuring for SOON", library, version),
+                )
+                .in_file(&file)
+                .recommend(*advice),
+            );
+        }
+    }
+
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("0.30.1"), Some((0, 30, 1)));
+        assert_eq!(parse_version("=0.29.0"), Some((0, 29, 0)));
+        assert_eq!(parse_version("^0.28"), Some((0, 28, 0)));
+        assert_eq!(parse_version("1.18.26-rc1"), Some((1, 18, 26)));
+        assert_eq!(parse_version("latest"), None);
+    }
+
+    #[test]
+    fn test_version_warning_direction() {
+        let old = version_warning(
+            WarningKind::AnchorVersion,
+            "anchor-lang",
+            "0.26.0",
+            "Cargo.toml",
+            ANCHOR_VALIDATED,
+            RECOMMENDED_ANCHOR_VERSION,
+        )
+        .unwrap();
+        assert!(old.recommendation.unwrap().starts_with("Upgrade"));
+
+        let new = version_warning(
+            WarningKind::AnchorVersion,
+            "anchor-lang",
+            "0.31.0",
+            "Cargo.toml",
+            ANCHOR_VALIDATED,
+            RECOMMENDED_ANCHOR_VERSION,
+        )
+        .unwrap();
+        assert!(new.message.contains("newer"));
+
+        assert!(version_warning(
+            WarningKind::AnchorVersion,
+            "anchor-lang",
+            "0.29.0",
+            "Cargo.toml",
+            ANCHOR_VALIDATED,
+            RECOMMENDED_ANCHOR_VERSION,
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_pinned_version() {
+        assert_eq!(
+            pinned_version("anchor_version", None, None).as_deref(),
+            Some(RECOMMENDED_ANCHOR_VERSION)
+        );
+        assert_eq!(
+            pinned_version("solana_version", Some("1.16.0"), None).as_deref(),
+            Some(RECOMMENDED_SOLANA_VERSION)
+        );
+        assert_eq!(pinned_version("anchor_version", Some("0.29.0"), None), None);
+        assert_eq!(
+            pinned_version("anchor_version", Some("0.29.0"), Some("0.30.0")).as_deref(),
+            Some("0.30.0")
+        );
+        assert_eq!(
+            pinned_version("anchor_version", Some("0.30.0"), Some("0.30.0")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_matrix_warnings() {
+        let packages: Vec<(String, String)> = [
+            ("spl-token", "3.5.0"),
+            ("spl-token", "3.5.0"),
+            ("spl-token-2022", "1.0.0"),
+            ("anchor-lang", "0.26.0"),
+            ("serde", "1.0.0"),
+        ]
+        .iter()
+        .map(|(name, version)| (name.to_string(), version.to_string()))
+        .collect();
+
+        let warnings = matrix_warnings(&packages);
+
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[0].kind, WarningKind::DependencyVersion);
+        assert_eq!(warnings[0].file.as_deref(), Some("Cargo.lock"));
+        assert_eq!(
+            warnings[0].recommendation.as_deref(),
+            Some("Upgrade anchor-lang to 0.30.1.")
+        );
+        assert_eq!(
+            warnings[1].recommendation.as_deref(),
+            Some("Upgrade spl-token to 4.0.0.")
+        );
+    }
+
+    #[test]
+    fn test_client_library_warnings() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::create_dir(dir.path().join("app")).unwrap();
+        fs::write(
+            dir.path().join("app").join("package.json"),
+            r#"{"dependencies": {"@solana/web3.js": "^1.95.3", "@solana/wallet-adapter-base": "0.9.23", "@solana/wallet-adapter-react": "0.15.35"}}"#,
+        )
+        .unwrap();
+
+        let warnings = client_library_warnings(dir.path());
+
+        let found: Vec<String> = warnings.iter().map(|w| w.to_string()).collect();
+        assert_eq!(
+            found,
+            vec![
+                "app/package.json: @solana/web3.js ^1.95.3 needs configuring for SOON",
+                "app/package.json: @solana/wallet-adapter-base 0.9.23 needs configuring for SOON",
+            ]
+        );
+        assert!(warnings[0]
+            .recommendation
+            .as_deref()
+            .unwrap()
+            .contains("wsEndpoint"));
+    }
+}
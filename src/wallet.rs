@@ -0,0 +1,136 @@
+use crate::errors::MigrationError;
+use crate::rpc::Rpc;
+use serde_json::json;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+
+/// Keypair the Solana CLI uses when Anchor.toml names none.
+const DEFAULT_WALLET: &str = "~/.config/solana/id.json";
+
+/// How long to wait for an airdrop to be confirmed.
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The keypair file `provider.wallet` in the project's Anchor.toml points
+/// at, with `~` expanded.
+pub fn wallet_path(project: &Path) -> PathBuf {
+    let wallet = fs::read_to_string(project.join("Anchor.toml"))
+        .ok()
+        .and_then(|content| content.parse::<toml::Value>().ok())
+        .and_then(|anchor| Some(anchor.get("provider")?.get("wallet")?.as_str()?.to_string()))
+        .unwrap_or_else(|| DEFAULT_WALLET.to_string());
+
+    match wallet.strip_prefix("~/") {
+        Some(rest) => match std::env::var_os("HOME") {
+            Some(home) => Path::new(&home).join(rest),
+            None => PathBuf::from(wallet),
+        },
+        None => project.join(wallet),
+    }
+}
+
+/// Base58 address of the project's wallet, read from its keypair file.
+pub fn wallet_address(project: &Path) -> Result<String, MigrationError> {
+    keypair_address(&wallet_path(project))
+        .map_err(|reason| MigrationError::ConfigError(format!("wallet {}", reason)))
+}
+
+/// Base58 address of the keypair in the Solana CLI keypair file at `path`.
+pub fn keypair_address(path: &Path) -> Result<String, String> {
+    let fail = |reason: String| format!("{}: {}", path.display(), reason);
+
+    let content = fs::read_to_string(path).map_err(|e| fail(e.to_string()))?;
+    let bytes: Vec<u8> = serde_json::from_str(&content).map_err(|e| fail(e.to_string()))?;
+    if bytes.len() != 64 {
+        return Err(fail("expected a 64-byte keypair".to_string()));
+    }
+    // A keypair file holds the secret key followed by the public key
+    Ok(bs58::encode(&bytes[32..]).into_string())
+}
+
+/// `sol` in lamports.
+pub fn lamports(sol: f64) -> u64 {
+    (sol * LAMPORTS_PER_SOL as f64).round() as u64
+}
+
+/// Balance of `address` in lamports.
+pub fn balance(rpc: &Rpc, address: &str) -> Result<u64, MigrationError> {
+    rpc.call("getBalance", json!([address]))?["value"]
+        .as_u64()
+        .ok_or_else(|| {
+            MigrationError::RpcError(format!("{} getBalance: not a balance", rpc.host()))
+        })
+}
+
+/// Requests `lamports` for `address` and waits until the transfer is
+/// confirmed. Returns the airdrop's transaction signature.
+pub fn airdrop(rpc: &Rpc, address: &str, lamports: u64) -> Result<String, MigrationError> {
+    let signature = rpc.call("requestAirdrop", json!([address, lamports]))?;
+    let signature = signature
+        .as_str()
+        .ok_or_else(|| {
+            MigrationError::RpcError(format!("{} requestAirdrop: not a signature", rpc.host()))
+        })?
+        .to_string();
+
+    let started = Instant::now();
+    while started.elapsed() < CONFIRM_TIMEOUT {
+        let statuses = rpc.call("getSignatureStatuses", json!([[signature]]))?;
+        let status = &statuses["value"][0];
+        if !status["err"].is_null() {
+            return Err(MigrationError::RpcError(format!(
+                "airdrop {} failed: {}",
+                signature, status["err"]
+            )));
+        }
+        if matches!(
+            status["confirmationStatus"].as_str(),
+            Some("confirmed" | "finalized")
+        ) {
+            return Ok(signature);
+        }
+        thread::sleep(Duration::from_millis(500));
+    }
+
+    Err(MigrationError::RpcError(format!(
+        "airdrop {} was not confirmed within {} seconds",
+        signature,
+        CONFIRM_TIMEOUT.as_secs()
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::SigningKey;
+
+    #[test]
+    fn test_wallet_address() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let key = SigningKey::from_bytes(&[7; 32]);
+        fs::write(
+            dir.path().join("id.json"),
+            serde_json::to_string(&key.to_keypair_bytes().to_vec()).unwrap(),
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("Anchor.toml"),
+            "[provider]\ncluster = \"devnet\"\nwallet = \"id.json\"\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            wallet_address(dir.path()).unwrap(),
+            bs58::encode(key.verifying_key().as_bytes()).into_string()
+        );
+    }
+
+    #[test]
+    fn test_lamports() {
+        assert_eq!(lamports(1.0), LAMPORTS_PER_SOL);
+        assert_eq!(lamports(0.5), 500_000_000);
+    }
+}
@@ -0,0 +1,241 @@
+use crate::endpoints::redact_urls;
+use crate::errors::MigrationError;
+use crate::journal::Journal;
+use crate::result::Change;
+use similar::{DiffOp, TextDiff};
+use std::fs;
+use std::path::Path;
+
+/// Why a line of a migrated file looks the way it does, URLs redacted.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Explanation {
+    pub file: String,
+    pub line: usize,
+    pub current: String,
+    /// Lines the current one replaced, from the backup. Empty for lines the
+    /// migration added; `None` when the line was not changed at all.
+    pub original: Option<Vec<String>>,
+    /// Recorded changes that account for the line.
+    pub changes: Vec<Change>,
+}
+
+/// Splits `Anchor.toml:12` into the file and its 1-based line number.
+pub fn parse_location(location: &str) -> Result<(String, usize), MigrationError> {
+    location
+        .rsplit_once(':')
+        .and_then(|(file, line)| Some((file, line.parse::<usize>().ok()?)))
+        .filter(|(file, line)| !file.is_empty() && *line > 0)
+        .map(|(file, line)| (file.trim_start_matches("./").to_string(), line))
+        .ok_or_else(|| {
+            MigrationError::ReadFailed(format!("expected <file>:<line>, got {}", location))
+        })
+}
+
+/// Explains `line` of `file` from the journal: what it was before the
+/// migration and which recorded change produced it.
+pub fn explain(project: &Path, file: &str, line: usize) -> Result<Explanation, MigrationError> {
+    let journal = Journal::load(project)?;
+    let entry = journal.entry(file).ok_or_else(|| {
+        MigrationError::BackupNotFound(format!("no migration recorded for {}", file))
+    })?;
+
+    let current_content = fs::read_to_string(project.join(file))
+        .map_err(|e| MigrationError::ReadFailed(format!("{}: {}", file, e)))?;
+    let backup_content = if entry.created {
+        String::new()
+    } else {
+        fs::read_to_string(project.join(&entry.backup))
+            .map_err(|e| MigrationError::BackupNotFound(format!("{}: {}", entry.backup, e)))?
+    };
+
+    let current_lines: Vec<&str> = current_content.lines().collect();
+    let backup_lines: Vec<&str> = backup_content.lines().collect();
+    let current = current_lines
+        .get(line - 1)
+        .ok_or_else(|| {
+            MigrationError::ReadFailed(format!("{} has only {} lines", file, current_lines.len()))
+        })?
+        .to_string();
+
+    // TOML files are re-serialized, which reorders tables, so their lines are
+    // matched up by key rather than by position
+    let current_keys = toml_keys(&current_lines);
+    let original = match current_keys[line - 1]
+        .as_ref()
+        .filter(|_| file.ends_with(".toml"))
+    {
+        Some(key) => {
+            let backup_keys = toml_keys(&backup_lines);
+            match backup_keys.iter().position(|k| k.as_ref() == Some(key)) {
+                Some(index) if backup_lines[index].trim() == current.trim() => None,
+                Some(index) => Some(vec![backup_lines[index].to_string()]),
+                None => Some(Vec::new()),
+            }
+        }
+        None => {
+            let diff = TextDiff::from_slices(&backup_lines, &current_lines);
+            diff.ops()
+                .iter()
+                .find_map(|op| {
+                    if !op.new_range().contains(&(line - 1)) {
+                        return None;
+                    }
+                    match op {
+                        DiffOp::Equal { .. } => Some(None),
+                        _ => Some(Some(
+                            backup_lines[op.old_range()]
+                                .iter()
+                                .map(|line| line.to_string())
+                                .collect(),
+                        )),
+                    }
+                })
+                .flatten()
+        }
+    };
+
+    let changes = if original.is_some() {
+        responsible_changes(
+            &entry.changes,
+            line,
+            current_keys[line - 1].as_deref(),
+            &current,
+        )
+    } else {
+        Vec::new()
+    };
+
+    // Shown on the terminal, so endpoints lose their keys
+    Ok(Explanation {
+        file: file.to_string(),
+        line,
+        current: redact_urls(&current),
+        original: original.map(|lines| lines.iter().map(|line| redact_urls(line)).collect()),
+        changes: changes
+            .into_iter()
+            .map(|change| Change {
+                detail: redact_urls(&change.detail),
+                ..change
+            })
+            .collect(),
+    })
+}
+
+/// Picks the changes behind a line: line rewrites name their line, TOML edits
+/// name the key, and anything else is matched on the values it introduced.
+fn responsible_changes(
+    changes: &[Change],
+    line: usize,
+    key: Option<&[String]>,
+    current: &str,
+) -> Vec<Change> {
+    let by_line: Vec<Change> = changes
+        .iter()
+        .filter(|change| change.detail.starts_with(&format!("line {}:", line)))
+        .cloned()
+        .collect();
+    if !by_line.is_empty() {
+        return by_line;
+    }
+
+    if let Some(key) = key {
+        // Longest key prefix first, so a remapped program beats the table move
+        for len in (1..=key.len()).rev() {
+            let dotted = key[..len].join(".");
+            let by_key: Vec<Change> = changes
+                .iter()
+                .filter(|change| mentions_key(&change.detail, &dotted))
+                .cloned()
+                .collect();
+            if !by_key.is_empty() {
+                return by_key;
+            }
+        }
+    }
+
+    changes
+        .iter()
+        .filter(|change| {
+            current
+                .split(|c: char| c.is_whitespace() || c == '"' || c == '\'')
+                .any(|token| token.len() >= 8 && change.detail.contains(token))
+        })
+        .cloned()
+        .collect()
+}
+
+/// Whether `detail` names `key` itself, not merely a longer key it prefixes.
+fn mentions_key(detail: &str, key: &str) -> bool {
+    detail.match_indices(key).any(|(start, _)| {
+        let after = detail[start + key.len()..].chars().next();
+        !matches!(after, Some(c) if c == '.' || c == '_' || c.is_alphanumeric())
+    })
+}
+
+/// Key path of every line of a TOML document: the table header itself for
+/// header lines, the enclosing table plus the key for assignments, and `None`
+/// for anything else.
+fn toml_keys(lines: &[&str]) -> Vec<Option<Vec<String>>> {
+    let mut table: Vec<String> = Vec::new();
+
+    lines
+        .iter()
+        .map(|text| {
+            let text = text.trim();
+            if let Some(header) = text.strip_prefix('[') {
+                let header = header.trim_start_matches('[').split(']').next()?;
+                table = split_key(header);
+                Some(table.clone())
+            } else if text.starts_with('#') {
+                None
+            } else {
+                let (key, _) = text.split_once('=')?;
+                let mut path = table.clone();
+                path.extend(split_key(key));
+                Some(path)
+            }
+        })
+        .collect()
+}
+
+fn split_key(key: &str) -> Vec<String> {
+    key.split('.')
+        .map(|part| part.trim().trim_matches('"').to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toml_keys() {
+        let lines = [
+            "[provider]",
+            "cluster = \"x\"",
+            "",
+            "[programs.devnet]",
+            "my_program = \"y\"",
+        ];
+        let keys = toml_keys(&lines);
+        assert_eq!(
+            keys[1],
+            Some(vec!["provider".to_string(), "cluster".to_string()])
+        );
+        assert_eq!(
+            keys[4].as_ref().unwrap().join("."),
+            "programs.devnet.my_program"
+        );
+        assert_eq!(keys[2], None);
+        assert!(mentions_key("[programs.devnet]", "programs.devnet"));
+        assert!(!mentions_key(
+            "programs.devnet.other set to x",
+            "programs.devnet"
+        ));
+        assert_eq!(
+            parse_location("./Anchor.toml:12").unwrap(),
+            ("Anchor.toml".to_string(), 12)
+        );
+        assert!(parse_location("Anchor.toml").is_err());
+    }
+}
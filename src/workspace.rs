@@ -0,0 +1,270 @@
+use crate::ignore::{IgnoreRules, IGNORE_FILE};
+use ignore::WalkBuilder;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Directory holding the Python sources of a Seahorse project.
+pub const SEAHORSE_DIR: &str = "programs_py";
+
+/// A program crate: one under `programs/` in an Anchor workspace, or a
+/// native Solana program anywhere in the project.
+#[derive(Debug, Clone)]
+pub struct ProgramCrate {
+    /// Name as used in Anchor.toml, i.e. the crate name with `-` replaced by `_`.
+    pub name: String,
+    /// Crate directory relative to the project root.
+    pub dir: PathBuf,
+}
+
+impl ProgramCrate {
+    /// Path of the crate's `lib.rs`, relative to the project root.
+    pub fn lib_rs(&self) -> PathBuf {
+        self.dir.join("src").join("lib.rs")
+    }
+
+    /// Path of the Python source Seahorse generates the crate from, relative
+    /// to the project root. Only exists in Seahorse projects.
+    pub fn seahorse_source(&self) -> PathBuf {
+        Path::new(SEAHORSE_DIR).join(format!("{}.py", self.name))
+    }
+}
+
+/// Lists the program crates under `programs/`, sorted by directory.
+/// Directories without a readable Cargo.toml are skipped.
+pub fn program_crates(project: &Path) -> Vec<ProgramCrate> {
+    let rules = IgnoreRules::load(project);
+    let Ok(entries) = fs::read_dir(project.join("programs")) else {
+        return Vec::new();
+    };
+
+    let mut dirs: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.join("Cargo.toml").is_file())
+        .filter(|path| {
+            let relative = path.strip_prefix(project).unwrap_or(path);
+            !rules.is_ignored(&relative.to_string_lossy().replace('\\', "/"))
+        })
+        .collect();
+    dirs.sort();
+
+    dirs.into_iter()
+        .filter_map(|dir| read_program_crate(project, &dir).map(|(program, _)| program))
+        .collect()
+}
+
+/// Crates anywhere in the project that depend on `solana-program` but not on
+/// `anchor-lang`, i.e. native Solana programs, sorted by directory.
+pub fn native_program_crates(project: &Path) -> Vec<ProgramCrate> {
+    find_files(project, &|name| name == "Cargo.toml")
+        .into_iter()
+        .filter_map(|manifest| {
+            let dir = project.join(manifest.trim_end_matches("Cargo.toml"));
+            let (program, manifest) = read_program_crate(project, &dir)?;
+            let dependencies = manifest.get("dependencies")?;
+            (dependencies.get("solana-program").is_some()
+                && dependencies.get("anchor-lang").is_none())
+            .then_some(program)
+        })
+        .collect()
+}
+
+/// The crate in `dir` and its parsed manifest, or `None` without a readable
+/// manifest naming a package.
+fn read_program_crate(project: &Path, dir: &Path) -> Option<(ProgramCrate, toml::Value)> {
+    let manifest: toml::Value = fs::read_to_string(dir.join("Cargo.toml"))
+        .ok()?
+        .parse()
+        .ok()?;
+    let name = manifest
+        .get("lib")
+        .and_then(|lib| lib.get("name"))
+        .or_else(|| manifest.get("package").and_then(|p| p.get("name")))
+        .and_then(|name| name.as_str())?
+        .replace('-', "_");
+    let dir = dir.strip_prefix(project).ok()?.to_path_buf();
+    Some((ProgramCrate { name, dir }, manifest))
+}
+
+/// Directories never worth descending into when searching the project.
+/// Directory in the project root where the tool keeps its own state.
+pub const STATE_DIR: &str = ".soon-migrate";
+
+const SKIPPED_DIRS: &[&str] = &[".git", STATE_DIR, "node_modules", "target"];
+
+/// Files anywhere in the project whose name is `wanted`, relative to the
+/// project root and sorted. Build output, dependencies, VCS data and paths
+/// in `.gitignore` or `.soonmigrateignore` files are skipped. Symlinks are
+/// not followed, so a link cycle cannot trap the walk.
+pub fn find_files(project: &Path, wanted: &dyn Fn(&str) -> bool) -> Vec<String> {
+    let walker = WalkBuilder::new(project)
+        // Dotfiles such as `.env.example` and `.github` are project files
+        .hidden(false)
+        // Only the project's own ignore files count, not the machine's
+        .parents(false)
+        .ignore(false)
+        .git_global(false)
+        .require_git(false)
+        .add_custom_ignore_filename(IGNORE_FILE)
+        .filter_entry(|entry| {
+            let is_dir = entry.file_type().is_some_and(|kind| kind.is_dir());
+            !(is_dir
+                && entry.depth() > 0
+                && entry
+                    .file_name()
+                    .to_str()
+                    .is_some_and(|name| SKIPPED_DIRS.contains(&name)))
+        })
+        .build();
+
+    let mut files: Vec<String> = walker
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_some_and(|kind| kind.is_file()))
+        .filter(|entry| entry.file_name().to_str().is_some_and(wanted))
+        .filter_map(|entry| {
+            let relative = entry.path().strip_prefix(project).ok()?;
+            Some(relative.to_string_lossy().replace('\\', "/"))
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// Finds `declare_id!("...")` in program source and returns its 1-based line
+/// number and the declared ID.
+pub fn find_declare_id(source: &str) -> Option<(usize, String)> {
+    source.lines().enumerate().find_map(|(index, line)| {
+        let code = line.split("//").next().unwrap_or(line);
+        let rest = code.split_once("declare_id!")?.1;
+        let quoted = rest.split_once('"')?.1;
+        let id = quoted.split_once('"')?.0;
+        Some((index + 1, id.to_string()))
+    })
+}
+
+/// Solang (Solidity) programs anywhere in the project, as the program name
+/// Anchor gives them (the file stem) and the file, sorted by file.
+pub fn solang_programs(project: &Path) -> Vec<(String, String)> {
+    find_files(project, &|name| name.ends_with(".sol"))
+        .into_iter()
+        .filter_map(|file| {
+            let stem = Path::new(&file).file_stem()?.to_string_lossy().into_owned();
+            Some((stem, file))
+        })
+        .collect()
+}
+
+/// Line and ID of the `@program_id("...")` annotation in a Solang contract.
+pub fn find_solang_program_id(source: &str) -> Option<(usize, String)> {
+    source.lines().enumerate().find_map(|(index, line)| {
+        let code = line.split("//").next().unwrap_or(line);
+        let rest = code.split_once("@program_id(")?.1;
+        let quoted = rest.split_once('"')?.1;
+        let id = quoted.split_once('"')?.0;
+        Some((index + 1, id.to_string()))
+    })
+}
+
+/// Directories under `root` holding an Anchor.toml, relative to `root` and
+/// sorted; `.` is `root` itself.
+pub fn anchor_projects(root: &Path) -> Vec<String> {
+    find_files(root, &|name| name == "Anchor.toml")
+        .into_iter()
+        .map(|file| match file.strip_suffix("/Anchor.toml") {
+            Some(dir) => dir.to_string(),
+            None => ".".to_string(),
+        })
+        .collect()
+}
+
+/// Line and ID of the `declare_id('...')` call in a Seahorse program.
+pub fn find_seahorse_declare_id(source: &str) -> Option<(usize, String)> {
+    source.lines().enumerate().find_map(|(index, line)| {
+        let code = line.split('#').next().unwrap_or(line);
+        let rest = code.split_once("declare_id(")?.1.trim_start();
+        let quote = rest.chars().next().filter(|c| *c == '\'' || *c == '"')?;
+        let id = rest[1..].split_once(quote)?.0;
+        Some((index + 1, id.to_string()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_declare_id() {
+        let source = "use anchor_lang::prelude::*;\n\
+                      // declare_id!(\"Commented1111111111111111111111111111111111\");\n\
+                      declare_id!(\"Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS\");\n";
+
+        assert_eq!(
+            find_declare_id(source),
+            Some((
+                3,
+                "Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS".to_string()
+            ))
+        );
+        assert_eq!(find_declare_id("fn main() {}"), None);
+    }
+
+    #[test]
+    fn test_find_seahorse_declare_id() {
+        let source = "from seahorse.prelude import *\n\n# declare_id('Commented')\ndeclare_id('Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS')\n";
+
+        assert_eq!(
+            find_seahorse_declare_id(source),
+            Some((
+                4,
+                "Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_find_solang_program_id() {
+        let source = "@program_id(\"Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS\")\ncontract counter {\n}\n";
+
+        assert_eq!(
+            find_solang_program_id(source),
+            Some((
+                1,
+                "Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS".to_string()
+            ))
+        );
+        assert_eq!(find_solang_program_id("contract counter {}"), None);
+    }
+
+    #[test]
+    fn test_anchor_projects() {
+        let dir = tempfile::TempDir::new().unwrap();
+        for project in ["", "apps/vault", "apps/staking", "node_modules/pkg"] {
+            fs::create_dir_all(dir.path().join(project)).unwrap();
+            fs::write(dir.path().join(project).join("Anchor.toml"), "").unwrap();
+        }
+
+        assert_eq!(
+            anchor_projects(dir.path()),
+            vec![".", "apps/staking", "apps/vault"]
+        );
+    }
+
+    #[test]
+    fn test_find_files_honors_ignore_files() {
+        let dir = tempfile::TempDir::new().unwrap();
+        for sub in ["app/dist", "app/generated", "app/src", "vendored", "target"] {
+            fs::create_dir_all(dir.path().join(sub)).unwrap();
+            fs::write(dir.path().join(sub).join("client.ts"), "").unwrap();
+        }
+        fs::write(dir.path().join(".gitignore"), "dist/\n").unwrap();
+        fs::write(dir.path().join("app").join(".gitignore"), "/generated\n").unwrap();
+        fs::write(dir.path().join(IGNORE_FILE), "vendored\n").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(dir.path(), dir.path().join("app").join("loop")).unwrap();
+
+        assert_eq!(
+            find_files(dir.path(), &|name| name.ends_with(".ts")),
+            vec!["app/src/client.ts"]
+        );
+    }
+}